@@ -0,0 +1,537 @@
+//! Command-line tool for setup, proving and verification of rewards proofs
+//!
+//! Every command prints its results as JSON to the standard output, one
+//! object per line. Errors are printed as `{"error": ...}` to the standard
+//! error. The exit code is 0 on success, 1 if a proof does not verify and 2
+//! for invalid inputs or I/O errors.
+
+use bulletproofs::{BulletproofGens, PedersenGens};
+use clap::{Parser, Subcommand, ValueEnum};
+use rewards_proof::api::{
+    reward, rewards_proof_generation_aggregated, rewards_proof_setup, to_scalars, RewardsProof,
+};
+use rewards_proof::policy::{now, IssuerPublicKey, IssuerSigningKey, Policy, SignedPolicy};
+use rewards_proof::stream::{read_rewards_proof, write_rewards_proof};
+use rewards_proof::verifier::{PreparedPolicy, PreparedVerifier};
+use rewards_proof::wire::{SetupParameters, WireFormat};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Exit code if a proof does not verify
+const EXIT_INVALID: i32 = 1;
+/// Exit code for invalid inputs or I/O errors
+const EXIT_ERROR: i32 = 2;
+
+#[derive(Parser)]
+#[command(
+    name = "rewards-proof",
+    version,
+    about = "Setup, proving and verification of rewards proofs"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the setup for an incentive catalog and write the parameters file
+    Setup {
+        /// Size of the incentive catalog
+        #[arg(long)]
+        incentive_catalog_size: u64,
+        /// Parameters file
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Create issuer keys and policy manifests
+    Policy {
+        #[command(subcommand)]
+        command: PolicyCommand,
+    },
+    /// Generate a rewards proof for a state and write the proof file
+    Prove {
+        #[command(flatten)]
+        inputs: Inputs,
+        /// State file, a JSON array with the count of every incentive
+        #[arg(long)]
+        state: PathBuf,
+        /// Proof file
+        #[arg(long, short)]
+        output: PathBuf,
+        /// Format of the proof file
+        #[arg(long, value_enum, default_value_t = ProofFormat::Wire)]
+        format: ProofFormat,
+    },
+    /// Verify a single proof file in the wire format
+    Verify {
+        #[command(flatten)]
+        inputs: Inputs,
+        /// Proof file
+        #[arg(long)]
+        proof: PathBuf,
+    },
+    /// Verify a directory of proof files in the wire format, or a stream of
+    /// proof records
+    VerifyBatch {
+        #[command(flatten)]
+        inputs: Inputs,
+        /// Directory of proof files
+        #[arg(long, conflicts_with = "stream", required_unless_present = "stream")]
+        dir: Option<PathBuf>,
+        /// Stream of proof records, `-` for the standard input
+        #[arg(long)]
+        stream: Option<PathBuf>,
+        /// Number of proofs verified at once
+        #[arg(long, default_value_t = 64)]
+        chunk_size: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum PolicyCommand {
+    /// Generate an issuer key pair and write the key file
+    Keygen {
+        /// Key file
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Create a policy manifest, signed if a key file is given
+    Create {
+        /// ID of the policy
+        #[arg(long)]
+        id: u64,
+        /// Version of the policy
+        #[arg(long)]
+        version: u32,
+        /// Comma-separated weight of every incentive of the catalog
+        #[arg(long, value_delimiter = ',', required = true)]
+        weights: Vec<u64>,
+        /// Start of the validity window (unix timestamp in seconds)
+        #[arg(long, default_value_t = 0)]
+        valid_from: u64,
+        /// End of the validity window (exclusive)
+        #[arg(long, default_value_t = u64::MAX)]
+        valid_until: u64,
+        /// Key file of the issuer
+        #[arg(long)]
+        signing_key: Option<PathBuf>,
+        /// Policy file
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+}
+
+/// Parameters and policy of proving and verification
+#[derive(clap::Args)]
+struct Inputs {
+    /// Parameters file written by `setup`
+    #[arg(long)]
+    parameters: PathBuf,
+    /// Policy file written by `policy create`
+    #[arg(long)]
+    policy: PathBuf,
+    /// Hex encoded public key of the issuer, required for signed policies
+    #[arg(long)]
+    issuer_key: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ProofFormat {
+    /// Canonical wire format of a single proof
+    Wire,
+    /// Length-prefixed record of a proof stream
+    Stream,
+}
+
+/// Key file of an issuer
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    signing_key: String,
+    public_key: String,
+}
+
+/// Setup parameters loaded from a parameters file
+struct Parameters {
+    pedersen_gens: Vec<PedersenGens>,
+    bulletproof_gens: Vec<BulletproofGens>,
+    wire_format: WireFormat,
+    incentive_catalog_size: u64,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Setup {
+            incentive_catalog_size,
+            output,
+        } => setup(incentive_catalog_size, &output),
+        Command::Policy {
+            command: PolicyCommand::Keygen { output },
+        } => keygen(&output),
+        Command::Policy {
+            command:
+                PolicyCommand::Create {
+                    id,
+                    version,
+                    weights,
+                    valid_from,
+                    valid_until,
+                    signing_key,
+                    output,
+                },
+        } => create_policy(
+            id,
+            version,
+            weights,
+            (valid_from, valid_until),
+            signing_key.as_deref(),
+            &output,
+        ),
+        Command::Prove {
+            inputs,
+            state,
+            output,
+            format,
+        } => prove(&inputs, &state, &output, format),
+        Command::Verify { inputs, proof } => verify(&inputs, &proof),
+        Command::VerifyBatch {
+            inputs,
+            dir,
+            stream,
+            chunk_size,
+        } => verify_batch(&inputs, dir.as_deref(), stream.as_deref(), chunk_size),
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(EXIT_INVALID),
+        Err(error) => {
+            eprintln!("{}", json!({ "error": error }));
+            process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+/// Runs the setup and writes the parameters file
+fn setup(incentive_catalog_size: u64, output: &Path) -> Result<bool, String> {
+    if incentive_catalog_size == 0 {
+        return Err("Incentive catalog must not be empty".to_string());
+    }
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(incentive_catalog_size);
+    // the parameters file only holds the size of the catalog and the
+    // fingerprint, the generators are derived again when it is loaded
+    let parameters =
+        SetupParameters::new(&pedersen_gens, &bulletproof_gens, incentive_catalog_size);
+    write_json(output, &parameters)?;
+    print(json!({
+        "parameters": output.display().to_string(),
+        "incentive_catalog_size": incentive_catalog_size,
+        "fingerprint": hex::encode(parameters.fingerprint()),
+    }))?;
+    Ok(true)
+}
+
+/// Generates an issuer key pair and writes the key file
+fn keygen(output: &Path) -> Result<bool, String> {
+    let signing_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let key_file = KeyFile {
+        signing_key: hex::encode(signing_key.to_bytes()),
+        public_key: hex::encode(signing_key.public_key().to_bytes()),
+    };
+    write_json(output, &key_file)?;
+    print(json!({ "key": output.display().to_string(), "public_key": key_file.public_key }))?;
+    Ok(true)
+}
+
+/// Creates a policy manifest and writes the policy file
+fn create_policy(
+    id: u64,
+    version: u32,
+    weights: Vec<u64>,
+    (valid_from, valid_until): (u64, u64),
+    signing_key: Option<&Path>,
+    output: &Path,
+) -> Result<bool, String> {
+    let policy = Policy::new(id, version, weights, valid_from, valid_until)
+        .ok_or("Validity window of the policy is empty")?;
+    let signed = match signing_key {
+        Some(path) => {
+            let key_file: KeyFile = read_json(path)?;
+            let signing_key = hex::decode(&key_file.signing_key)
+                .ok()
+                .and_then(|bytes| IssuerSigningKey::from_bytes(&bytes))
+                .ok_or("Invalid signing key")?;
+            write_json(output, &signing_key.sign(&policy))?;
+            true
+        }
+        None => {
+            write_json(output, &policy)?;
+            false
+        }
+    };
+    print(json!({
+        "policy": output.display().to_string(),
+        "id": id,
+        "version": version,
+        "signed": signed,
+    }))?;
+    Ok(true)
+}
+
+/// Generates a rewards proof and writes the proof file
+fn prove(
+    inputs: &Inputs,
+    state: &Path,
+    output: &Path,
+    format: ProofFormat,
+) -> Result<bool, String> {
+    let parameters = load_parameters(&inputs.parameters)?;
+    let policy = load_policy(inputs, &parameters)?;
+    let state: Vec<u64> = read_json(state)?;
+    if state.len() as u64 != parameters.incentive_catalog_size {
+        return Err("State does not match the incentive catalog".to_string());
+    }
+    let reward = reward(&state, policy.weights())
+        .ok_or("Count exceeds the maximum state entry or reward is too large")?;
+
+    let proof = rewards_proof_generation_aggregated(
+        &parameters.pedersen_gens,
+        &parameters.bulletproof_gens,
+        &[reward],
+        to_scalars(&state),
+        policy.policy_vector(),
+        parameters.incentive_catalog_size,
+        None,
+        None,
+    )
+    .ok_or("State is not valid")?;
+    let bytes = match format {
+        ProofFormat::Wire => parameters
+            .wire_format
+            .encode(&proof)
+            .ok_or("Proof was not generated with the setup")?,
+        ProofFormat::Stream => {
+            let mut record = Vec::new();
+            write_rewards_proof(&mut record, &proof).map_err(|e| e.to_string())?;
+            record
+        }
+    };
+    fs::write(output, &bytes).map_err(|e| format!("{}: {}", output.display(), e))?;
+    print(json!({ "proof": output.display().to_string(), "size": bytes.len() }))?;
+    Ok(true)
+}
+
+/// Verifies a single proof file
+fn verify(inputs: &Inputs, proof: &Path) -> Result<bool, String> {
+    let parameters = load_parameters(&inputs.parameters)?;
+    let policy = load_policy(inputs, &parameters)?;
+    let bytes = fs::read(proof).map_err(|e| format!("{}: {}", proof.display(), e))?;
+
+    let valid = match parameters.wire_format.decode(&bytes) {
+        Some(rewards_proof) => {
+            parameters
+                .verifier()
+                .verify(&rewards_proof, &policy.policy_vector(), None, None)
+        }
+        None => false,
+    };
+    print(json!({ "proof": proof.display().to_string(), "valid": valid }))?;
+    Ok(valid)
+}
+
+/// Verifies a directory or stream of proofs in chunks against the same
+/// prepared policy
+fn verify_batch(
+    inputs: &Inputs,
+    dir: Option<&Path>,
+    stream: Option<&Path>,
+    chunk_size: usize,
+) -> Result<bool, String> {
+    if chunk_size == 0 {
+        return Err("Chunk size must not be zero".to_string());
+    }
+    let parameters = load_parameters(&inputs.parameters)?;
+    let policy = load_policy(inputs, &parameters)?;
+    let verifier = parameters.verifier();
+    let prepared_policy = verifier
+        .prepare_policy(&policy.policy_vector())
+        .ok_or("Policy does not match the incentive catalog")?;
+
+    let mut batch = Batch {
+        verifier: &verifier,
+        policy: &prepared_policy,
+        chunk: Vec::with_capacity(chunk_size),
+        chunk_size,
+        valid: 0,
+        invalid: 0,
+    };
+    match (dir, stream) {
+        (Some(dir), _) => {
+            let mut paths = fs::read_dir(dir)
+                .and_then(|entries| {
+                    entries
+                        .map(|entry| entry.map(|entry| entry.path()))
+                        .collect::<io::Result<Vec<PathBuf>>>()
+                })
+                .map_err(|e| format!("{}: {}", dir.display(), e))?;
+            paths.retain(|path| path.is_file());
+            paths.sort();
+            for path in paths {
+                let bytes = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                let proof = parameters.wire_format.decode(&bytes);
+                batch.push(json!({ "proof": path.display().to_string() }), proof)?;
+            }
+        }
+        (None, Some(stream)) => {
+            let mut reader: BufReader<Box<dyn Read>> = if stream == Path::new("-") {
+                BufReader::new(Box::new(io::stdin()))
+            } else {
+                let file =
+                    fs::File::open(stream).map_err(|e| format!("{}: {}", stream.display(), e))?;
+                BufReader::new(Box::new(file))
+            };
+            let mut index: u64 = 0;
+            while let Some(proof) = read_rewards_proof(&mut reader)
+                .map_err(|e| format!("{}: {}", stream.display(), e))?
+            {
+                batch.push(json!({ "index": index }), proof)?;
+                index += 1;
+            }
+        }
+        (None, None) => unreachable!("clap requires --dir or --stream"),
+    }
+    batch.flush()?;
+
+    print(json!({ "valid": batch.valid, "invalid": batch.invalid }))?;
+    Ok(batch.invalid == 0)
+}
+
+/// Chunk of proofs of `verify-batch`, the results are printed when the chunk
+/// is full
+struct Batch<'a> {
+    verifier: &'a PreparedVerifier,
+    policy: &'a PreparedPolicy,
+    chunk: Vec<(Value, Option<RewardsProof>)>,
+    chunk_size: usize,
+    valid: u64,
+    invalid: u64,
+}
+
+impl<'a> Batch<'a> {
+    /// Adds a proof, `None` for proofs that cannot be decoded
+    fn push(&mut self, label: Value, proof: Option<RewardsProof>) -> Result<(), String> {
+        self.chunk.push((label, proof));
+        if self.chunk.len() == self.chunk_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Verifies the proofs of the chunk and prints the results
+    fn flush(&mut self) -> Result<(), String> {
+        let proofs: Vec<Option<&RewardsProof>> =
+            self.chunk.iter().map(|(_, proof)| proof.as_ref()).collect();
+        let results = self
+            .verifier
+            .verify_decoded_batch_with_policy(&proofs, self.policy);
+
+        let stdout = io::stdout();
+        let mut out = BufWriter::new(stdout.lock());
+        for ((mut label, _), valid) in self.chunk.drain(..).zip(results) {
+            if valid {
+                self.valid += 1;
+            } else {
+                self.invalid += 1;
+            }
+            label["valid"] = json!(valid);
+            writeln!(out, "{}", label).map_err(|e| e.to_string())?;
+        }
+        out.flush().map_err(|e| e.to_string())
+    }
+}
+
+impl Parameters {
+    /// Prepares a verifier for the parameters
+    fn verifier(&self) -> PreparedVerifier {
+        PreparedVerifier::new(
+            &self.pedersen_gens,
+            &self.bulletproof_gens,
+            self.incentive_catalog_size,
+        )
+    }
+}
+
+/// Loads a parameters file and checks the fingerprint of the setup
+fn load_parameters(path: &Path) -> Result<Parameters, String> {
+    let file: SetupParameters = read_json(path)?;
+    let incentive_catalog_size = file.incentive_catalog_size();
+    if incentive_catalog_size == 0 {
+        return Err("Incentive catalog must not be empty".to_string());
+    }
+    let (pedersen_gens, bulletproof_gens) =
+        file.setup().ok_or("Fingerprint does not match the setup")?;
+    let wire_format = WireFormat::new(&pedersen_gens, &bulletproof_gens, incentive_catalog_size);
+    Ok(Parameters {
+        pedersen_gens,
+        bulletproof_gens,
+        wire_format,
+        incentive_catalog_size,
+    })
+}
+
+/// Loads a policy file, signed policies are verified with the public key of
+/// the issuer and the current time
+fn load_policy(inputs: &Inputs, parameters: &Parameters) -> Result<Policy, String> {
+    let value: Value = read_json(&inputs.policy)?;
+    let issuer_key = match &inputs.issuer_key {
+        Some(key) => Some(
+            hex::decode(key)
+                .ok()
+                .and_then(|bytes| IssuerPublicKey::from_bytes(&bytes))
+                .ok_or("Invalid issuer key")?,
+        ),
+        None => None,
+    };
+
+    let policy = match (value.get("signature").is_some(), issuer_key) {
+        (true, Some(issuer_key)) => {
+            let signed_policy: SignedPolicy =
+                serde_json::from_value(value).map_err(|e| e.to_string())?;
+            if !signed_policy.verify(&issuer_key, now()) {
+                return Err("Signature or validity window of the policy is not valid".to_string());
+            }
+            signed_policy.policy().clone()
+        }
+        (true, None) => return Err("Signed policies require --issuer-key".to_string()),
+        (false, Some(_)) => return Err("Policy is not signed".to_string()),
+        (false, None) => serde_json::from_value(value).map_err(|e| e.to_string())?,
+    };
+    if policy.incentive_catalog_size() != parameters.incentive_catalog_size {
+        return Err("Policy does not match the incentive catalog".to_string());
+    }
+    Ok(policy)
+}
+
+/// Reads a JSON file
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Writes a JSON file
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    fs::write(path, json + "\n").map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Prints a result line
+fn print(value: Value) -> Result<(), String> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    writeln!(out, "{}", value).map_err(|e| e.to_string())
+}
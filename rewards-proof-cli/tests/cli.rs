@@ -0,0 +1,355 @@
+//! Integration tests of the `rewards-proof` binary, checking the JSON output
+//! and the exit codes of every command
+
+use assert_cmd::Command;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Exit code if a proof does not verify
+const EXIT_INVALID: i32 = 1;
+/// Exit code for invalid inputs or I/O errors
+const EXIT_ERROR: i32 = 2;
+
+/// Runs the binary in `dir` and returns the exit code and the JSON lines of
+/// the standard output and the standard error
+fn run(dir: &Path, args: &[&str]) -> (i32, Vec<Value>, Vec<Value>) {
+    let output = Command::cargo_bin("rewards-proof")
+        .unwrap()
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap(),
+        json_lines(&output.stdout),
+        json_lines(&output.stderr),
+    )
+}
+
+/// Parses every line as JSON
+fn json_lines(bytes: &[u8]) -> Vec<Value> {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+/// Creates the parameters, an issuer key, a signed policy with the weights
+/// `[2, 5, 1, 0]` and a state file, returns the public key of the issuer
+fn fixtures(dir: &Path) -> String {
+    let (code, _, _) = run(
+        dir,
+        &[
+            "setup",
+            "--incentive-catalog-size",
+            "4",
+            "-o",
+            "params.json",
+        ],
+    );
+    assert_eq!(code, 0);
+    let (code, stdout, _) = run(dir, &["policy", "keygen", "-o", "issuer.json"]);
+    assert_eq!(code, 0);
+    let public_key = stdout[0]["public_key"].as_str().unwrap().to_string();
+    let (code, _, _) = run(
+        dir,
+        &[
+            "policy",
+            "create",
+            "--id",
+            "1",
+            "--version",
+            "1",
+            "--weights",
+            "2,5,1,0",
+            "--signing-key",
+            "issuer.json",
+            "-o",
+            "policy.json",
+        ],
+    );
+    assert_eq!(code, 0);
+    fs::write(dir.join("state.json"), "[1, 0, 3, 2]").unwrap();
+    public_key
+}
+
+/// Arguments of the parameters and the signed policy
+fn inputs<'a>(public_key: &'a str, policy: &'a str) -> Vec<&'a str> {
+    vec![
+        "--parameters",
+        "params.json",
+        "--policy",
+        policy,
+        "--issuer-key",
+        public_key,
+    ]
+}
+
+/// Proves the state of the fixtures and writes the proof to `output`
+fn prove(
+    dir: &Path,
+    public_key: &str,
+    output: &str,
+    format: &str,
+) -> (i32, Vec<Value>, Vec<Value>) {
+    let mut args = vec!["prove"];
+    args.extend(inputs(public_key, "policy.json"));
+    args.extend(&["--state", "state.json", "-o", output, "--format", format]);
+    run(dir, &args)
+}
+
+#[test]
+fn setup() {
+    let dir = TempDir::new().unwrap();
+    let (code, stdout, _) = run(
+        dir.path(),
+        &[
+            "setup",
+            "--incentive-catalog-size",
+            "4",
+            "-o",
+            "params.json",
+        ],
+    );
+    assert_eq!(code, 0);
+    assert_eq!(stdout[0]["incentive_catalog_size"], 4);
+    assert_eq!(stdout[0]["fingerprint"].as_str().unwrap().len(), 64);
+    let parameters: Value =
+        serde_json::from_slice(&fs::read(dir.path().join("params.json")).unwrap()).unwrap();
+    assert_eq!(parameters["fingerprint"], stdout[0]["fingerprint"]);
+
+    let (code, stdout, stderr) = run(
+        dir.path(),
+        &[
+            "setup",
+            "--incentive-catalog-size",
+            "0",
+            "-o",
+            "params.json",
+        ],
+    );
+    assert_eq!(code, EXIT_ERROR);
+    assert!(stdout.is_empty());
+    assert!(stderr[0]["error"].is_string());
+}
+
+#[test]
+fn policy() {
+    let dir = TempDir::new().unwrap();
+    let public_key = fixtures(dir.path());
+    assert_eq!(public_key.len(), 64);
+
+    let (code, stdout, _) = run(
+        dir.path(),
+        &[
+            "policy",
+            "create",
+            "--id",
+            "2",
+            "--version",
+            "1",
+            "--weights",
+            "1,1,1,1",
+            "-o",
+            "unsigned.json",
+        ],
+    );
+    assert_eq!(code, 0);
+    assert_eq!(stdout[0]["signed"], false);
+
+    let (code, _, stderr) = run(
+        dir.path(),
+        &[
+            "policy",
+            "create",
+            "--id",
+            "2",
+            "--version",
+            "1",
+            "--weights",
+            "1,1,1,1",
+            "--valid-from",
+            "2",
+            "--valid-until",
+            "1",
+            "-o",
+            "invalid.json",
+        ],
+    );
+    assert_eq!(code, EXIT_ERROR);
+    assert!(stderr[0]["error"].is_string());
+}
+
+#[test]
+fn prove_and_verify() {
+    let dir = TempDir::new().unwrap();
+    let public_key = fixtures(dir.path());
+    let (code, stdout, _) = prove(dir.path(), &public_key, "proof.bin", "wire");
+    assert_eq!(code, 0);
+    assert_eq!(stdout[0]["proof"], "proof.bin");
+
+    let mut args = vec!["verify"];
+    args.extend(inputs(&public_key, "policy.json"));
+    args.extend(&["--proof", "proof.bin"]);
+    let (code, stdout, _) = run(dir.path(), &args);
+    assert_eq!(code, 0);
+    assert_eq!(stdout[0]["valid"], true);
+
+    // a proof for another policy does not verify
+    let (code, _, _) = run(
+        dir.path(),
+        &[
+            "policy",
+            "create",
+            "--id",
+            "1",
+            "--version",
+            "2",
+            "--weights",
+            "2,5,1,1",
+            "-o",
+            "other.json",
+        ],
+    );
+    assert_eq!(code, 0);
+    let mut args = vec![
+        "verify",
+        "--parameters",
+        "params.json",
+        "--policy",
+        "other.json",
+    ];
+    args.extend(&["--proof", "proof.bin"]);
+    let (code, stdout, _) = run(dir.path(), &args);
+    assert_eq!(code, EXIT_INVALID);
+    assert_eq!(stdout[0]["valid"], false);
+}
+
+#[test]
+fn invalid_inputs() {
+    let dir = TempDir::new().unwrap();
+    let public_key = fixtures(dir.path());
+
+    // a signed policy requires the key of the issuer
+    let args = [
+        "prove",
+        "--parameters",
+        "params.json",
+        "--policy",
+        "policy.json",
+        "--state",
+        "state.json",
+        "-o",
+        "proof.bin",
+    ];
+    let (code, _, stderr) = run(dir.path(), &args);
+    assert_eq!(code, EXIT_ERROR);
+    assert!(stderr[0]["error"].is_string());
+
+    // counts must fit into STATE_ENTRY_BITSIZE bits
+    fs::write(dir.path().join("state.json"), "[1, 0, 3, 65536]").unwrap();
+    let (code, _, stderr) = prove(dir.path(), &public_key, "proof.bin", "wire");
+    assert_eq!(code, EXIT_ERROR);
+    assert!(stderr[0]["error"].is_string());
+
+    // the reward must be smaller than 2^REWARD_BITSIZE
+    fs::write(dir.path().join("state.json"), "[1, 0, 3, 2]").unwrap();
+    let (code, _, _) = run(
+        dir.path(),
+        &[
+            "policy",
+            "create",
+            "--id",
+            "1",
+            "--version",
+            "1",
+            "--weights",
+            "65536,0,0,0",
+            "--signing-key",
+            "issuer.json",
+            "-o",
+            "policy.json",
+        ],
+    );
+    assert_eq!(code, 0);
+    let (code, _, stderr) = prove(dir.path(), &public_key, "proof.bin", "wire");
+    assert_eq!(code, EXIT_ERROR);
+    assert!(stderr[0]["error"].is_string());
+
+    // missing files are reported as errors
+    let mut args = vec!["verify"];
+    args.extend(inputs(&public_key, "missing.json"));
+    args.extend(&["--proof", "proof.bin"]);
+    let (code, _, stderr) = run(dir.path(), &args);
+    assert_eq!(code, EXIT_ERROR);
+    assert!(stderr[0]["error"].is_string());
+}
+
+#[test]
+fn verify_batch_dir() {
+    let dir = TempDir::new().unwrap();
+    let public_key = fixtures(dir.path());
+    fs::create_dir(dir.path().join("proofs")).unwrap();
+    for name in ["proofs/0.bin", "proofs/1.bin"].iter() {
+        let (code, _, _) = prove(dir.path(), &public_key, name, "wire");
+        assert_eq!(code, 0);
+    }
+    fs::write(dir.path().join("proofs/2.bin"), b"not a proof").unwrap();
+
+    let mut args = vec!["verify-batch"];
+    args.extend(inputs(&public_key, "policy.json"));
+    args.extend(&["--dir", "proofs", "--chunk-size", "2"]);
+    let (code, stdout, _) = run(dir.path(), &args);
+    assert_eq!(code, EXIT_INVALID);
+    assert_eq!(stdout.len(), 4);
+    assert_eq!(stdout[0]["proof"], "proofs/0.bin");
+    let results: Vec<&Value> = stdout[..3].iter().map(|line| &line["valid"]).collect();
+    assert_eq!(results, [true, true, false]);
+    assert_eq!(stdout[3]["valid"], 2);
+    assert_eq!(stdout[3]["invalid"], 1);
+
+    fs::remove_file(dir.path().join("proofs/2.bin")).unwrap();
+    let (code, stdout, _) = run(dir.path(), &args);
+    assert_eq!(code, 0);
+    assert_eq!(stdout[2]["valid"], 2);
+}
+
+#[test]
+fn verify_batch_stream() {
+    let dir = TempDir::new().unwrap();
+    let public_key = fixtures(dir.path());
+    let mut stream = Vec::new();
+    for _ in 0..3 {
+        let (code, _, _) = prove(dir.path(), &public_key, "record.bin", "stream");
+        assert_eq!(code, 0);
+        stream.extend(fs::read(dir.path().join("record.bin")).unwrap());
+    }
+    fs::write(dir.path().join("proofs.stream"), &stream).unwrap();
+
+    let mut args = vec!["verify-batch"];
+    args.extend(inputs(&public_key, "policy.json"));
+    args.extend(&["--stream", "proofs.stream"]);
+    let (code, stdout, _) = run(dir.path(), &args);
+    assert_eq!(code, 0);
+    assert_eq!(stdout.len(), 4);
+    assert_eq!(stdout[0]["index"], 0);
+    assert_eq!(stdout[3]["valid"], 3);
+
+    // a truncated record is an error
+    fs::write(
+        dir.path().join("proofs.stream"),
+        &stream[..stream.len() - 1],
+    )
+    .unwrap();
+    let (code, _, stderr) = run(dir.path(), &args);
+    assert_eq!(code, EXIT_ERROR);
+    assert!(stderr[0]["error"].is_string());
+
+    let mut args = vec!["verify-batch"];
+    args.extend(inputs(&public_key, "policy.json"));
+    args.extend(&["--stream", "proofs.stream", "--chunk-size", "0"]);
+    let (code, _, _) = run(dir.path(), &args);
+    assert_eq!(code, EXIT_ERROR);
+}
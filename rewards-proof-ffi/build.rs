@@ -0,0 +1,11 @@
+fn main() {
+    // Generate the C header of the FFI layer
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir))
+        .expect("Invalid cbindgen.toml");
+    cbindgen::generate_with_config(&crate_dir, config)
+        .expect("Failed to generate the C header")
+        .write_to_file(format!("{}/include/rewards_proof.h", crate_dir));
+}
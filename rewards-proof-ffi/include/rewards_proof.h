@@ -0,0 +1,191 @@
+#ifndef REWARDS_PROOF_H
+#define REWARDS_PROOF_H
+
+/* Generated by cbindgen from rewards-proof-ffi/src/lib.rs, do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Size of the fingerprint of the parameters
+ */
+#define REWARDS_PROOF_FINGERPRINT_SIZE 32
+
+/**
+ * Size of the public key of an issuer
+ */
+#define REWARDS_PROOF_ISSUER_KEY_SIZE 32
+
+/**
+ * Result of the FFI functions
+ *
+ * The Rust API reports failures as `None` or `false`, the error codes
+ * distinguish the inputs that caused them.
+ */
+typedef enum RewardsProofError {
+  /**
+   * Success, or a proof that verified
+   */
+  REWARDS_PROOF_ERROR_OK = 0,
+  /**
+   * A required pointer is null
+   */
+  REWARDS_PROOF_ERROR_NULL_POINTER = 1,
+  /**
+   * The catalog size is zero or the fingerprint does not match the setup
+   */
+  REWARDS_PROOF_ERROR_INVALID_PARAMETERS = 2,
+  /**
+   * The state does not match the catalog, a count is out of range or the
+   * reward does not fit into `REWARD_BITSIZE` bits
+   */
+  REWARDS_PROOF_ERROR_INVALID_STATE = 3,
+  /**
+   * The policy does not match the catalog, cannot be decoded, or its
+   * signature or validity window is not valid
+   */
+  REWARDS_PROOF_ERROR_INVALID_POLICY = 4,
+  /**
+   * The proof cannot be decoded or was created for other parameters
+   */
+  REWARDS_PROOF_ERROR_INVALID_PROOF = 5,
+  /**
+   * The proof does not verify
+   */
+  REWARDS_PROOF_ERROR_VERIFICATION_FAILED = 6,
+  /**
+   * A panic was caught at the FFI boundary
+   */
+  REWARDS_PROOF_ERROR_PANIC = 7,
+} RewardsProofError;
+
+/**
+ * Parameters of an incentive catalog, an opaque handle
+ */
+typedef struct RewardsProofParameters RewardsProofParameters;
+
+/**
+ * Byte buffer owned by the caller, freed with `rewards_proof_buffer_free`
+ */
+typedef struct RewardsProofBuffer {
+  uint8_t *data;
+  size_t len;
+} RewardsProofBuffer;
+
+/**
+ * Runs the setup for an incentive catalog and writes the handle to
+ * `parameters`, which must be freed with `rewards_proof_parameters_free`
+ *
+ * # Safety
+ *
+ * `parameters` must be valid for writes.
+ */
+RewardsProofError rewards_proof_parameters_new(uint64_t incentive_catalog_size,
+                                               RewardsProofParameters **parameters);
+
+/**
+ * Loads the parameters published by the verifier, fails if the fingerprint
+ * (`REWARDS_PROOF_FINGERPRINT_SIZE` bytes) does not match the setup
+ *
+ * # Safety
+ *
+ * `fingerprint` must point to `REWARDS_PROOF_FINGERPRINT_SIZE` readable
+ * bytes and `parameters` must be valid for writes.
+ */
+RewardsProofError rewards_proof_parameters_load(uint64_t incentive_catalog_size,
+                                                const uint8_t *fingerprint,
+                                                RewardsProofParameters **parameters);
+
+/**
+ * Writes the fingerprint of the parameters
+ * (`REWARDS_PROOF_FINGERPRINT_SIZE` bytes) to `fingerprint`
+ *
+ * # Safety
+ *
+ * `parameters` must be a handle of this library and `fingerprint` must
+ * point to `REWARDS_PROOF_FINGERPRINT_SIZE` writable bytes.
+ */
+RewardsProofError rewards_proof_parameters_fingerprint(const RewardsProofParameters *parameters,
+                                                       uint8_t *fingerprint);
+
+/**
+ * Frees parameters created with `rewards_proof_parameters_new` or
+ * `rewards_proof_parameters_load`
+ *
+ * # Safety
+ *
+ * `parameters` must be null or a handle of this library that was not
+ * freed before.
+ */
+void rewards_proof_parameters_free(RewardsProofParameters *parameters);
+
+/**
+ * Generates a rewards proof for a state and the weights of a policy, and
+ * writes the serialized proof to `proof`
+ *
+ * The reward is the inner product of the state and the weights, it must be
+ * smaller than `2^REWARD_BITSIZE`.
+ *
+ * # Safety
+ *
+ * `parameters` must be a handle of this library, `state` and `weights` must
+ * point to `state_len` and `weights_len` readable values, and `proof` must be
+ * valid for writes.
+ */
+RewardsProofError rewards_proof_generate(const RewardsProofParameters *parameters,
+                                         const uint64_t *state,
+                                         size_t state_len,
+                                         const uint64_t *weights,
+                                         size_t weights_len,
+                                         RewardsProofBuffer *proof);
+
+/**
+ * Generates a rewards proof for a state and a signed policy, and writes the
+ * serialized proof to `proof`
+ *
+ * The signed policy is serialized with `SignedPolicy::to_bytes`, it is
+ * verified with the public key of the issuer
+ * (`REWARDS_PROOF_ISSUER_KEY_SIZE` bytes) and the unix timestamp in seconds
+ * before proving.
+ *
+ * # Safety
+ *
+ * `parameters` must be a handle of this library, `state` and
+ * `signed_policy` must point to `state_len` and `signed_policy_len` readable
+ * values, `issuer_key` to `REWARDS_PROOF_ISSUER_KEY_SIZE` readable bytes, and
+ * `proof` must be valid for writes.
+ */
+RewardsProofError rewards_proof_generate_signed(const RewardsProofParameters *parameters,
+                                                const uint64_t *state,
+                                                size_t state_len,
+                                                const uint8_t *signed_policy,
+                                                size_t signed_policy_len,
+                                                const uint8_t *issuer_key,
+                                                uint64_t timestamp,
+                                                RewardsProofBuffer *proof);
+
+/**
+ * Verifies a serialized rewards proof against the weights of a policy,
+ * returns `REWARDS_PROOF_ERROR_OK` if the proof verifies
+ *
+ * # Safety
+ *
+ * `parameters` must be a handle of this library, `proof` and `weights` must
+ * point to `proof_len` and `weights_len` readable values.
+ */
+RewardsProofError rewards_proof_verify(const RewardsProofParameters *parameters,
+                                       const uint8_t *proof,
+                                       size_t proof_len,
+                                       const uint64_t *weights,
+                                       size_t weights_len);
+
+/**
+ * Frees a buffer returned by the FFI functions
+ *
+ * # Safety
+ *
+ * `buffer` must be returned by this library and not freed before.
+ */
+void rewards_proof_buffer_free(RewardsProofBuffer buffer);
+
+#endif /* REWARDS_PROOF_H */
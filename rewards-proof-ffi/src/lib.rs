@@ -0,0 +1,404 @@
+//! C FFI layer of the rewards proof for native clients
+//!
+//! All functions return a `RewardsProofError`, outputs are written to the
+//! pointers passed by the caller. Parameters are an opaque handle, proofs are
+//! byte buffers in the `WireFormat` of `rewards-proof`, which are owned by the
+//! caller and must be freed with `rewards_proof_buffer_free`. Panics are caught
+//! at the boundary and reported as `REWARDS_PROOF_ERROR_PANIC`.
+
+use bulletproofs::{BulletproofGens, PedersenGens};
+use rewards_proof::api::{
+    rewards_proof_generation_aggregated, rewards_proof_generation_signed, rewards_proof_setup,
+    rewards_proof_verification_aggregated, to_scalars, RewardsProof,
+};
+use rewards_proof::policy::{IssuerPublicKey, SignedPolicy};
+use rewards_proof::wire::WireFormat;
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+/// Size of the fingerprint of the parameters
+pub const REWARDS_PROOF_FINGERPRINT_SIZE: usize = 32;
+
+/// Size of the public key of an issuer
+pub const REWARDS_PROOF_ISSUER_KEY_SIZE: usize = 32;
+
+/// Result of the FFI functions
+///
+/// The Rust API reports failures as `None` or `false`, the error codes
+/// distinguish the inputs that caused them.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RewardsProofError {
+    /// Success, or a proof that verified
+    Ok = 0,
+    /// A required pointer is null
+    NullPointer = 1,
+    /// The catalog size is zero or the fingerprint does not match the setup
+    InvalidParameters = 2,
+    /// The state does not match the catalog, a count is out of range or the
+    /// reward does not fit into `REWARD_BITSIZE` bits
+    InvalidState = 3,
+    /// The policy does not match the catalog, cannot be decoded, or its
+    /// signature or validity window is not valid
+    InvalidPolicy = 4,
+    /// The proof cannot be decoded or was created for other parameters
+    InvalidProof = 5,
+    /// The proof does not verify
+    VerificationFailed = 6,
+    /// A panic was caught at the FFI boundary
+    Panic = 7,
+}
+
+/// Parameters of an incentive catalog, an opaque handle
+pub struct RewardsProofParameters {
+    pedersen_gens: Vec<PedersenGens>,
+    bulletproof_gens: Vec<BulletproofGens>,
+    wire_format: WireFormat,
+    incentive_catalog_size: u64,
+}
+
+/// Byte buffer owned by the caller, freed with `rewards_proof_buffer_free`
+#[repr(C)]
+pub struct RewardsProofBuffer {
+    data: *mut u8,
+    len: usize,
+}
+
+impl RewardsProofBuffer {
+    /// Moves a vector into a buffer
+    fn from_vec(vec: Vec<u8>) -> RewardsProofBuffer {
+        let len = vec.len();
+        let data = Box::into_raw(vec.into_boxed_slice()) as *mut u8;
+        RewardsProofBuffer { data, len }
+    }
+}
+
+/// Runs the setup for an incentive catalog and writes the handle to
+/// `parameters`, which must be freed with `rewards_proof_parameters_free`
+///
+/// # Safety
+///
+/// `parameters` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rewards_proof_parameters_new(
+    incentive_catalog_size: u64,
+    parameters: *mut *mut RewardsProofParameters,
+) -> RewardsProofError {
+    ffi_call(|| {
+        if parameters.is_null() {
+            return RewardsProofError::NullPointer;
+        }
+        if incentive_catalog_size == 0 {
+            return RewardsProofError::InvalidParameters;
+        }
+        *parameters = Box::into_raw(Box::new(setup(incentive_catalog_size)));
+        RewardsProofError::Ok
+    })
+}
+
+/// Loads the parameters published by the verifier, fails if the fingerprint
+/// (`REWARDS_PROOF_FINGERPRINT_SIZE` bytes) does not match the setup
+///
+/// # Safety
+///
+/// `fingerprint` must point to `REWARDS_PROOF_FINGERPRINT_SIZE` readable
+/// bytes and `parameters` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rewards_proof_parameters_load(
+    incentive_catalog_size: u64,
+    fingerprint: *const u8,
+    parameters: *mut *mut RewardsProofParameters,
+) -> RewardsProofError {
+    ffi_call(|| {
+        if fingerprint.is_null() || parameters.is_null() {
+            return RewardsProofError::NullPointer;
+        }
+        if incentive_catalog_size == 0 {
+            return RewardsProofError::InvalidParameters;
+        }
+        let loaded = setup(incentive_catalog_size);
+        let fingerprint = slice::from_raw_parts(fingerprint, REWARDS_PROOF_FINGERPRINT_SIZE);
+        if loaded.wire_format.fingerprint()[..] != *fingerprint {
+            return RewardsProofError::InvalidParameters;
+        }
+        *parameters = Box::into_raw(Box::new(loaded));
+        RewardsProofError::Ok
+    })
+}
+
+/// Writes the fingerprint of the parameters
+/// (`REWARDS_PROOF_FINGERPRINT_SIZE` bytes) to `fingerprint`
+///
+/// # Safety
+///
+/// `parameters` must be a handle of this library and `fingerprint` must
+/// point to `REWARDS_PROOF_FINGERPRINT_SIZE` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rewards_proof_parameters_fingerprint(
+    parameters: *const RewardsProofParameters,
+    fingerprint: *mut u8,
+) -> RewardsProofError {
+    ffi_call(|| {
+        if parameters.is_null() || fingerprint.is_null() {
+            return RewardsProofError::NullPointer;
+        }
+        let source = (*parameters).wire_format.fingerprint();
+        ptr::copy_nonoverlapping(source.as_ptr(), fingerprint, REWARDS_PROOF_FINGERPRINT_SIZE);
+        RewardsProofError::Ok
+    })
+}
+
+/// Frees parameters created with `rewards_proof_parameters_new` or
+/// `rewards_proof_parameters_load`
+///
+/// # Safety
+///
+/// `parameters` must be null or a handle of this library that was not
+/// freed before.
+#[no_mangle]
+pub unsafe extern "C" fn rewards_proof_parameters_free(parameters: *mut RewardsProofParameters) {
+    if !parameters.is_null() {
+        drop(Box::from_raw(parameters));
+    }
+}
+
+/// Generates a rewards proof for a state and the weights of a policy, and
+/// writes the serialized proof to `proof`
+///
+/// The reward is the inner product of the state and the weights, it must be
+/// smaller than `2^REWARD_BITSIZE`.
+///
+/// # Safety
+///
+/// `parameters` must be a handle of this library, `state` and `weights` must
+/// point to `state_len` and `weights_len` readable values, and `proof` must be
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rewards_proof_generate(
+    parameters: *const RewardsProofParameters,
+    state: *const u64,
+    state_len: usize,
+    weights: *const u64,
+    weights_len: usize,
+    proof: *mut RewardsProofBuffer,
+) -> RewardsProofError {
+    ffi_call(|| {
+        let (parameters, state, weights) = match (
+            parameters.as_ref(),
+            from_raw_parts(state, state_len),
+            from_raw_parts(weights, weights_len),
+        ) {
+            (Some(parameters), Some(state), Some(weights)) if !proof.is_null() => {
+                (parameters, state, weights)
+            }
+            _ => return RewardsProofError::NullPointer,
+        };
+        if weights.len() as u64 != parameters.incentive_catalog_size {
+            return RewardsProofError::InvalidPolicy;
+        }
+        let reward = match reward(parameters, state, weights) {
+            Ok(reward) => reward,
+            Err(error) => return error,
+        };
+
+        match rewards_proof_generation_aggregated(
+            &parameters.pedersen_gens,
+            &parameters.bulletproof_gens,
+            &[reward],
+            to_scalars(state),
+            to_scalars(weights),
+            parameters.incentive_catalog_size,
+            None,
+            None,
+        ) {
+            Some(rewards_proof) => write_proof(parameters, &rewards_proof, proof),
+            None => RewardsProofError::InvalidState,
+        }
+    })
+}
+
+/// Generates a rewards proof for a state and a signed policy, and writes the
+/// serialized proof to `proof`
+///
+/// The signed policy is serialized with `SignedPolicy::to_bytes`, it is
+/// verified with the public key of the issuer
+/// (`REWARDS_PROOF_ISSUER_KEY_SIZE` bytes) and the unix timestamp in seconds
+/// before proving.
+///
+/// # Safety
+///
+/// `parameters` must be a handle of this library, `state` and
+/// `signed_policy` must point to `state_len` and `signed_policy_len` readable
+/// values, `issuer_key` to `REWARDS_PROOF_ISSUER_KEY_SIZE` readable bytes, and
+/// `proof` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rewards_proof_generate_signed(
+    parameters: *const RewardsProofParameters,
+    state: *const u64,
+    state_len: usize,
+    signed_policy: *const u8,
+    signed_policy_len: usize,
+    issuer_key: *const u8,
+    timestamp: u64,
+    proof: *mut RewardsProofBuffer,
+) -> RewardsProofError {
+    ffi_call(|| {
+        let (parameters, state, signed_policy) = match (
+            parameters.as_ref(),
+            from_raw_parts(state, state_len),
+            from_raw_parts(signed_policy, signed_policy_len),
+        ) {
+            (Some(parameters), Some(state), Some(signed_policy))
+                if !issuer_key.is_null() && !proof.is_null() =>
+            {
+                (parameters, state, signed_policy)
+            }
+            _ => return RewardsProofError::NullPointer,
+        };
+        let issuer_key = slice::from_raw_parts(issuer_key, REWARDS_PROOF_ISSUER_KEY_SIZE);
+        let (signed_policy, issuer_key) = match (
+            SignedPolicy::from_bytes(signed_policy),
+            IssuerPublicKey::from_bytes(issuer_key),
+        ) {
+            (Some(signed_policy), Some(issuer_key)) => (signed_policy, issuer_key),
+            _ => return RewardsProofError::InvalidPolicy,
+        };
+        if signed_policy.policy().incentive_catalog_size() != parameters.incentive_catalog_size {
+            return RewardsProofError::InvalidPolicy;
+        }
+        let reward = match reward(parameters, state, signed_policy.policy().weights()) {
+            Ok(reward) => reward,
+            Err(error) => return error,
+        };
+
+        match rewards_proof_generation_signed(
+            &parameters.pedersen_gens,
+            &parameters.bulletproof_gens,
+            &[reward],
+            to_scalars(state),
+            &signed_policy,
+            &issuer_key,
+            timestamp,
+        ) {
+            Some(rewards_proof) => write_proof(parameters, &rewards_proof, proof),
+            None => RewardsProofError::InvalidPolicy,
+        }
+    })
+}
+
+/// Verifies a serialized rewards proof against the weights of a policy,
+/// returns `REWARDS_PROOF_ERROR_OK` if the proof verifies
+///
+/// # Safety
+///
+/// `parameters` must be a handle of this library, `proof` and `weights` must
+/// point to `proof_len` and `weights_len` readable values.
+#[no_mangle]
+pub unsafe extern "C" fn rewards_proof_verify(
+    parameters: *const RewardsProofParameters,
+    proof: *const u8,
+    proof_len: usize,
+    weights: *const u64,
+    weights_len: usize,
+) -> RewardsProofError {
+    ffi_call(|| {
+        let (parameters, proof, weights) = match (
+            parameters.as_ref(),
+            from_raw_parts(proof, proof_len),
+            from_raw_parts(weights, weights_len),
+        ) {
+            (Some(parameters), Some(proof), Some(weights)) => (parameters, proof, weights),
+            _ => return RewardsProofError::NullPointer,
+        };
+        if weights.len() as u64 != parameters.incentive_catalog_size {
+            return RewardsProofError::InvalidPolicy;
+        }
+        let rewards_proof = match parameters.wire_format.decode(proof) {
+            Some(rewards_proof) => rewards_proof,
+            None => return RewardsProofError::InvalidProof,
+        };
+
+        if rewards_proof_verification_aggregated(
+            &parameters.pedersen_gens,
+            &parameters.bulletproof_gens,
+            &rewards_proof,
+            &to_scalars(weights),
+            None,
+            None,
+        ) {
+            RewardsProofError::Ok
+        } else {
+            RewardsProofError::VerificationFailed
+        }
+    })
+}
+
+/// Frees a buffer returned by the FFI functions
+///
+/// # Safety
+///
+/// `buffer` must be returned by this library and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn rewards_proof_buffer_free(buffer: RewardsProofBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+/// Runs `f` and reports panics as `RewardsProofError::Panic`
+fn ffi_call<F: FnOnce() -> RewardsProofError>(f: F) -> RewardsProofError {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(RewardsProofError::Panic)
+}
+
+/// Runs the setup for an incentive catalog
+fn setup(incentive_catalog_size: u64) -> RewardsProofParameters {
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(incentive_catalog_size);
+    let wire_format = WireFormat::new(&pedersen_gens, &bulletproof_gens, incentive_catalog_size);
+    RewardsProofParameters {
+        pedersen_gens,
+        bulletproof_gens,
+        wire_format,
+        incentive_catalog_size,
+    }
+}
+
+/// Borrows an array of the caller, returns `None` if `data` is null and
+/// `len` is not zero
+unsafe fn from_raw_parts<'a, T>(data: *const T, len: usize) -> Option<&'a [T]> {
+    if data.is_null() {
+        return if len == 0 { Some(&[]) } else { None };
+    }
+    Some(slice::from_raw_parts(data, len))
+}
+
+/// Computes the reward `<state, weights>`, fails if the state does not match
+/// the catalog, a count does not fit into `STATE_ENTRY_BITSIZE` bits or the
+/// reward does not fit into `REWARD_BITSIZE` bits
+fn reward(
+    parameters: &RewardsProofParameters,
+    state: &[u64],
+    weights: &[u64],
+) -> Result<u64, RewardsProofError> {
+    if state.len() as u64 != parameters.incentive_catalog_size {
+        return Err(RewardsProofError::InvalidState);
+    }
+    rewards_proof::api::reward(state, weights).ok_or(RewardsProofError::InvalidState)
+}
+
+/// Serializes a proof into the buffer of the caller
+unsafe fn write_proof(
+    parameters: &RewardsProofParameters,
+    rewards_proof: &RewardsProof,
+    proof: *mut RewardsProofBuffer,
+) -> RewardsProofError {
+    match parameters.wire_format.encode(rewards_proof) {
+        Some(bytes) => {
+            *proof = RewardsProofBuffer::from_vec(bytes);
+            RewardsProofError::Ok
+        }
+        None => RewardsProofError::InvalidParameters,
+    }
+}
@@ -0,0 +1,117 @@
+/*
+ * Tests of the C FFI layer, run with `make test` on Linux
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "rewards_proof.h"
+
+#define INCENTIVE_CATALOG_SIZE 4
+
+static int failures = 0;
+
+#define CHECK(condition)                                                      \
+  do {                                                                        \
+    if (!(condition)) {                                                       \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__,        \
+              #condition);                                                    \
+      failures++;                                                             \
+    }                                                                         \
+  } while (0)
+
+int main(void) {
+  RewardsProofParameters *parameters = NULL;
+  RewardsProofParameters *loaded = NULL;
+  RewardsProofBuffer proof = {NULL, 0};
+  uint8_t fingerprint[REWARDS_PROOF_FINGERPRINT_SIZE];
+  uint8_t other_fingerprint[REWARDS_PROOF_FINGERPRINT_SIZE];
+  uint64_t state[INCENTIVE_CATALOG_SIZE] = {1, 0, 3, 2};
+  uint64_t weights[INCENTIVE_CATALOG_SIZE] = {2, 5, 1, 0};
+  uint64_t other_weights[INCENTIVE_CATALOG_SIZE] = {2, 5, 1, 1};
+  uint64_t large_state[INCENTIVE_CATALOG_SIZE] = {1, 0, 3, 65536};
+  uint64_t max_reward_weights[INCENTIVE_CATALOG_SIZE] = {65535, 0, 0, 0};
+  uint64_t large_reward_weights[INCENTIVE_CATALOG_SIZE] = {65536, 0, 0, 0};
+  uint8_t issuer_key[REWARDS_PROOF_ISSUER_KEY_SIZE] = {0};
+  uint8_t signed_policy[64] = {0};
+  uint8_t *tampered;
+
+  /* parameters */
+  CHECK(rewards_proof_parameters_new(INCENTIVE_CATALOG_SIZE, &parameters) ==
+        REWARDS_PROOF_ERROR_OK);
+  CHECK(rewards_proof_parameters_new(0, &parameters) ==
+        REWARDS_PROOF_ERROR_INVALID_PARAMETERS);
+  CHECK(rewards_proof_parameters_new(INCENTIVE_CATALOG_SIZE, NULL) ==
+        REWARDS_PROOF_ERROR_NULL_POINTER);
+  CHECK(rewards_proof_parameters_fingerprint(parameters, fingerprint) ==
+        REWARDS_PROOF_ERROR_OK);
+  CHECK(rewards_proof_parameters_load(INCENTIVE_CATALOG_SIZE, fingerprint,
+                                      &loaded) == REWARDS_PROOF_ERROR_OK);
+  CHECK(rewards_proof_parameters_fingerprint(loaded, other_fingerprint) ==
+        REWARDS_PROOF_ERROR_OK);
+  CHECK(memcmp(fingerprint, other_fingerprint, sizeof(fingerprint)) == 0);
+  rewards_proof_parameters_free(loaded);
+  CHECK(rewards_proof_parameters_load(2 * INCENTIVE_CATALOG_SIZE, fingerprint,
+                                      &loaded) ==
+        REWARDS_PROOF_ERROR_INVALID_PARAMETERS);
+
+  /* proof generation and verification */
+  CHECK(rewards_proof_generate(parameters, state, INCENTIVE_CATALOG_SIZE,
+                               weights, INCENTIVE_CATALOG_SIZE,
+                               &proof) == REWARDS_PROOF_ERROR_OK);
+  CHECK(proof.data != NULL && proof.len > 0);
+  CHECK(rewards_proof_verify(parameters, proof.data, proof.len, weights,
+                             INCENTIVE_CATALOG_SIZE) == REWARDS_PROOF_ERROR_OK);
+  CHECK(rewards_proof_verify(parameters, proof.data, proof.len, other_weights,
+                             INCENTIVE_CATALOG_SIZE) ==
+        REWARDS_PROOF_ERROR_VERIFICATION_FAILED);
+  CHECK(rewards_proof_verify(parameters, proof.data, proof.len, weights,
+                             INCENTIVE_CATALOG_SIZE - 1) ==
+        REWARDS_PROOF_ERROR_INVALID_POLICY);
+
+  /* trailing bytes are rejected */
+  tampered = malloc(proof.len + 1);
+  memcpy(tampered, proof.data, proof.len);
+  tampered[proof.len] = 0;
+  CHECK(rewards_proof_verify(parameters, tampered, proof.len + 1, weights,
+                             INCENTIVE_CATALOG_SIZE) ==
+        REWARDS_PROOF_ERROR_INVALID_PROOF);
+  free(tampered);
+  rewards_proof_buffer_free(proof);
+
+  /* the reward must be smaller than 2^REWARD_BITSIZE */
+  CHECK(rewards_proof_generate(parameters, state, INCENTIVE_CATALOG_SIZE,
+                               max_reward_weights, INCENTIVE_CATALOG_SIZE,
+                               &proof) == REWARDS_PROOF_ERROR_OK);
+  CHECK(rewards_proof_verify(parameters, proof.data, proof.len,
+                             max_reward_weights, INCENTIVE_CATALOG_SIZE) ==
+        REWARDS_PROOF_ERROR_OK);
+  rewards_proof_buffer_free(proof);
+
+  /* invalid inputs */
+  CHECK(rewards_proof_generate(parameters, state, INCENTIVE_CATALOG_SIZE - 1,
+                               weights, INCENTIVE_CATALOG_SIZE,
+                               &proof) == REWARDS_PROOF_ERROR_INVALID_STATE);
+  CHECK(rewards_proof_generate(parameters, large_state, INCENTIVE_CATALOG_SIZE,
+                               weights, INCENTIVE_CATALOG_SIZE,
+                               &proof) == REWARDS_PROOF_ERROR_INVALID_STATE);
+  CHECK(rewards_proof_generate(parameters, state, INCENTIVE_CATALOG_SIZE,
+                               large_reward_weights, INCENTIVE_CATALOG_SIZE,
+                               &proof) == REWARDS_PROOF_ERROR_INVALID_STATE);
+  CHECK(rewards_proof_generate(parameters, NULL, INCENTIVE_CATALOG_SIZE,
+                               weights, INCENTIVE_CATALOG_SIZE,
+                               &proof) == REWARDS_PROOF_ERROR_NULL_POINTER);
+  CHECK(rewards_proof_generate_signed(
+            parameters, state, INCENTIVE_CATALOG_SIZE, signed_policy,
+            sizeof(signed_policy), issuer_key, 0,
+            &proof) == REWARDS_PROOF_ERROR_INVALID_POLICY);
+
+  rewards_proof_parameters_free(parameters);
+
+  if (failures > 0) {
+    fprintf(stderr, "%d checks failed\n", failures);
+    return EXIT_FAILURE;
+  }
+  printf("all checks passed\n");
+  return EXIT_SUCCESS;
+}
@@ -0,0 +1,16 @@
+fn main() {
+    // Generate the gRPC service, the messages of `rewards.proto` are generated
+    // by `rewards-proof` with the `protobuf` feature
+    println!("cargo:rerun-if-changed=proto/verifier.proto");
+    std::env::set_var(
+        "PROTOC",
+        protoc_bin_vendored::protoc_bin_path().expect("No protoc binary for this platform"),
+    );
+    tonic_build::configure()
+        .extern_path(".rewards_proof.v1", "::rewards_proof::protobuf::proto")
+        .compile(
+            &["proto/verifier.proto"],
+            &["proto", "../rewards-proof/proto"],
+        )
+        .expect("Failed to compile the gRPC service");
+}
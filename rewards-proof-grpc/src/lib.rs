@@ -0,0 +1,250 @@
+//! gRPC verification service of the rewards proof
+//!
+//! The `Verifier` service of `proto/verifier.proto` verifies the protobuf
+//! messages of `rewards-proof` against the signed policies of an issuer:
+//!
+//! - `GetParameters` returns the setup parameters
+//! - `Verify` verifies a `RewardsProofSubmission` against the policy with its
+//!   ID and version, and fails with `NOT_FOUND` if the policy is not
+//!   registered or not valid now
+//! - `VerifyBatch` verifies a client stream of submissions in chunks, and
+//!   returns the result of every proof once the stream is closed. It fails
+//!   with `RESOURCE_EXHAUSTED` if the stream exceeds the maximum batch size.
+//!
+//! Proofs that cannot be decoded, and submissions of a stream without a valid
+//! policy, are not valid. The policies have no tier tables, so predicate
+//! proofs are not verified and results never report a tier.
+
+use bulletproofs::{BulletproofGens, PedersenGens};
+use rewards_proof::api::{rewards_proof_setup, RewardsProof};
+use rewards_proof::policy::{now, IssuerPublicKey, SignedPolicy};
+use rewards_proof::protobuf::proto::{RewardsProofSubmission, SetupParameters, VerificationResult};
+use rewards_proof::protobuf::setup_parameters;
+use rewards_proof::verifier::{PolicyVerifier, PreparedVerifier};
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+use tonic::{Request, Response, Status, Streaming};
+
+/// Generated service of `proto/verifier.proto`
+pub mod proto {
+    tonic::include_proto!("rewards_proof.verifier.v1");
+}
+
+use proto::verifier_server::{Verifier, VerifierServer};
+use proto::{GetParametersRequest, VerifyBatchResponse};
+
+/// Default maximum number of proofs of a stream
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 1024;
+
+/// Number of proofs of a stream that are verified together
+const CHUNK_SIZE: usize = 64;
+
+/// Submission of a stream, with the policy it is verified against if the
+/// policy is valid and the proof can be decoded
+type Entry = (u64, Option<((u64, u32), RewardsProof)>);
+
+/// Prepared verifier and signed policies of the service
+pub struct VerifierState {
+    verifier: PolicyVerifier,
+    parameters: SetupParameters,
+    max_batch_size: usize,
+}
+
+impl VerifierState {
+    /// Runs the setup for an incentive catalog and prepares the verifier for
+    /// policies signed by `issuer_key`
+    pub fn new(
+        incentive_catalog_size: u64,
+        issuer_key: IssuerPublicKey,
+        max_batch_size: usize,
+    ) -> VerifierState {
+        let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(incentive_catalog_size);
+        VerifierState::with_setup(
+            &pedersen_gens,
+            &bulletproof_gens,
+            incentive_catalog_size,
+            issuer_key,
+            max_batch_size,
+        )
+    }
+
+    /// Prepares the verifier for the output of `rewards_proof_setup`
+    pub fn with_setup(
+        pedersen_gens: &[PedersenGens],
+        bulletproof_gens: &[BulletproofGens],
+        incentive_catalog_size: u64,
+        issuer_key: IssuerPublicKey,
+        max_batch_size: usize,
+    ) -> VerifierState {
+        VerifierState {
+            verifier: PolicyVerifier::new(
+                PreparedVerifier::new(pedersen_gens, bulletproof_gens, incentive_catalog_size),
+                issuer_key,
+            ),
+            parameters: setup_parameters(pedersen_gens, bulletproof_gens, incentive_catalog_size),
+            max_batch_size,
+        }
+    }
+
+    /// Registers a signed policy and prepares its policy vector, returns
+    /// `false` if the signature is invalid, the policy does not match the
+    /// catalog, or a different policy with the same ID and version is
+    /// registered
+    pub fn add_policy(&mut self, signed_policy: SignedPolicy) -> bool {
+        self.verifier.add_policy(signed_policy)
+    }
+
+    /// Returns the ID and version of the policy of a submission, if the
+    /// policy is registered and valid now
+    fn policy_key(&self, submission: &RewardsProofSubmission) -> Option<(u64, u32)> {
+        self.verifier
+            .valid_policy(submission.policy_id, Some(submission.policy_version), now())
+            .map(|_| (submission.policy_id, submission.policy_version))
+    }
+
+    /// Decodes a submission whose policy is valid, see `policy_key`
+    fn entry(
+        &self,
+        index: u64,
+        policy: Option<(u64, u32)>,
+        submission: &RewardsProofSubmission,
+    ) -> Entry {
+        let proof = submission
+            .proof
+            .as_ref()
+            .and_then(RewardsProof::from_protobuf);
+        (index, policy.zip(proof))
+    }
+
+    /// Verifies the decoded submissions, grouped by their policy
+    fn verify(&self, entries: &[Entry]) -> Result<Vec<VerificationResult>, Status> {
+        let mut groups: HashMap<(u64, u32), Vec<usize>> = HashMap::new();
+        for (position, (_, entry)) in entries.iter().enumerate() {
+            if let Some((policy, _)) = entry {
+                groups.entry(*policy).or_default().push(position);
+            }
+        }
+
+        let mut valid = vec![false; entries.len()];
+        for ((id, version), positions) in groups {
+            let proofs: Vec<Option<&RewardsProof>> = positions
+                .iter()
+                .map(|position| entries[*position].1.as_ref().map(|(_, proof)| proof))
+                .collect();
+            let results = self
+                .verifier
+                .verify_batch(id, version, &proofs)
+                .ok_or_else(policy_not_found)?;
+            for (position, result) in positions.into_iter().zip(results) {
+                valid[position] = result;
+            }
+        }
+
+        // the policies have no tier tables, predicate proofs are not verified
+        // and the tier they claim is not reported
+        Ok(entries
+            .iter()
+            .zip(valid)
+            .map(|((index, _), valid)| VerificationResult {
+                index: *index,
+                valid,
+                tier: None,
+            })
+            .collect())
+    }
+}
+
+/// Implementation of the `Verifier` service
+#[derive(Clone)]
+pub struct VerifierService {
+    state: Arc<VerifierState>,
+}
+
+impl VerifierService {
+    pub fn new(state: VerifierState) -> VerifierService {
+        VerifierService {
+            state: Arc::new(state),
+        }
+    }
+
+    /// Returns the service to add to a `tonic` server
+    pub fn into_server(self) -> VerifierServer<VerifierService> {
+        VerifierServer::new(self)
+    }
+
+    /// Verifies decoded submissions on the blocking thread pool
+    async fn verify_blocking(
+        &self,
+        entries: Vec<Entry>,
+    ) -> Result<Vec<VerificationResult>, Status> {
+        let state = self.state.clone();
+        tokio::task::spawn_blocking(move || state.verify(&entries))
+            .await
+            .map_err(|_| Status::internal("Verification failed unexpectedly"))?
+    }
+}
+
+#[tonic::async_trait]
+impl Verifier for VerifierService {
+    async fn get_parameters(
+        &self,
+        _request: Request<GetParametersRequest>,
+    ) -> Result<Response<SetupParameters>, Status> {
+        Ok(Response::new(self.state.parameters.clone()))
+    }
+
+    async fn verify(
+        &self,
+        request: Request<RewardsProofSubmission>,
+    ) -> Result<Response<VerificationResult>, Status> {
+        let submission = request.into_inner();
+        let policy = self
+            .state
+            .policy_key(&submission)
+            .ok_or_else(policy_not_found)?;
+        let entry = self.state.entry(0, Some(policy), &submission);
+
+        let mut results = self.verify_blocking(vec![entry]).await?;
+        Ok(Response::new(results.remove(0)))
+    }
+
+    async fn verify_batch(
+        &self,
+        request: Request<Streaming<RewardsProofSubmission>>,
+    ) -> Result<Response<VerifyBatchResponse>, Status> {
+        let mut stream = request.into_inner();
+        let mut results = Vec::new();
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+        let mut index = 0;
+
+        while let Some(submission) = stream.message().await? {
+            if index as usize >= self.state.max_batch_size {
+                return Err(Status::resource_exhausted(format!(
+                    "Batch exceeds the maximum of {} proofs",
+                    self.state.max_batch_size
+                )));
+            }
+            let policy = self.state.policy_key(&submission);
+            chunk.push(self.state.entry(index, policy, &submission));
+            index += 1;
+
+            if chunk.len() == CHUNK_SIZE {
+                results.extend(self.verify_blocking(mem::take(&mut chunk)).await?);
+            }
+        }
+        results.extend(self.verify_blocking(chunk).await?);
+
+        let valid = results.iter().filter(|result| result.valid).count() as u64;
+        Ok(Response::new(VerifyBatchResponse {
+            invalid: results.len() as u64 - valid,
+            valid,
+            results,
+        }))
+    }
+}
+
+/// Error of a submission without a registered policy that is valid now
+fn policy_not_found() -> Status {
+    Status::not_found("No valid policy with this ID and version")
+}
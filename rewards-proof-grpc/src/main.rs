@@ -0,0 +1,84 @@
+//! gRPC verification service of the rewards proof, see the library for the
+//! methods
+
+use clap::Parser;
+use rewards_proof::policy::{IssuerPublicKey, SignedPolicy};
+use rewards_proof_grpc::{VerifierService, VerifierState, DEFAULT_MAX_BATCH_SIZE};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process;
+use tonic::transport::Server;
+
+#[derive(Parser)]
+#[command(
+    name = "grpc-server",
+    version,
+    about = "gRPC verification service of the rewards proof"
+)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:50051")]
+    listen: SocketAddr,
+    /// Size of the incentive catalog
+    #[arg(long)]
+    incentive_catalog_size: u64,
+    /// Hex encoded public key of the issuer
+    #[arg(long)]
+    issuer_key: String,
+    /// Signed policy file, can be given multiple times
+    #[arg(long = "policy")]
+    policies: Vec<PathBuf>,
+    /// Maximum number of proofs of a stream
+    #[arg(long, default_value_t = DEFAULT_MAX_BATCH_SIZE)]
+    max_batch_size: usize,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let state = load_state(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(2);
+    });
+
+    eprintln!("Listening on {}", args.listen);
+    let result = Server::builder()
+        .add_service(VerifierService::new(state).into_server())
+        .serve_with_shutdown(args.listen, async {
+            tokio::signal::ctrl_c()
+                .await
+                .expect("Failed to install the signal handler");
+        })
+        .await;
+    if let Err(error) = result {
+        eprintln!("{}: {}", args.listen, error);
+        process::exit(2);
+    }
+}
+
+/// Runs the setup and registers the signed policies
+fn load_state(args: &Args) -> Result<VerifierState, String> {
+    if args.incentive_catalog_size == 0 {
+        return Err("Incentive catalog must not be empty".to_string());
+    }
+    let issuer_key = hex::decode(&args.issuer_key)
+        .ok()
+        .and_then(|bytes| IssuerPublicKey::from_bytes(&bytes))
+        .ok_or("Invalid issuer key")?;
+
+    let mut state =
+        VerifierState::new(args.incentive_catalog_size, issuer_key, args.max_batch_size);
+    for path in args.policies.iter() {
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let signed_policy: SignedPolicy =
+            serde_json::from_slice(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if !state.add_policy(signed_policy) {
+            return Err(format!(
+                "{}: Policy is not signed by the issuer or does not match the catalog",
+                path.display()
+            ));
+        }
+    }
+    Ok(state)
+}
@@ -0,0 +1,216 @@
+//! End-to-end tests against a locally started instance of the service
+
+use curve25519_dalek::scalar::Scalar;
+use rewards_proof::api::{rewards_proof_generation_aggregated, rewards_proof_setup};
+use rewards_proof::policy::{IssuerSigningKey, Policy};
+use rewards_proof::predicate::TierTable;
+use rewards_proof::protobuf::proto::{RewardsProofSubmission, SetupParameters};
+use rewards_proof::protobuf::setup_parameters;
+use rewards_proof_grpc::proto::verifier_client::VerifierClient;
+use rewards_proof_grpc::proto::GetParametersRequest;
+use rewards_proof_grpc::{VerifierService, VerifierState};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Channel, Server};
+use tonic::Code;
+
+const INCENTIVE_CATALOG_SIZE: u64 = 4;
+const WEIGHTS: [u64; 4] = [2, 5, 1, 0];
+
+/// Client of the service and the fixtures of a test
+struct TestServer {
+    client: VerifierClient<Channel>,
+    parameters: SetupParameters,
+    submissions: Vec<RewardsProofSubmission>,
+}
+
+/// Starts the service on a local port with one signed policy (ID 1, version
+/// 1) and generates valid submissions for it
+async fn start(max_batch_size: usize, number_of_proofs: usize) -> TestServer {
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(INCENTIVE_CATALOG_SIZE);
+    let signing_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let policy = Policy::new(1, 1, WEIGHTS.to_vec(), 0, u64::MAX).unwrap();
+
+    let mut state = VerifierState::with_setup(
+        &pedersen_gens,
+        &bulletproof_gens,
+        INCENTIVE_CATALOG_SIZE,
+        signing_key.public_key(),
+        max_batch_size,
+    );
+    assert!(state.add_policy(signing_key.sign(&policy)));
+    // a policy of another issuer is rejected
+    let other_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    assert!(!state.add_policy(other_key.sign(&policy)));
+
+    let submissions = (0..number_of_proofs as u64)
+        .map(|i| {
+            let state = [i, 0, 3, 2];
+            let reward = state.iter().zip(WEIGHTS.iter()).map(|(x, y)| x * y).sum();
+            let proof = rewards_proof_generation_aggregated(
+                &pedersen_gens,
+                &bulletproof_gens,
+                &[reward],
+                state.iter().map(|count| Scalar::from(*count)).collect(),
+                policy.policy_vector(),
+                INCENTIVE_CATALOG_SIZE,
+                None,
+                None,
+            )
+            .unwrap();
+            RewardsProofSubmission {
+                proof: Some(proof.to_protobuf()),
+                policy_id: 1,
+                policy_version: 1,
+            }
+        })
+        .collect();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = Server::builder()
+        .add_service(VerifierService::new(state).into_server())
+        .serve_with_incoming(TcpListenerStream::new(listener));
+    tokio::spawn(server);
+
+    TestServer {
+        client: VerifierClient::connect(url).await.unwrap(),
+        parameters: setup_parameters(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE),
+        submissions,
+    }
+}
+
+/// Returns a submission whose proof decodes but does not verify
+fn tampered(submission: &RewardsProofSubmission) -> RewardsProofSubmission {
+    let mut submission = submission.clone();
+    let proof = submission.proof.as_mut().unwrap();
+    proof.c = proof.g[0].clone();
+    submission
+}
+
+/// Returns a submission whose proof cannot be decoded
+fn undecodable(submission: &RewardsProofSubmission) -> RewardsProofSubmission {
+    let mut submission = submission.clone();
+    submission.proof.as_mut().unwrap().c = vec![0; 31];
+    submission
+}
+
+#[tokio::test]
+async fn get_parameters() {
+    let mut server = start(16, 0).await;
+    let parameters = server
+        .client
+        .get_parameters(GetParametersRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(parameters, server.parameters);
+}
+
+#[tokio::test]
+async fn verify() {
+    let mut server = start(16, 1).await;
+    let submission = server.submissions[0].clone();
+    let result = server.client.verify(submission.clone()).await.unwrap();
+    assert!(result.get_ref().valid);
+    assert_eq!(result.get_ref().tier, None);
+
+    let result = server.client.verify(tampered(&submission)).await.unwrap();
+    assert!(!result.get_ref().valid);
+
+    let result = server
+        .client
+        .verify(undecodable(&submission))
+        .await
+        .unwrap();
+    assert!(!result.get_ref().valid);
+
+    let mut unknown_policy = submission;
+    unknown_policy.policy_version = 2;
+    let status = server.client.verify(unknown_policy).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn forged_tier_is_not_reported() {
+    let mut server = start(16, 0).await;
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(INCENTIVE_CATALOG_SIZE);
+    let tier_table = TierTable::new(vec![3, 10], vec![0, 50, 100]).unwrap();
+    let proof = rewards_proof_generation_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &[3],
+        [0, 0, 3, 2]
+            .iter()
+            .map(|count| Scalar::from(*count))
+            .collect(),
+        WEIGHTS.iter().map(|weight| Scalar::from(*weight)).collect(),
+        INCENTIVE_CATALOG_SIZE,
+        None,
+        Some(&tier_table),
+    )
+    .unwrap();
+
+    // claim the highest tier instead of tier 1
+    let mut message = proof.to_protobuf();
+    message.predicate_proof.as_mut().unwrap()[..4].copy_from_slice(&2u32.to_le_bytes());
+    let submission = RewardsProofSubmission {
+        proof: Some(message),
+        policy_id: 1,
+        policy_version: 1,
+    };
+
+    let result = server.client.verify(submission.clone()).await.unwrap();
+    assert!(result.get_ref().valid);
+    assert_eq!(result.get_ref().tier, None);
+    let response = server
+        .client
+        .verify_batch(tokio_stream::iter(vec![submission]))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.results.len(), 1);
+    assert!(response.results[0].valid);
+    assert_eq!(response.results[0].tier, None);
+}
+
+#[tokio::test]
+async fn verify_batch() {
+    let mut server = start(256, 70).await;
+    let mut submissions = server.submissions.clone();
+    submissions[1] = tampered(&submissions[1]);
+    submissions[66] = undecodable(&submissions[66]);
+    submissions[68].policy_id = 2;
+
+    let response = server
+        .client
+        .verify_batch(tokio_stream::iter(submissions))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!((response.valid, response.invalid), (67, 3));
+    for (i, result) in response.results.iter().enumerate() {
+        assert_eq!(result.index, i as u64);
+        assert_eq!(result.valid, ![1, 66, 68].contains(&i));
+    }
+
+    let response = server
+        .client
+        .verify_batch(tokio_stream::iter(Vec::new()))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(response.results.is_empty());
+}
+
+#[tokio::test]
+async fn batch_limit() {
+    let mut server = start(2, 3).await;
+    let submissions = server.submissions.clone();
+    let status = server
+        .client
+        .verify_batch(tokio_stream::iter(submissions))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+}
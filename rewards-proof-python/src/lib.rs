@@ -0,0 +1,210 @@
+//! Python bindings of the rewards proof
+//!
+//! The `rewards_proof` module exposes the setup, proving, verification and
+//! batch verification. State and policy vectors are one-dimensional numpy
+//! arrays of `numpy.uint64`, proofs are `bytes` in the `WireFormat` of
+//! `rewards-proof`. The GIL is released while proving and verifying, and
+//! batches are verified on all CPU cores.
+
+use bulletproofs::{BulletproofGens, PedersenGens};
+use numpy::PyReadonlyArray1;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rewards_proof::api::{
+    rewards_proof_generation_aggregated, rewards_proof_setup, to_scalars, REWARD_BITSIZE,
+};
+use rewards_proof::bounds::STATE_ENTRY_BITSIZE;
+use rewards_proof::verifier::PreparedVerifier;
+use rewards_proof::wire::WireFormat;
+
+/// Setup parameters and prepared verifier for an incentive catalog
+#[pyclass(module = "rewards_proof")]
+pub struct Setup {
+    pedersen_gens: Vec<PedersenGens>,
+    bulletproof_gens: Vec<BulletproofGens>,
+    wire_format: WireFormat,
+    verifier: PreparedVerifier,
+    incentive_catalog_size: u64,
+}
+
+#[pymethods]
+impl Setup {
+    /// Runs the setup for an incentive catalog
+    #[new]
+    fn new(py: Python<'_>, incentive_catalog_size: u64) -> PyResult<Setup> {
+        if incentive_catalog_size == 0 {
+            return Err(PyValueError::new_err("Incentive catalog must not be empty"));
+        }
+        Ok(py.allow_threads(|| setup(incentive_catalog_size)))
+    }
+
+    /// Loads the setup published by the verifier, raises `ValueError` if the
+    /// fingerprint does not match the derived generators
+    #[staticmethod]
+    fn load(py: Python<'_>, incentive_catalog_size: u64, fingerprint: &[u8]) -> PyResult<Setup> {
+        let loaded = Setup::new(py, incentive_catalog_size)?;
+        if loaded.wire_format.fingerprint()[..] != *fingerprint {
+            return Err(PyValueError::new_err(
+                "Fingerprint does not match the setup",
+            ));
+        }
+        Ok(loaded)
+    }
+
+    /// Fingerprint of the setup parameters
+    #[getter]
+    fn fingerprint<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, self.wire_format.fingerprint())
+    }
+
+    /// Size of the incentive catalog
+    #[getter]
+    fn incentive_catalog_size(&self) -> u64 {
+        self.incentive_catalog_size
+    }
+}
+
+/// Generates a rewards proof for a state and a policy vector
+///
+/// The reward is the inner product of the state and the policy vector.
+/// Raises `ValueError` if the vectors do not match the catalog, a count does
+/// not fit into `STATE_ENTRY_BITSIZE` bits or the reward does not fit into
+/// `REWARD_BITSIZE` bits.
+#[pyfunction]
+fn prove<'py>(
+    py: Python<'py>,
+    setup: &Setup,
+    state: PyReadonlyArray1<u64>,
+    policy: PyReadonlyArray1<u64>,
+) -> PyResult<&'py PyBytes> {
+    let state = to_vec(&state);
+    let policy = to_vec(&policy);
+    check_policy(setup, &policy)?;
+    let reward = reward(setup, &state, &policy)?;
+
+    let proof = py.allow_threads(|| {
+        rewards_proof_generation_aggregated(
+            &setup.pedersen_gens,
+            &setup.bulletproof_gens,
+            &[reward],
+            to_scalars(&state),
+            to_scalars(&policy),
+            setup.incentive_catalog_size,
+            None,
+            None,
+        )
+        .and_then(|rewards_proof| setup.wire_format.encode(&rewards_proof))
+    });
+    match proof {
+        Some(proof) => Ok(PyBytes::new(py, &proof)),
+        None => Err(PyValueError::new_err("State is not valid")),
+    }
+}
+
+/// Verifies a rewards proof against a policy vector, returns `False` if the
+/// proof cannot be decoded or does not verify
+#[pyfunction]
+fn verify(
+    py: Python<'_>,
+    setup: &Setup,
+    proof: &[u8],
+    policy: PyReadonlyArray1<u64>,
+) -> PyResult<bool> {
+    let policy = to_vec(&policy);
+    check_policy(setup, &policy)?;
+
+    Ok(py.allow_threads(|| match setup.wire_format.decode(proof) {
+        Some(rewards_proof) => {
+            setup
+                .verifier
+                .verify(&rewards_proof, &to_scalars(&policy), None, None)
+        }
+        None => false,
+    }))
+}
+
+/// Verifies multiple rewards proofs against the same policy vector and
+/// returns the result of every proof
+///
+/// The policy vector is prepared once for the batch and the proofs are
+/// verified on all CPU cores.
+#[pyfunction]
+fn verify_batch(
+    py: Python<'_>,
+    setup: &Setup,
+    proofs: Vec<&[u8]>,
+    policy: PyReadonlyArray1<u64>,
+) -> PyResult<Vec<bool>> {
+    let policy = to_vec(&policy);
+    check_policy(setup, &policy)?;
+
+    Ok(py.allow_threads(|| {
+        let decoded: Vec<_> = proofs
+            .iter()
+            .map(|proof| setup.wire_format.decode(proof))
+            .collect();
+        // proofs that cannot be decoded are not valid
+        let decoded: Vec<_> = decoded.iter().map(Option::as_ref).collect();
+        match setup.verifier.prepare_policy(&to_scalars(&policy)) {
+            Some(prepared) => setup
+                .verifier
+                .verify_decoded_batch_with_policy(&decoded, &prepared),
+            None => vec![false; decoded.len()],
+        }
+    }))
+}
+
+/// Python module of the rewards proof
+#[pymodule]
+#[pyo3(name = "rewards_proof")]
+fn rewards_proof_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Setup>()?;
+    m.add_function(wrap_pyfunction!(prove, m)?)?;
+    m.add_function(wrap_pyfunction!(verify, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_batch, m)?)?;
+    m.add("STATE_ENTRY_BITSIZE", STATE_ENTRY_BITSIZE)?;
+    m.add("REWARD_BITSIZE", REWARD_BITSIZE)?;
+    Ok(())
+}
+
+/// Runs the setup and prepares the verifier for an incentive catalog
+fn setup(incentive_catalog_size: u64) -> Setup {
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(incentive_catalog_size);
+    let wire_format = WireFormat::new(&pedersen_gens, &bulletproof_gens, incentive_catalog_size);
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, incentive_catalog_size);
+    Setup {
+        pedersen_gens,
+        bulletproof_gens,
+        wire_format,
+        verifier,
+        incentive_catalog_size,
+    }
+}
+
+/// Copies a numpy array, which may not be contiguous
+fn to_vec(array: &PyReadonlyArray1<u64>) -> Vec<u64> {
+    array.as_array().iter().copied().collect()
+}
+
+/// Checks that the policy vector matches the catalog
+fn check_policy(setup: &Setup, policy: &[u64]) -> PyResult<()> {
+    if policy.len() as u64 != setup.incentive_catalog_size {
+        return Err(PyValueError::new_err(
+            "Policy vector does not match the incentive catalog",
+        ));
+    }
+    Ok(())
+}
+
+/// Computes the reward `<state, policy>`
+fn reward(setup: &Setup, state: &[u64], policy: &[u64]) -> PyResult<u64> {
+    if state.len() as u64 != setup.incentive_catalog_size {
+        return Err(PyValueError::new_err(
+            "State does not match the incentive catalog",
+        ));
+    }
+    rewards_proof::api::reward(state, policy).ok_or_else(|| {
+        PyValueError::new_err("Count exceeds the maximum state entry or reward is too large")
+    })
+}
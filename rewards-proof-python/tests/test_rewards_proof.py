@@ -0,0 +1,94 @@
+"""Tests of the Python bindings, run with `pytest` after `maturin develop`"""
+
+import numpy as np
+import pytest
+
+import rewards_proof
+
+INCENTIVE_CATALOG_SIZE = 4
+
+
+@pytest.fixture(scope="module")
+def setup():
+    return rewards_proof.Setup(INCENTIVE_CATALOG_SIZE)
+
+
+@pytest.fixture
+def state():
+    return np.array([1, 0, 3, 2], dtype=np.uint64)
+
+
+@pytest.fixture
+def policy():
+    return np.array([2, 5, 1, 0], dtype=np.uint64)
+
+
+def test_setup(setup):
+    assert setup.incentive_catalog_size == INCENTIVE_CATALOG_SIZE
+    assert len(setup.fingerprint) == 32
+
+    loaded = rewards_proof.Setup.load(INCENTIVE_CATALOG_SIZE, setup.fingerprint)
+    assert loaded.fingerprint == setup.fingerprint
+    with pytest.raises(ValueError):
+        rewards_proof.Setup.load(2 * INCENTIVE_CATALOG_SIZE, setup.fingerprint)
+    with pytest.raises(ValueError):
+        rewards_proof.Setup(0)
+
+
+def test_prove_and_verify(setup, state, policy):
+    proof = rewards_proof.prove(setup, state, policy)
+    assert isinstance(proof, bytes)
+    assert rewards_proof.verify(setup, proof, policy)
+
+    other_policy = policy.copy()
+    other_policy[3] = 1
+    assert not rewards_proof.verify(setup, proof, other_policy)
+    assert not rewards_proof.verify(setup, proof + b"\x00", policy)
+
+
+def test_non_contiguous_arrays(setup):
+    state = np.array([1, 9, 0, 9, 3, 9, 2, 9], dtype=np.uint64)[::2]
+    policy = np.array([2, 5, 1, 0], dtype=np.uint64)
+    proof = rewards_proof.prove(setup, state, policy)
+    assert rewards_proof.verify(setup, proof, policy)
+
+
+def test_invalid_inputs(setup, state, policy):
+    with pytest.raises(ValueError):
+        rewards_proof.prove(setup, state[:3], policy)
+    with pytest.raises(ValueError):
+        rewards_proof.prove(setup, state, policy[:3])
+    with pytest.raises(ValueError):
+        large_state = state.copy()
+        large_state[0] = 1 << rewards_proof.STATE_ENTRY_BITSIZE
+        rewards_proof.prove(setup, large_state, policy)
+    with pytest.raises(ValueError):
+        rewards_proof.verify(setup, b"", policy[:3])
+    with pytest.raises(TypeError):
+        rewards_proof.prove(setup, state.astype(np.float64), policy)
+
+
+def test_reward_bitsize(setup, state):
+    max_reward = (1 << rewards_proof.REWARD_BITSIZE) - 1
+    policy = np.array([max_reward, 0, 0, 0], dtype=np.uint64)
+    proof = rewards_proof.prove(setup, state, policy)
+    assert rewards_proof.verify(setup, proof, policy)
+
+    policy[0] = max_reward + 1
+    with pytest.raises(ValueError):
+        rewards_proof.prove(setup, state, policy)
+
+
+def test_verify_batch(setup, policy):
+    rng = np.random.default_rng()
+    states = rng.integers(0, 10, size=(8, INCENTIVE_CATALOG_SIZE), dtype=np.uint64)
+    proofs = [rewards_proof.prove(setup, state, policy) for state in states]
+
+    assert rewards_proof.verify_batch(setup, proofs, policy) == [True] * len(proofs)
+
+    proofs[2] = b"not a proof"
+    proofs[5] = proofs[5][:-1]
+    expected = [True] * len(proofs)
+    expected[2] = expected[5] = False
+    assert rewards_proof.verify_batch(setup, proofs, policy) == expected
+    assert rewards_proof.verify_batch(setup, [], policy) == []
@@ -0,0 +1,337 @@
+//! HTTP verification service of the rewards proof
+//!
+//! The service verifies proofs in the `WireFormat` of `rewards-proof`, hex
+//! encoded in JSON requests, against the signed policies of an issuer:
+//!
+//! - `GET /params` returns the setup parameters,
+//!   `{"incentive_catalog_size": .., "fingerprint": ..}`
+//! - `GET /policy/{id}` returns the latest signed policy with the ID that is
+//!   valid now
+//! - `POST /verify` verifies `{"policy_id": .., "policy_version": .., "proof":
+//!   ..}` and returns `{"valid": ..}`, the latest valid version of the policy
+//!   is used if the version is omitted
+//! - `POST /verify/batch` verifies `{"policy_id": .., "policy_version": ..,
+//!   "proofs": [..]}` and returns `{"results": [..], "valid": .., "invalid":
+//!   ..}`
+//!
+//! Proofs that cannot be decoded are not valid. Errors are returned as
+//! `{"error": {"code": .., "message": ..}}` with a matching status code.
+
+use axum::extract::rejection::{JsonRejection, PathRejection};
+use axum::extract::{DefaultBodyLimit, Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use bulletproofs::{BulletproofGens, PedersenGens};
+use rewards_proof::api::{rewards_proof_setup, RewardsProof};
+use rewards_proof::policy::{now, IssuerPublicKey, SignedPolicy};
+use rewards_proof::verifier::{PolicyVerifier, PreparedVerifier};
+use rewards_proof::wire::WireFormat;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Request size limits of the service
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// Maximum size of a request body in bytes
+    pub max_body_size: usize,
+    /// Maximum number of proofs of a batch
+    pub max_batch_size: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_body_size: 16 << 20,
+            max_batch_size: 1024,
+        }
+    }
+}
+
+/// Prepared verifier and signed policies of the service
+pub struct ServerState {
+    verifier: PolicyVerifier,
+    wire_format: WireFormat,
+    incentive_catalog_size: u64,
+    limits: Limits,
+}
+
+impl ServerState {
+    /// Runs the setup for an incentive catalog and prepares the verifier for
+    /// policies signed by `issuer_key`
+    pub fn new(
+        incentive_catalog_size: u64,
+        issuer_key: IssuerPublicKey,
+        limits: Limits,
+    ) -> ServerState {
+        let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(incentive_catalog_size);
+        ServerState::with_setup(
+            &pedersen_gens,
+            &bulletproof_gens,
+            incentive_catalog_size,
+            issuer_key,
+            limits,
+        )
+    }
+
+    /// Prepares the verifier for the output of `rewards_proof_setup`
+    pub fn with_setup(
+        pedersen_gens: &[PedersenGens],
+        bulletproof_gens: &[BulletproofGens],
+        incentive_catalog_size: u64,
+        issuer_key: IssuerPublicKey,
+        limits: Limits,
+    ) -> ServerState {
+        ServerState {
+            verifier: PolicyVerifier::new(
+                PreparedVerifier::new(pedersen_gens, bulletproof_gens, incentive_catalog_size),
+                issuer_key,
+            ),
+            wire_format: WireFormat::new(pedersen_gens, bulletproof_gens, incentive_catalog_size),
+            incentive_catalog_size,
+            limits,
+        }
+    }
+
+    /// Registers a signed policy and prepares its policy vector, returns
+    /// `false` if the signature is invalid, the policy does not match the
+    /// catalog, or a different policy with the same ID and version is
+    /// registered
+    pub fn add_policy(&mut self, signed_policy: SignedPolicy) -> bool {
+        self.verifier.add_policy(signed_policy)
+    }
+
+    /// Returns the signed policy a proof is verified against, the latest
+    /// version that is valid now if no version is given
+    fn signed_policy(&self, id: u64, version: Option<u32>) -> Result<&SignedPolicy, ApiError> {
+        self.verifier
+            .valid_policy(id, version, now())
+            .ok_or_else(ApiError::policy_not_found)
+    }
+
+    /// Returns the ID and version of the policy a proof is verified against
+    fn policy_key(&self, id: u64, version: Option<u32>) -> Result<(u64, u32), ApiError> {
+        let policy = self.signed_policy(id, version)?.policy();
+        Ok((policy.id(), policy.version()))
+    }
+
+    /// Decodes and verifies proofs against a registered policy
+    fn verify(&self, (id, version): (u64, u32), proofs: &[Vec<u8>]) -> Result<Vec<bool>, ApiError> {
+        let decoded: Vec<Option<RewardsProof>> = proofs
+            .iter()
+            .map(|proof| self.wire_format.decode(proof))
+            .collect();
+        let decoded: Vec<Option<&RewardsProof>> = decoded.iter().map(Option::as_ref).collect();
+        self.verifier
+            .verify_batch(id, version, &decoded)
+            .ok_or_else(ApiError::policy_not_found)
+    }
+}
+
+/// Returns the router of the service
+pub fn router(state: ServerState) -> Router {
+    let max_body_size = state.limits.max_body_size;
+    Router::new()
+        .route("/params", get(params))
+        .route("/policy/:id", get(policy))
+        .route("/verify", post(verify))
+        .route("/verify/batch", post(verify_batch))
+        .fallback(not_found)
+        .layer(DefaultBodyLimit::max(max_body_size))
+        .with_state(Arc::new(state))
+}
+
+/// Setup parameters, the generators are derived from the size of the catalog
+#[derive(Serialize, Deserialize)]
+pub struct ParamsResponse {
+    pub incentive_catalog_size: u64,
+    pub fingerprint: String,
+}
+
+/// Request of `POST /verify`
+#[derive(Serialize, Deserialize)]
+pub struct VerifyRequest {
+    pub policy_id: u64,
+    #[serde(default)]
+    pub policy_version: Option<u32>,
+    /// Hex encoded proof in the wire format
+    pub proof: String,
+}
+
+/// Response of `POST /verify`
+#[derive(Serialize, Deserialize)]
+pub struct VerifyResponse {
+    pub valid: bool,
+}
+
+/// Request of `POST /verify/batch`
+#[derive(Serialize, Deserialize)]
+pub struct VerifyBatchRequest {
+    pub policy_id: u64,
+    #[serde(default)]
+    pub policy_version: Option<u32>,
+    /// Hex encoded proofs in the wire format
+    pub proofs: Vec<String>,
+}
+
+/// Response of `POST /verify/batch`
+#[derive(Serialize, Deserialize)]
+pub struct VerifyBatchResponse {
+    pub results: Vec<bool>,
+    pub valid: u64,
+    pub invalid: u64,
+}
+
+/// Error of a request, returned as `{"error": {"code": .., "message": ..}}`
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, code: &'static str, message: String) -> ApiError {
+        ApiError {
+            status,
+            code,
+            message,
+        }
+    }
+
+    fn policy_not_found() -> ApiError {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "policy_not_found",
+            "No valid policy with this ID and version".to_string(),
+        )
+    }
+
+    fn invalid_request(message: &str) -> ApiError {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            message.to_string(),
+        )
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({ "error": { "code": self.code, "message": self.message } });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> ApiError {
+        let status = rejection.status();
+        let code = if status == StatusCode::PAYLOAD_TOO_LARGE {
+            "payload_too_large"
+        } else {
+            "invalid_request"
+        };
+        ApiError::new(status, code, rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> ApiError {
+        ApiError::invalid_request(&rejection.body_text())
+    }
+}
+
+/// `GET /params`
+async fn params(State(state): State<Arc<ServerState>>) -> Json<ParamsResponse> {
+    Json(ParamsResponse {
+        incentive_catalog_size: state.incentive_catalog_size,
+        fingerprint: hex::encode(state.wire_format.fingerprint()),
+    })
+}
+
+/// `GET /policy/{id}`
+async fn policy(
+    State(state): State<Arc<ServerState>>,
+    id: Result<Path<u64>, PathRejection>,
+) -> Result<Json<SignedPolicy>, ApiError> {
+    let Path(id) = id?;
+    Ok(Json(state.signed_policy(id, None)?.clone()))
+}
+
+/// `POST /verify`
+async fn verify(
+    State(state): State<Arc<ServerState>>,
+    request: Result<Json<VerifyRequest>, JsonRejection>,
+) -> Result<Json<VerifyResponse>, ApiError> {
+    let Json(request) = request?;
+    let policy = state.policy_key(request.policy_id, request.policy_version)?;
+    let proof = decode_hex(&request.proof)?;
+
+    let results = verify_blocking(state, policy, vec![proof]).await?;
+    Ok(Json(VerifyResponse { valid: results[0] }))
+}
+
+/// `POST /verify/batch`
+async fn verify_batch(
+    State(state): State<Arc<ServerState>>,
+    request: Result<Json<VerifyBatchRequest>, JsonRejection>,
+) -> Result<Json<VerifyBatchResponse>, ApiError> {
+    let Json(request) = request?;
+    if request.proofs.len() > state.limits.max_batch_size {
+        return Err(ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "batch_too_large",
+            format!(
+                "Batch exceeds the maximum of {} proofs",
+                state.limits.max_batch_size
+            ),
+        ));
+    }
+    let policy = state.policy_key(request.policy_id, request.policy_version)?;
+    let proofs = request
+        .proofs
+        .iter()
+        .map(|proof| decode_hex(proof))
+        .collect::<Result<Vec<Vec<u8>>, ApiError>>()?;
+
+    let results = verify_blocking(state, policy, proofs).await?;
+    let valid = results.iter().filter(|valid| **valid).count() as u64;
+    Ok(Json(VerifyBatchResponse {
+        invalid: results.len() as u64 - valid,
+        valid,
+        results,
+    }))
+}
+
+/// Fallback for unknown routes
+async fn not_found() -> ApiError {
+    ApiError::new(
+        StatusCode::NOT_FOUND,
+        "not_found",
+        "Unknown endpoint".to_string(),
+    )
+}
+
+/// Verifies proofs on the blocking thread pool
+async fn verify_blocking(
+    state: Arc<ServerState>,
+    policy: (u64, u32),
+    proofs: Vec<Vec<u8>>,
+) -> Result<Vec<bool>, ApiError> {
+    tokio::task::spawn_blocking(move || state.verify(policy, &proofs))
+        .await
+        .map_err(|_| {
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Verification failed unexpectedly".to_string(),
+            )
+        })?
+}
+
+/// Decodes a hex encoded proof
+fn decode_hex(proof: &str) -> Result<Vec<u8>, ApiError> {
+    hex::decode(proof).map_err(|_| ApiError::invalid_request("Proof is not hex encoded"))
+}
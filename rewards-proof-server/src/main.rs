@@ -0,0 +1,90 @@
+//! HTTP verification service of the rewards proof, see the library for the
+//! endpoints
+
+use clap::Parser;
+use rewards_proof::policy::{IssuerPublicKey, SignedPolicy};
+use rewards_proof_server::{router, Limits, ServerState};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process;
+
+#[derive(Parser)]
+#[command(
+    name = "server",
+    version,
+    about = "HTTP verification service of the rewards proof"
+)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+    /// Size of the incentive catalog
+    #[arg(long)]
+    incentive_catalog_size: u64,
+    /// Hex encoded public key of the issuer
+    #[arg(long)]
+    issuer_key: String,
+    /// Signed policy file, can be given multiple times
+    #[arg(long = "policy")]
+    policies: Vec<PathBuf>,
+    /// Maximum size of a request body in bytes
+    #[arg(long, default_value_t = Limits::default().max_body_size)]
+    max_body_size: usize,
+    /// Maximum number of proofs of a batch
+    #[arg(long, default_value_t = Limits::default().max_batch_size)]
+    max_batch_size: usize,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let state = load_state(&args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(2);
+    });
+
+    let server = axum::Server::try_bind(&args.listen).unwrap_or_else(|error| {
+        eprintln!("{}: {}", args.listen, error);
+        process::exit(2);
+    });
+    eprintln!("Listening on {}", args.listen);
+    server
+        .serve(router(state).into_make_service())
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c()
+                .await
+                .expect("Failed to install the signal handler");
+        })
+        .await
+        .expect("Server failed");
+}
+
+/// Runs the setup and registers the signed policies
+fn load_state(args: &Args) -> Result<ServerState, String> {
+    if args.incentive_catalog_size == 0 {
+        return Err("Incentive catalog must not be empty".to_string());
+    }
+    let issuer_key = hex::decode(&args.issuer_key)
+        .ok()
+        .and_then(|bytes| IssuerPublicKey::from_bytes(&bytes))
+        .ok_or("Invalid issuer key")?;
+    let limits = Limits {
+        max_body_size: args.max_body_size,
+        max_batch_size: args.max_batch_size,
+    };
+
+    let mut state = ServerState::new(args.incentive_catalog_size, issuer_key, limits);
+    for path in args.policies.iter() {
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let signed_policy: SignedPolicy =
+            serde_json::from_slice(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if !state.add_policy(signed_policy) {
+            return Err(format!(
+                "{}: Policy is not signed by the issuer or does not match the catalog",
+                path.display()
+            ));
+        }
+    }
+    Ok(state)
+}
@@ -0,0 +1,211 @@
+//! Integration tests against a locally started instance of the service
+
+use curve25519_dalek::scalar::Scalar;
+use reqwest::StatusCode;
+use rewards_proof::api::{rewards_proof_generation_aggregated, rewards_proof_setup};
+use rewards_proof::policy::{IssuerSigningKey, Policy, SignedPolicy};
+use rewards_proof::wire::WireFormat;
+use rewards_proof_server::{
+    router, Limits, ParamsResponse, ServerState, VerifyBatchResponse, VerifyResponse,
+};
+use serde_json::{json, Value};
+use std::net::TcpListener;
+
+const INCENTIVE_CATALOG_SIZE: u64 = 4;
+const WEIGHTS: [u64; 4] = [2, 5, 1, 0];
+
+/// Instance of the service and the fixtures of a test
+struct TestServer {
+    url: String,
+    client: reqwest::Client,
+    signed_policy: SignedPolicy,
+    fingerprint: String,
+    proofs: Vec<String>,
+}
+
+/// Starts the service on a local port with one signed policy (ID 1) and
+/// generates valid proofs for it
+async fn start(limits: Limits, number_of_proofs: usize) -> TestServer {
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(INCENTIVE_CATALOG_SIZE);
+    let wire_format = WireFormat::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let signing_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let policy = Policy::new(1, 1, WEIGHTS.to_vec(), 0, u64::MAX).unwrap();
+    let signed_policy = signing_key.sign(&policy);
+
+    let mut state = ServerState::with_setup(
+        &pedersen_gens,
+        &bulletproof_gens,
+        INCENTIVE_CATALOG_SIZE,
+        signing_key.public_key(),
+        limits,
+    );
+    assert!(state.add_policy(signed_policy.clone()));
+    // a policy of another issuer is rejected
+    let other_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    assert!(!state.add_policy(other_key.sign(&policy)));
+
+    let proofs = (0..number_of_proofs as u64)
+        .map(|i| {
+            let state = [i, 0, 3, 2];
+            let reward = state.iter().zip(WEIGHTS.iter()).map(|(x, y)| x * y).sum();
+            let proof = rewards_proof_generation_aggregated(
+                &pedersen_gens,
+                &bulletproof_gens,
+                &[reward],
+                state.iter().map(|count| Scalar::from(*count)).collect(),
+                policy.policy_vector(),
+                INCENTIVE_CATALOG_SIZE,
+                None,
+                None,
+            )
+            .unwrap();
+            hex::encode(wire_format.encode(&proof).unwrap())
+        })
+        .collect();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = axum::Server::from_tcp(listener)
+        .unwrap()
+        .serve(router(state).into_make_service());
+    tokio::spawn(server);
+
+    TestServer {
+        url,
+        client: reqwest::Client::new(),
+        signed_policy,
+        fingerprint: hex::encode(wire_format.fingerprint()),
+        proofs,
+    }
+}
+
+impl TestServer {
+    async fn get(&self, path: &str) -> (StatusCode, Value) {
+        let response = self
+            .client
+            .get(format!("{}{}", self.url, path))
+            .send()
+            .await
+            .unwrap();
+        (response.status(), response.json().await.unwrap())
+    }
+
+    async fn post(&self, path: &str, body: &Value) -> (StatusCode, Value) {
+        let response = self
+            .client
+            .post(format!("{}{}", self.url, path))
+            .json(body)
+            .send()
+            .await
+            .unwrap();
+        (response.status(), response.json().await.unwrap())
+    }
+}
+
+/// Returns the result of a verify response
+fn is_valid(body: Value) -> bool {
+    serde_json::from_value::<VerifyResponse>(body)
+        .unwrap()
+        .valid
+}
+
+/// Returns the error code of an error response
+fn error_code(body: &Value) -> &str {
+    body["error"]["code"].as_str().unwrap()
+}
+
+#[tokio::test]
+async fn get_params() {
+    let server = start(Limits::default(), 0).await;
+    let (status, body) = server.get("/params").await;
+    assert_eq!(status, StatusCode::OK);
+    let params: ParamsResponse = serde_json::from_value(body).unwrap();
+    assert_eq!(params.incentive_catalog_size, INCENTIVE_CATALOG_SIZE);
+    assert_eq!(params.fingerprint, server.fingerprint);
+}
+
+#[tokio::test]
+async fn get_policy() {
+    let server = start(Limits::default(), 0).await;
+    let (status, body) = server.get("/policy/1").await;
+    assert_eq!(status, StatusCode::OK);
+    let signed_policy: SignedPolicy = serde_json::from_value(body).unwrap();
+    assert_eq!(signed_policy, server.signed_policy);
+
+    let (status, body) = server.get("/policy/2").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error_code(&body), "policy_not_found");
+
+    let (status, body) = server.get("/policy/latest").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error_code(&body), "invalid_request");
+
+    let (status, body) = server.get("/unknown").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error_code(&body), "not_found");
+}
+
+#[tokio::test]
+async fn verify() {
+    let server = start(Limits::default(), 1).await;
+    let request = json!({ "policy_id": 1, "proof": server.proofs[0] });
+    let (status, body) = server.post("/verify", &request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(is_valid(body));
+
+    let request = json!({ "policy_id": 1, "policy_version": 1, "proof": server.proofs[0] });
+    let (_, body) = server.post("/verify", &request).await;
+    assert!(is_valid(body));
+
+    // trailing bytes are not canonical
+    let request = json!({ "policy_id": 1, "proof": format!("{}00", server.proofs[0]) });
+    let (status, body) = server.post("/verify", &request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!is_valid(body));
+
+    let request = json!({ "policy_id": 1, "policy_version": 2, "proof": server.proofs[0] });
+    let (status, body) = server.post("/verify", &request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error_code(&body), "policy_not_found");
+
+    let request = json!({ "policy_id": 1, "proof": "not hex" });
+    let (status, body) = server.post("/verify", &request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error_code(&body), "invalid_request");
+
+    let (status, body) = server.post("/verify", &json!({ "proof": "00" })).await;
+    assert!(status.is_client_error());
+    assert_eq!(error_code(&body), "invalid_request");
+}
+
+#[tokio::test]
+async fn verify_batch() {
+    let server = start(Limits::default(), 3).await;
+    let mut proofs = server.proofs.clone();
+    proofs.push("00".to_string());
+    let request = json!({ "policy_id": 1, "proofs": proofs });
+    let (status, body) = server.post("/verify/batch", &request).await;
+    assert_eq!(status, StatusCode::OK);
+    let response: VerifyBatchResponse = serde_json::from_value(body).unwrap();
+    assert_eq!(response.results, vec![true, true, true, false]);
+    assert_eq!((response.valid, response.invalid), (3, 1));
+}
+
+#[tokio::test]
+async fn request_limits() {
+    let limits = Limits {
+        max_body_size: 64 << 10,
+        max_batch_size: 2,
+    };
+    let server = start(limits, 3).await;
+
+    let request = json!({ "policy_id": 1, "proofs": server.proofs });
+    let (status, body) = server.post("/verify/batch", &request).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(error_code(&body), "batch_too_large");
+
+    let request = json!({ "policy_id": 1, "proof": "00".repeat(64 << 10) });
+    let (status, body) = server.post("/verify", &request).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(error_code(&body), "payload_too_large");
+}
@@ -0,0 +1,30 @@
+//! Program entrypoint, with a bump allocator over the requested heap frame
+
+use crate::processor::process_instruction;
+use solana_program::entrypoint::{deserialize, SUCCESS};
+
+/// Deserializes the input of the runtime and processes the instruction
+///
+/// # Safety
+///
+/// `input` must be the serialized input of the runtime.
+#[no_mangle]
+pub unsafe extern "C" fn entrypoint(input: *mut u8) -> u64 {
+    let (program_id, accounts, instruction_data) = deserialize(input);
+    match process_instruction(program_id, &accounts, instruction_data) {
+        Ok(()) => SUCCESS,
+        Err(error) => error.into(),
+    }
+}
+
+/// The default allocator of `solana-program` only uses the default heap of
+/// 32 KiB, the setup of the verifier needs the full `HEAP_FRAME_SIZE`
+#[cfg(target_os = "solana")]
+#[global_allocator]
+static ALLOCATOR: solana_program::entrypoint::BumpAllocator =
+    solana_program::entrypoint::BumpAllocator {
+        start: solana_program::entrypoint::HEAP_START_ADDRESS as usize,
+        len: crate::HEAP_FRAME_SIZE as usize,
+    };
+
+solana_program::custom_panic_default!();
@@ -0,0 +1,138 @@
+//! Instructions of the program and their encoding
+//!
+//! An instruction is encoded as a tag byte followed by its fields, integers
+//! are little endian.
+
+use core::convert::TryInto;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+
+/// Instructions of the program
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RewardsProofInstruction {
+    /// Initializes a proof buffer, which must be allocated with `buffer_size`
+    /// bytes and assigned to the program
+    ///
+    /// Accounts: `[writable]` proof buffer, `[signer]` authority
+    InitializeBuffer,
+    /// Writes bytes of the wire encoded proof at `offset`
+    ///
+    /// Accounts: `[writable]` proof buffer, `[signer]` authority
+    Write { offset: u32, data: Vec<u8> },
+    /// Verifies the proof of a buffer against a policy vector
+    ///
+    /// Accounts: `[writable]` proof buffer
+    Verify { policy_vector: Vec<u64> },
+    /// Closes a proof buffer and transfers its lamports to the recipient
+    ///
+    /// Accounts: `[writable]` proof buffer, `[signer]` authority, `[writable]`
+    /// recipient
+    Close,
+}
+
+impl RewardsProofInstruction {
+    /// Encodes the instruction
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            RewardsProofInstruction::InitializeBuffer => buf.push(0),
+            RewardsProofInstruction::Write { offset, data } => {
+                buf.push(1);
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            RewardsProofInstruction::Verify { policy_vector } => {
+                buf.push(2);
+                for weight in policy_vector.iter() {
+                    buf.extend_from_slice(&weight.to_le_bytes());
+                }
+            }
+            RewardsProofInstruction::Close => buf.push(3),
+        }
+        buf
+    }
+
+    /// Decodes an instruction, returns `None` if the encoding is invalid
+    pub fn unpack(input: &[u8]) -> Option<RewardsProofInstruction> {
+        let (tag, rest) = input.split_first()?;
+        match tag {
+            0 if rest.is_empty() => Some(RewardsProofInstruction::InitializeBuffer),
+            1 if rest.len() >= 4 => Some(RewardsProofInstruction::Write {
+                offset: u32::from_le_bytes(rest[..4].try_into().ok()?),
+                data: rest[4..].to_vec(),
+            }),
+            2 if rest.len() % 8 == 0 => Some(RewardsProofInstruction::Verify {
+                policy_vector: rest
+                    .chunks(8)
+                    .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+                    .collect(),
+            }),
+            3 if rest.is_empty() => Some(RewardsProofInstruction::Close),
+            _ => None,
+        }
+    }
+}
+
+/// Creates an `InitializeBuffer` instruction
+pub fn initialize_buffer(program_id: &Pubkey, buffer: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*buffer, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: RewardsProofInstruction::InitializeBuffer.pack(),
+    }
+}
+
+/// Creates a `Write` instruction
+pub fn write(
+    program_id: &Pubkey,
+    buffer: &Pubkey,
+    authority: &Pubkey,
+    offset: u32,
+    data: &[u8],
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*buffer, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: RewardsProofInstruction::Write {
+            offset,
+            data: data.to_vec(),
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `Verify` instruction
+pub fn verify(program_id: &Pubkey, buffer: &Pubkey, policy_vector: &[u64]) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*buffer, false)],
+        data: RewardsProofInstruction::Verify {
+            policy_vector: policy_vector.to_vec(),
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `Close` instruction
+pub fn close(
+    program_id: &Pubkey,
+    buffer: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*buffer, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false),
+        ],
+        data: RewardsProofInstruction::Close.pack(),
+    }
+}
@@ -0,0 +1,42 @@
+//! Solana program that verifies rewards proofs on-chain
+//!
+//! A proof in the `WireFormat` of `rewards-proof` does not fit into a single
+//! transaction, so it is uploaded into a proof buffer account owned by the
+//! program with `Write` instructions of at most `MAX_WRITE_SIZE` bytes. The
+//! `Verify` instruction then verifies the buffer against a policy vector and
+//! stores the result and a hash of the policy vector in the buffer, see
+//! `state.rs`.
+//!
+//! Verification runs the setup for the catalog, sized to a proof of a single
+//! value, and needs a larger heap than the default: a `Verify` transaction
+//! must request a heap frame of `HEAP_FRAME_SIZE` bytes and should request the
+//! compute units it needs, e.g. with
+//! `ComputeBudgetInstruction::set_compute_unit_limit`. The program code only
+//! uses `core` and `alloc`.
+
+pub mod instruction;
+pub mod processor;
+pub mod state;
+
+#[cfg(not(feature = "no-entrypoint"))]
+mod entrypoint;
+
+solana_program::declare_id!("BhQdhmQSVKJmo6sifK1UZSSHyirczqiQEf5uquuKTVdz");
+
+/// Size of the heap frame a `Verify` transaction must request, the maximum of
+/// the runtime
+pub const HEAP_FRAME_SIZE: u32 = 256 * 1024;
+
+/// Maximum number of compute units of a transaction
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Maximum size of the incentive catalog
+///
+/// The generators of the range proof over the state grow linearly with the
+/// catalog, and the setup and verification must fit into the heap frame and
+/// the compute budget of a transaction.
+pub const MAX_INCENTIVE_CATALOG_SIZE: usize = 8;
+
+/// Maximum number of proof bytes of a `Write` instruction that fit into a
+/// transaction
+pub const MAX_WRITE_SIZE: usize = 900;
@@ -0,0 +1,209 @@
+//! Processing of the instructions
+
+use crate::instruction::RewardsProofInstruction;
+use crate::state::{self, VerificationStatus, BUFFER_HEADER_SIZE};
+use crate::MAX_INCENTIVE_CATALOG_SIZE;
+use curve25519_dalek::scalar::Scalar;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rewards_proof::api::{
+    rewards_proof_verification_aggregated_with_rng, rewards_proof_verification_setup,
+};
+use rewards_proof::wire::{WireFormat, WIRE_MAGIC, WIRE_VERSION};
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::hash::hashv;
+use solana_program::log::sol_log_compute_units;
+use solana_program::msg;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+/// Processes an instruction of the program
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = RewardsProofInstruction::unpack(instruction_data)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    match instruction {
+        RewardsProofInstruction::InitializeBuffer => {
+            process_initialize_buffer(program_id, accounts)
+        }
+        RewardsProofInstruction::Write { offset, data } => {
+            process_write(program_id, accounts, offset as usize, &data)
+        }
+        RewardsProofInstruction::Verify { policy_vector } => {
+            process_verify(program_id, accounts, &policy_vector)
+        }
+        RewardsProofInstruction::Close => process_close(program_id, accounts),
+    }
+}
+
+fn process_initialize_buffer(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let buffer = next_account_info(accounts)?;
+    let authority = next_account_info(accounts)?;
+    check_buffer(program_id, buffer)?;
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut data = buffer.try_borrow_mut_data()?;
+    if state::authority(&data) != Pubkey::default() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    state::set_authority(&mut data, authority.key);
+    state::set_status(&mut data, VerificationStatus::Pending, &Default::default());
+    Ok(())
+}
+
+fn process_write(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    offset: usize,
+    bytes: &[u8],
+) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let buffer = next_account_info(accounts)?;
+    let authority = next_account_info(accounts)?;
+    check_buffer(program_id, buffer)?;
+
+    let mut data = buffer.try_borrow_mut_data()?;
+    check_authority(&data, authority)?;
+    let start = BUFFER_HEADER_SIZE + offset;
+    let end = start
+        .checked_add(bytes.len())
+        .filter(|end| *end <= data.len())
+        .ok_or(ProgramError::AccountDataTooSmall)?;
+    data[start..end].copy_from_slice(bytes);
+    state::set_status(&mut data, VerificationStatus::Pending, &Default::default());
+    Ok(())
+}
+
+fn process_verify(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    policy_vector: &[u64],
+) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let buffer = next_account_info(accounts)?;
+    check_buffer(program_id, buffer)?;
+    if policy_vector.is_empty() || policy_vector.len() > MAX_INCENTIVE_CATALOG_SIZE {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut data = buffer.try_borrow_mut_data()?;
+    if state::authority(&data) == Pubkey::default() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let valid = verify(state::proof(&data), policy_vector);
+    let status = if valid {
+        VerificationStatus::Valid
+    } else {
+        VerificationStatus::Invalid
+    };
+    msg!("Rewards proof: {:?}", status);
+    state::set_status(&mut data, status, &state::policy_hash(policy_vector));
+    Ok(())
+}
+
+fn process_close(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts = &mut accounts.iter();
+    let buffer = next_account_info(accounts)?;
+    let authority = next_account_info(accounts)?;
+    let recipient = next_account_info(accounts)?;
+    check_buffer(program_id, buffer)?;
+
+    let mut data = buffer.try_borrow_mut_data()?;
+    check_authority(&data, authority)?;
+    data.fill(0);
+
+    let lamports = buffer.lamports();
+    **buffer.try_borrow_mut_lamports()? = 0;
+    **recipient.try_borrow_mut_lamports()? = recipient
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidArgument)?;
+    Ok(())
+}
+
+/// Verifies a wire encoded proof of a single value against a policy vector
+///
+/// The setup only contains the generators to verify a proof of a single
+/// value, see `rewards_proof_verification_setup`. The compute units remaining after the setup, the decoding and the
+/// verification are logged, see the compute unit report of the tests.
+fn verify(proof: &[u8], policy_vector: &[u64]) -> bool {
+    // reject other encodings before the expensive setup
+    if proof.len() < WIRE_MAGIC.len() + 1
+        || proof[..WIRE_MAGIC.len()] != WIRE_MAGIC[..]
+        || proof[WIRE_MAGIC.len()] != WIRE_VERSION
+    {
+        return false;
+    }
+
+    let n = policy_vector.len() as u64;
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_verification_setup(n, 1);
+    log_stage("setup");
+    let wire_format = WireFormat::for_verification_setup(&pedersen_gens, &bulletproof_gens, n);
+    let rewards_proof = match wire_format.decode(proof) {
+        Some(rewards_proof) => rewards_proof,
+        None => return false,
+    };
+    log_stage("decode");
+
+    // There is no secret randomness on-chain, the random weights of the
+    // verifier are derived from the proof and the policy vector, which are
+    // fixed before the prover learns the weights
+    let policy_hash = state::policy_hash(policy_vector);
+    let seed = hashv(&[b"rewards proof verifier", policy_hash.as_ref(), proof]);
+    let mut rng = ChaCha20Rng::from_seed(seed.to_bytes());
+    let public_value: Vec<Scalar> = policy_vector
+        .iter()
+        .map(|weight| Scalar::from(*weight))
+        .collect();
+    let valid = rewards_proof_verification_aggregated_with_rng(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &rewards_proof,
+        &public_value,
+        None,
+        None,
+        &mut rng,
+    );
+    log_stage("verify");
+    valid
+}
+
+/// Logs the remaining compute units after a stage of the verification
+fn log_stage(stage: &str) {
+    msg!("Rewards proof stage: {}", stage);
+    sol_log_compute_units();
+}
+
+/// Checks that the proof buffer is owned by the program and large enough for
+/// the header
+fn check_buffer(program_id: &Pubkey, buffer: &AccountInfo) -> ProgramResult {
+    if buffer.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !buffer.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if buffer.data_len() < BUFFER_HEADER_SIZE {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    Ok(())
+}
+
+/// Checks that the authority of an initialized proof buffer signed
+fn check_authority(data: &[u8], authority: &AccountInfo) -> ProgramResult {
+    let expected = state::authority(data);
+    if expected == Pubkey::default() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if !authority.is_signer || *authority.key != expected {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Layout of the proof buffer account
+//!
+//! ```text
+//! authority (32 bytes) || status (1 byte) || policy hash (32 bytes)
+//!   || wire encoded proof
+//! ```
+//!
+//! The policy hash is the hash of the policy vector the proof was verified
+//! against, see `policy_hash`. Writing to the buffer resets the status.
+
+use core::convert::TryInto;
+use solana_program::hash::{hashv, Hash};
+use solana_program::pubkey::Pubkey;
+
+/// Size of the header of a proof buffer
+pub const BUFFER_HEADER_SIZE: usize = 32 + 1 + 32;
+
+/// Offset of the status in a proof buffer
+const STATUS_OFFSET: usize = 32;
+/// Offset of the policy hash in a proof buffer
+const POLICY_HASH_OFFSET: usize = 33;
+
+/// Verification status of a proof buffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum VerificationStatus {
+    /// The proof was not verified since the last write
+    Pending = 0,
+    Valid = 1,
+    Invalid = 2,
+}
+
+/// Returns the size of a proof buffer for a wire encoded proof of `proof_len`
+/// bytes
+pub fn buffer_size(proof_len: usize) -> usize {
+    BUFFER_HEADER_SIZE + proof_len
+}
+
+/// Returns the authority of a proof buffer, the default public key if the
+/// buffer is not initialized
+pub fn authority(data: &[u8]) -> Pubkey {
+    Pubkey::new_from_array(
+        data[..32]
+            .try_into()
+            .expect("Buffer is smaller than the header"),
+    )
+}
+
+/// Sets the authority of a proof buffer
+pub(crate) fn set_authority(data: &mut [u8], authority: &Pubkey) {
+    data[..32].copy_from_slice(authority.as_ref());
+}
+
+/// Returns the verification status of a proof buffer, `None` if the status is
+/// unknown
+pub fn status(data: &[u8]) -> Option<VerificationStatus> {
+    match data[STATUS_OFFSET] {
+        0 => Some(VerificationStatus::Pending),
+        1 => Some(VerificationStatus::Valid),
+        2 => Some(VerificationStatus::Invalid),
+        _ => None,
+    }
+}
+
+/// Returns the hash of the policy vector the proof was verified against
+pub fn verified_policy_hash(data: &[u8]) -> Hash {
+    Hash::new(&data[POLICY_HASH_OFFSET..BUFFER_HEADER_SIZE])
+}
+
+/// Sets the verification status and the hash of the policy vector
+pub(crate) fn set_status(data: &mut [u8], status: VerificationStatus, policy_hash: &Hash) {
+    data[STATUS_OFFSET] = status as u8;
+    data[POLICY_HASH_OFFSET..BUFFER_HEADER_SIZE].copy_from_slice(policy_hash.as_ref());
+}
+
+/// Returns the wire encoded proof of a proof buffer
+pub fn proof(data: &[u8]) -> &[u8] {
+    &data[BUFFER_HEADER_SIZE..]
+}
+
+/// Returns the hash of a policy vector, the weights are hashed as 64-bit
+/// little endian integers
+pub fn policy_hash(policy_vector: &[u64]) -> Hash {
+    let bytes: Vec<u8> = policy_vector
+        .iter()
+        .flat_map(|weight| weight.to_le_bytes().to_vec())
+        .collect();
+    hashv(&[b"rewards proof policy vector", &bytes])
+}
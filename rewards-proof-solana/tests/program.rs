@@ -0,0 +1,392 @@
+//! Tests of the program with `solana-program-test`, without a cluster
+//!
+//! The tests process the program natively. With `cargo test-sbf`, the
+//! compute unit report runs the SBF build of the program with the compute
+//! budget of a transaction and prints the compute units consumed per catalog
+//! size:
+//!
+//! ```text
+//! cargo test-sbf -- compute_unit_report --nocapture
+//! ```
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use rewards_proof::api::{
+    rewards_proof_generation_aggregated, rewards_proof_setup, rewards_proof_verification_setup,
+};
+use rewards_proof::wire::WireFormat;
+use rewards_proof_solana::processor::process_instruction;
+use rewards_proof_solana::state::{self, VerificationStatus};
+use rewards_proof_solana::{
+    instruction, HEAP_FRAME_SIZE, MAX_INCENTIVE_CATALOG_SIZE, MAX_WRITE_SIZE,
+};
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+const WEIGHTS: [u64; 4] = [2, 5, 1, 0];
+const STATE: [u64; 4] = [1, 0, 3, 2];
+
+/// Client of a local bank with the program
+struct TestClient {
+    banks_client: BanksClient,
+    payer: Keypair,
+}
+
+/// Starts a local bank with the native program
+async fn start() -> TestClient {
+    let mut program_test = ProgramTest::new(
+        "rewards_proof_solana",
+        rewards_proof_solana::id(),
+        processor!(process_instruction),
+    );
+    // also natively under `cargo test-sbf`
+    program_test.prefer_bpf(false);
+    start_with(program_test).await
+}
+
+async fn start_with(program_test: ProgramTest) -> TestClient {
+    let (banks_client, payer, _) = program_test.start().await;
+    TestClient {
+        banks_client,
+        payer,
+    }
+}
+
+impl TestClient {
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), solana_program_test::BanksClientError> {
+        let recent_blockhash = self.banks_client.get_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            recent_blockhash,
+        );
+        self.banks_client.process_transaction(transaction).await
+    }
+
+    /// Creates and initializes a proof buffer and uploads the proof
+    async fn upload(&mut self, authority: &Keypair, proof: &[u8]) -> Keypair {
+        let buffer = Keypair::new();
+        let space = state::buffer_size(proof.len());
+        let rent = self.banks_client.get_rent().await.unwrap();
+        let create = system_instruction::create_account(
+            &self.payer.pubkey(),
+            &buffer.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &rewards_proof_solana::id(),
+        );
+        let initialize = instruction::initialize_buffer(
+            &rewards_proof_solana::id(),
+            &buffer.pubkey(),
+            &authority.pubkey(),
+        );
+        self.process(&[create, initialize], &[&buffer, authority])
+            .await
+            .unwrap();
+
+        for (i, chunk) in proof.chunks(MAX_WRITE_SIZE).enumerate() {
+            let write = instruction::write(
+                &rewards_proof_solana::id(),
+                &buffer.pubkey(),
+                &authority.pubkey(),
+                (i * MAX_WRITE_SIZE) as u32,
+                chunk,
+            );
+            self.process(&[write], &[authority]).await.unwrap();
+        }
+        buffer
+    }
+
+    /// Verifies the proof of a buffer and returns the status
+    async fn verify(&mut self, buffer: &Keypair, policy_vector: &[u64]) -> VerificationStatus {
+        let verify =
+            instruction::verify(&rewards_proof_solana::id(), &buffer.pubkey(), policy_vector);
+        self.process(
+            &[
+                ComputeBudgetInstruction::request_heap_frame(rewards_proof_solana::HEAP_FRAME_SIZE),
+                verify,
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        let data = self.buffer_data(buffer).await;
+        assert_eq!(
+            state::verified_policy_hash(&data),
+            state::policy_hash(policy_vector)
+        );
+        state::status(&data).unwrap()
+    }
+
+    async fn buffer_data(&mut self, buffer: &Keypair) -> Vec<u8> {
+        self.banks_client
+            .get_account(buffer.pubkey())
+            .await
+            .unwrap()
+            .unwrap()
+            .data
+    }
+}
+
+/// Generates a wire encoded proof for a state and policy vector
+fn wire_proof(state: &[u64], policy_vector: &[u64]) -> Vec<u8> {
+    let n = policy_vector.len() as u64;
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(n);
+    let reward = state
+        .iter()
+        .zip(policy_vector.iter())
+        .map(|(x, y)| x * y)
+        .sum();
+    let proof = rewards_proof_generation_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &[reward],
+        state.iter().map(|count| Scalar::from(*count)).collect(),
+        policy_vector
+            .iter()
+            .map(|weight| Scalar::from(*weight))
+            .collect(),
+        n,
+        None,
+        None,
+    )
+    .unwrap();
+    WireFormat::new(&pedersen_gens, &bulletproof_gens, n)
+        .encode(&proof)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn verify_proof() {
+    let mut client = start().await;
+    let authority = Keypair::new();
+    let proof = wire_proof(&STATE, &WEIGHTS);
+    let buffer = client.upload(&authority, &proof).await;
+    let data = client.buffer_data(&buffer).await;
+    assert_eq!(state::authority(&data), authority.pubkey());
+    assert_eq!(state::status(&data), Some(VerificationStatus::Pending));
+    assert_eq!(state::proof(&data), &proof[..]);
+
+    assert_eq!(
+        client.verify(&buffer, &WEIGHTS).await,
+        VerificationStatus::Valid
+    );
+    // a different policy vector
+    assert_eq!(
+        client.verify(&buffer, &[2, 5, 1, 1]).await,
+        VerificationStatus::Invalid
+    );
+    // a different catalog
+    assert_eq!(
+        client.verify(&buffer, &[2, 5, 1]).await,
+        VerificationStatus::Invalid
+    );
+
+    // writing resets the status
+    let write = instruction::write(
+        &rewards_proof_solana::id(),
+        &buffer.pubkey(),
+        &authority.pubkey(),
+        0,
+        &proof[..1],
+    );
+    client.process(&[write], &[&authority]).await.unwrap();
+    let data = client.buffer_data(&buffer).await;
+    assert_eq!(state::status(&data), Some(VerificationStatus::Pending));
+
+    let mut tampered = proof.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    let buffer = client.upload(&authority, &tampered).await;
+    assert_eq!(
+        client.verify(&buffer, &WEIGHTS).await,
+        VerificationStatus::Invalid
+    );
+}
+
+#[tokio::test]
+async fn reject_large_catalog() {
+    let mut client = start().await;
+    let authority = Keypair::new();
+    let n = MAX_INCENTIVE_CATALOG_SIZE + 1;
+    let proof = wire_proof(&vec![1; n], &vec![1; n]);
+    let buffer = client.upload(&authority, &proof).await;
+    let verify = instruction::verify(&rewards_proof_solana::id(), &buffer.pubkey(), &vec![1; n]);
+    assert!(client.process(&[verify], &[]).await.is_err());
+}
+
+/// The generators of the on-chain setup for the largest catalog take at most
+/// half of the heap frame
+///
+/// The bump allocator of the program never frees memory, the rest of the heap
+/// frame is left for growing the generator vectors, the decoding and the
+/// verification. This only estimates the size of the points, the heap usage of
+/// the SBF build is not measured.
+#[test]
+fn setup_fits_heap_frame() {
+    let (pedersen_gens, bulletproof_gens) =
+        rewards_proof_verification_setup(MAX_INCENTIVE_CATALOG_SIZE as u64, 1);
+    let points = 2 * pedersen_gens.len()
+        + bulletproof_gens
+            .iter()
+            .map(|bp_gen| 2 * bp_gen.gens_capacity * bp_gen.party_capacity)
+            .sum::<usize>();
+    let size = points * std::mem::size_of::<RistrettoPoint>();
+    assert!(size <= HEAP_FRAME_SIZE as usize / 2, "{} bytes", size);
+
+    // the full setup does not fit
+    let (_, bulletproof_gens) = rewards_proof_setup(MAX_INCENTIVE_CATALOG_SIZE as u64);
+    let points: usize = bulletproof_gens
+        .iter()
+        .map(|bp_gen| 2 * bp_gen.gens_capacity * bp_gen.party_capacity)
+        .sum();
+    assert!(points * std::mem::size_of::<RistrettoPoint>() > HEAP_FRAME_SIZE as usize / 2);
+}
+
+#[tokio::test]
+async fn buffer_authority() {
+    let mut client = start().await;
+    let authority = Keypair::new();
+    let proof = wire_proof(&STATE, &WEIGHTS);
+    let buffer = client.upload(&authority, &proof).await;
+
+    // only the authority can write and close
+    let other = Keypair::new();
+    let write = instruction::write(
+        &rewards_proof_solana::id(),
+        &buffer.pubkey(),
+        &other.pubkey(),
+        0,
+        &[0],
+    );
+    assert!(client.process(&[write], &[&other]).await.is_err());
+    let initialize = instruction::initialize_buffer(
+        &rewards_proof_solana::id(),
+        &buffer.pubkey(),
+        &other.pubkey(),
+    );
+    assert!(client.process(&[initialize], &[&other]).await.is_err());
+
+    // writes must fit into the buffer
+    let write = instruction::write(
+        &rewards_proof_solana::id(),
+        &buffer.pubkey(),
+        &authority.pubkey(),
+        proof.len() as u32,
+        &[0],
+    );
+    assert!(client.process(&[write], &[&authority]).await.is_err());
+
+    let recipient = Keypair::new();
+    let close = instruction::close(
+        &rewards_proof_solana::id(),
+        &buffer.pubkey(),
+        &authority.pubkey(),
+        &recipient.pubkey(),
+    );
+    client.process(&[close], &[&authority]).await.unwrap();
+    assert!(client
+        .banks_client
+        .get_account(buffer.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+    let balance = client.banks_client.get_balance(recipient.pubkey()).await;
+    assert!(balance.unwrap() > 0);
+}
+
+/// Catalog sizes of the compute unit report
+#[cfg(feature = "test-sbf")]
+const REPORT_CATALOG_SIZES: [usize; 4] = [1, 2, 4, 8];
+
+/// Prints the compute units consumed by `Verify` per catalog size, within the
+/// compute budget of a single transaction
+///
+/// The program logs the remaining compute units after every stage, so the
+/// report shows how far verification gets if it exceeds the budget.
+#[cfg(feature = "test-sbf")]
+#[tokio::test]
+async fn compute_unit_report() {
+    use rewards_proof_solana::{HEAP_FRAME_SIZE, MAX_COMPUTE_UNITS};
+
+    let mut program_test =
+        ProgramTest::new("rewards_proof_solana", rewards_proof_solana::id(), None);
+    program_test.prefer_bpf(true);
+    let mut client = start_with(program_test).await;
+    let authority = Keypair::new();
+
+    println!("| catalog size | proof bytes | setup | decode | verify | result |");
+    println!("|---|---|---|---|---|---|");
+    for n in REPORT_CATALOG_SIZES.iter() {
+        let proof = wire_proof(&vec![1; *n], &vec![1; *n]);
+        let buffer = client.upload(&authority, &proof).await;
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNITS),
+            ComputeBudgetInstruction::request_heap_frame(HEAP_FRAME_SIZE),
+            instruction::verify(&rewards_proof_solana::id(), &buffer.pubkey(), &vec![1; *n]),
+        ];
+        let recent_blockhash = client.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&client.payer.pubkey()),
+            &[&client.payer],
+            recent_blockhash,
+        );
+        let simulation = client
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        let details = simulation.simulation_details.unwrap();
+        let result = match simulation.result.unwrap() {
+            Ok(())
+                if details
+                    .logs
+                    .iter()
+                    .any(|log| log.ends_with("Rewards proof: Valid")) =>
+            {
+                "valid".to_string()
+            }
+            Ok(()) => "invalid".to_string(),
+            Err(error) => error.to_string(),
+        };
+        let stages: Vec<String> = ["setup", "decode", "verify"]
+            .iter()
+            .map(|stage| match stage_units(&details.logs, stage) {
+                Some(units) => units.to_string(),
+                None => "-".to_string(),
+            })
+            .collect();
+        println!(
+            "| {} | {} | {} | {} | {} | {} |",
+            n,
+            proof.len(),
+            stages[0],
+            stages[1],
+            stages[2],
+            result
+        );
+    }
+}
+
+/// Returns the compute units consumed when a stage of the verification was
+/// completed, from the logs of the program
+#[cfg(feature = "test-sbf")]
+fn stage_units(logs: &[String], stage: &str) -> Option<u64> {
+    let marker = format!("Rewards proof stage: {}", stage);
+    let position = logs.iter().position(|log| log.ends_with(&marker))?;
+    let remaining: u64 = logs[position + 1]
+        .split_whitespace()
+        .find_map(|word| word.parse().ok())?;
+    Some(rewards_proof_solana::MAX_COMPUTE_UNITS as u64 - remaining)
+}
@@ -0,0 +1,185 @@
+//! WebAssembly bindings for client-side proof generation
+//!
+//! Built without the `avx2_backend` feature of `rewards-proof`, so that the
+//! crate compiles to `wasm32-unknown-unknown`. Proofs are serialized with the
+//! `WireFormat` of `rewards-proof` or as JSON.
+
+use bulletproofs::{BulletproofGens, PedersenGens};
+use rewards_proof::api::{
+    rewards_proof_generation_aggregated, rewards_proof_generation_signed, rewards_proof_setup,
+    to_scalars, RewardsProof,
+};
+use rewards_proof::policy::{IssuerPublicKey, Policy, SignedPolicy};
+use rewards_proof::wire::WireFormat;
+use wasm_bindgen::prelude::*;
+
+/// Setup parameters for an incentive catalog
+#[wasm_bindgen]
+pub struct Setup {
+    pedersen_gens: Vec<PedersenGens>,
+    bulletproof_gens: Vec<BulletproofGens>,
+    wire_format: WireFormat,
+    incentive_catalog_size: u32,
+}
+
+#[wasm_bindgen]
+impl Setup {
+    /// Runs the setup for an incentive catalog
+    #[wasm_bindgen(constructor)]
+    pub fn new(incentive_catalog_size: u32) -> Setup {
+        let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(incentive_catalog_size as u64);
+        let wire_format = WireFormat::new(
+            &pedersen_gens,
+            &bulletproof_gens,
+            incentive_catalog_size as u64,
+        );
+        Setup {
+            pedersen_gens,
+            bulletproof_gens,
+            wire_format,
+            incentive_catalog_size,
+        }
+    }
+
+    /// Loads the setup published by the verifier, fails if the fingerprint
+    /// does not match the derived generators
+    pub fn load(incentive_catalog_size: u32, fingerprint: &[u8]) -> Result<Setup, JsError> {
+        let setup = Setup::new(incentive_catalog_size);
+        if setup.wire_format.fingerprint()[..] != *fingerprint {
+            return Err(JsError::new("Fingerprint does not match the setup"));
+        }
+        Ok(setup)
+    }
+
+    /// Returns the fingerprint of the setup parameters
+    pub fn fingerprint(&self) -> Vec<u8> {
+        self.wire_format.fingerprint().to_vec()
+    }
+
+    /// Returns the size of the incentive catalog
+    #[wasm_bindgen(getter, js_name = incentiveCatalogSize)]
+    pub fn incentive_catalog_size(&self) -> u32 {
+        self.incentive_catalog_size
+    }
+}
+
+/// Rewards proof generated by the client
+#[wasm_bindgen]
+pub struct Proof {
+    proof: RewardsProof,
+}
+
+#[wasm_bindgen]
+impl Proof {
+    /// Serializes the proof with the wire format of the setup
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self, setup: &Setup) -> Result<Vec<u8>, JsError> {
+        setup
+            .wire_format
+            .encode(&self.proof)
+            .ok_or_else(|| JsError::new("Proof was not generated with the setup"))
+    }
+
+    /// Deserializes a proof created with `toBytes`
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(setup: &Setup, bytes: &[u8]) -> Result<Proof, JsError> {
+        let proof = setup
+            .wire_format
+            .decode(bytes)
+            .ok_or_else(|| JsError::new("Invalid proof encoding"))?;
+        Ok(Proof { proof })
+    }
+
+    /// Serializes the proof as JSON
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.proof)?)
+    }
+
+    /// Deserializes a proof created with `toJson`
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<Proof, JsError> {
+        Ok(Proof {
+            proof: serde_json::from_str(json)?,
+        })
+    }
+}
+
+impl Proof {
+    /// Returns the rewards proof
+    pub fn rewards_proof(&self) -> &RewardsProof {
+        &self.proof
+    }
+}
+
+/// Generates a rewards proof for a state and a policy
+///
+/// The state is a JSON array with the count of every incentive, the policy is
+/// a JSON `Policy` (see `rewards_proof::serialization`). The reward is the
+/// inner product of the state and the weights of the policy, it must be
+/// smaller than `2^REWARD_BITSIZE`.
+#[wasm_bindgen(js_name = generateProof)]
+pub fn generate_proof(setup: &Setup, state: &str, policy: &str) -> Result<Proof, JsError> {
+    let state: Vec<u64> = serde_json::from_str(state)?;
+    let policy: Policy = serde_json::from_str(policy)?;
+    let reward = reward(setup, &state, policy.weights())?;
+
+    let proof = rewards_proof_generation_aggregated(
+        &setup.pedersen_gens,
+        &setup.bulletproof_gens,
+        &[reward],
+        to_scalars(&state),
+        policy.policy_vector(),
+        setup.incentive_catalog_size as u64,
+        None,
+        None,
+    )
+    .ok_or_else(|| JsError::new("State is not valid"))?;
+    Ok(Proof { proof })
+}
+
+/// Generates a rewards proof for a state and a signed policy
+///
+/// Same as `generateProof`, but the policy is a JSON `SignedPolicy`, which is
+/// verified with the public key of the issuer (32 bytes) and the current unix
+/// timestamp in seconds before proving.
+#[wasm_bindgen(js_name = generateProofSigned)]
+pub fn generate_proof_signed(
+    setup: &Setup,
+    state: &str,
+    signed_policy: &str,
+    issuer_key: &[u8],
+    timestamp: f64,
+) -> Result<Proof, JsError> {
+    let state: Vec<u64> = serde_json::from_str(state)?;
+    let signed_policy: SignedPolicy = serde_json::from_str(signed_policy)?;
+    let issuer_key = IssuerPublicKey::from_bytes(issuer_key)
+        .ok_or_else(|| JsError::new("Invalid issuer key"))?;
+    let reward = reward(setup, &state, signed_policy.policy().weights())?;
+
+    let proof = rewards_proof_generation_signed(
+        &setup.pedersen_gens,
+        &setup.bulletproof_gens,
+        &[reward],
+        to_scalars(&state),
+        &signed_policy,
+        &issuer_key,
+        timestamp as u64,
+    )
+    .ok_or_else(|| JsError::new("Policy is not valid"))?;
+    Ok(Proof { proof })
+}
+
+/// Computes the reward `<state, weights>`, fails if the sizes do not match
+/// the catalog, a count does not fit into `STATE_ENTRY_BITSIZE` bits or the
+/// reward does not fit into `REWARD_BITSIZE` bits
+fn reward(setup: &Setup, state: &[u64], weights: &[u64]) -> Result<u64, JsError> {
+    let n = setup.incentive_catalog_size as usize;
+    if state.len() != n || weights.len() != n {
+        return Err(JsError::new(
+            "State and policy do not match the incentive catalog",
+        ));
+    }
+    rewards_proof::api::reward(state, weights)
+        .ok_or_else(|| JsError::new("Count exceeds the maximum state entry or reward is too large"))
+}
@@ -0,0 +1,95 @@
+//! Headless tests of the WebAssembly bindings, run under Node with
+//! `cargo test` (see `.cargo/config.toml`)
+
+use rewards_proof::api::rewards_proof_verification_aggregated;
+use rewards_proof::policy::{IssuerSigningKey, Policy};
+use rewards_proof_wasm::{generate_proof, generate_proof_signed, Proof, Setup};
+use wasm_bindgen_test::*;
+
+const INCENTIVE_CATALOG_SIZE: u32 = 4;
+const STATE: &str = "[1, 0, 3, 2]";
+const POLICY: &str =
+    r#"{"id":1,"version":1,"weights":[2,5,1,0],"valid_from":0,"valid_until":4102444800}"#;
+
+/// Verifies a proof against the policy of the tests
+fn verify(setup: &Setup, proof: &Proof) -> bool {
+    let policy: Policy = serde_json::from_str(POLICY).unwrap();
+    let (pedersen_gens, bulletproof_gens) =
+        rewards_proof::api::rewards_proof_setup(setup.incentive_catalog_size() as u64);
+    rewards_proof_verification_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        proof.rewards_proof(),
+        &policy.policy_vector(),
+        None,
+        None,
+    )
+}
+
+#[wasm_bindgen_test]
+fn generate_and_verify() {
+    let setup = Setup::new(INCENTIVE_CATALOG_SIZE);
+    let proof = generate_proof(&setup, STATE, POLICY).unwrap();
+    assert!(verify(&setup, &proof));
+}
+
+#[wasm_bindgen_test]
+fn serialization_round_trip() {
+    let setup = Setup::new(INCENTIVE_CATALOG_SIZE);
+    let proof = generate_proof(&setup, STATE, POLICY).unwrap();
+
+    let bytes = proof.to_bytes(&setup).unwrap();
+    let decoded = Proof::from_bytes(&setup, &bytes).unwrap();
+    assert_eq!(decoded.to_bytes(&setup).unwrap(), bytes);
+    assert!(verify(&setup, &decoded));
+
+    let json = proof.to_json().unwrap();
+    assert!(verify(&setup, &Proof::from_json(&json).unwrap()));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(Proof::from_bytes(&setup, &trailing).is_err());
+}
+
+#[wasm_bindgen_test]
+fn load_setup() {
+    let setup = Setup::new(INCENTIVE_CATALOG_SIZE);
+    let loaded = Setup::load(INCENTIVE_CATALOG_SIZE, &setup.fingerprint()).unwrap();
+    assert_eq!(loaded.fingerprint(), setup.fingerprint());
+    assert!(Setup::load(2 * INCENTIVE_CATALOG_SIZE, &setup.fingerprint()).is_err());
+}
+
+#[wasm_bindgen_test]
+fn reject_invalid_state() {
+    let setup = Setup::new(INCENTIVE_CATALOG_SIZE);
+    assert!(generate_proof(&setup, "[1, 0, 3]", POLICY).is_err());
+    assert!(generate_proof(&setup, "[1, 0, 3, 65536]", POLICY).is_err());
+    assert!(generate_proof(&setup, "not json", POLICY).is_err());
+}
+
+#[wasm_bindgen_test]
+fn reject_large_reward() {
+    let setup = Setup::new(INCENTIVE_CATALOG_SIZE);
+    let max_reward_policy =
+        r#"{"id":1,"version":1,"weights":[65535,0,0,0],"valid_from":0,"valid_until":4102444800}"#;
+    assert!(generate_proof(&setup, STATE, max_reward_policy).is_ok());
+    // the reward must be smaller than 2^REWARD_BITSIZE
+    let large_reward_policy =
+        r#"{"id":1,"version":1,"weights":[65536,0,0,0],"valid_from":0,"valid_until":4102444800}"#;
+    assert!(generate_proof(&setup, STATE, large_reward_policy).is_err());
+}
+
+#[wasm_bindgen_test]
+fn generate_signed() {
+    let setup = Setup::new(INCENTIVE_CATALOG_SIZE);
+    let signing_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let policy: Policy = serde_json::from_str(POLICY).unwrap();
+    let signed_policy = serde_json::to_string(&signing_key.sign(&policy)).unwrap();
+    let issuer_key = signing_key.public_key().to_bytes();
+
+    let proof = generate_proof_signed(&setup, STATE, &signed_policy, &issuer_key, 1.7e9).unwrap();
+    assert!(verify(&setup, &proof));
+
+    // expired policy
+    assert!(generate_proof_signed(&setup, STATE, &signed_policy, &issuer_key, 4.2e9).is_err());
+}
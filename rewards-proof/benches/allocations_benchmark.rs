@@ -0,0 +1,133 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use curve25519_dalek::scalar::Scalar;
+use rand::Rng;
+use rewards_proof::api::{
+    rewards_proof_generation_aggregated, rewards_proof_setup, rewards_proof_verification,
+    rewards_proof_verification_aggregated,
+};
+use rewards_proof::verifier::PreparedVerifier;
+
+/// Global allocator that counts the number of allocations
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations of `f`
+fn count_allocations<F: FnMut() -> bool>(mut f: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    assert!(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    benchmark_verification_allocations(c, 64);
+}
+
+fn benchmark_verification_allocations(c: &mut Criterion, incentive_size: usize) {
+    let mut rng = rand::thread_rng();
+
+    //pre-processing
+    let policy_vector: Vec<u64> = (0..incentive_size).map(|_| rng.gen_range(0, 10)).collect();
+    let policy_vector_scalar: Vec<Scalar> = policy_vector
+        .iter()
+        .map(|u64_value| Scalar::from(*u64_value))
+        .collect();
+    // private value
+    let state: Vec<u64> = (0..incentive_size).map(|_| rng.gen_range(0, 10)).collect();
+    let state_scalar: Vec<Scalar> = state
+        .iter()
+        .map(|u64_value| Scalar::from(*u64_value))
+        .collect();
+
+    // reward = <state, policy_vector>
+    let reward: u64 = state
+        .iter()
+        .zip(policy_vector.iter())
+        .map(|(x, y)| x * y)
+        .sum();
+
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(incentive_size as u64);
+    let proof = rewards_proof_generation_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &[reward],
+        state_scalar,
+        policy_vector_scalar.clone(),
+        incentive_size as u64,
+        None,
+        None,
+    )
+    .unwrap();
+    let (range_proof, linear_proof, range_comm, linear_comm, bounds_proof) = proof.to_bytes();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, incentive_size as u64);
+
+    // verification of serialized proofs, deserializes the proof on every call
+    let mut verify_bytes = || {
+        rewards_proof_verification(
+            &pedersen_gens,
+            &bulletproof_gens,
+            &range_proof,
+            &range_comm,
+            &linear_proof,
+            &policy_vector_scalar,
+            (
+                &linear_comm.0,
+                &linear_comm.1,
+                &linear_comm.2,
+                &linear_comm.3,
+            ),
+            &bounds_proof,
+        )
+    };
+    // verification of a pre-parsed proof
+    let mut verify_parsed = || {
+        rewards_proof_verification_aggregated(
+            &pedersen_gens,
+            &bulletproof_gens,
+            &proof,
+            &policy_vector_scalar,
+            None,
+            None,
+        )
+    };
+    // verification of a pre-parsed proof with a prepared verifier
+    let mut verify_prepared = || verifier.verify(&proof, &policy_vector_scalar, None, None);
+
+    println!(
+        "allocations per verified proof: bytes {}, pre-parsed {}, prepared {}",
+        count_allocations(&mut verify_bytes),
+        count_allocations(&mut verify_parsed),
+        count_allocations(&mut verify_prepared),
+    );
+
+    let mut group = c.benchmark_group("rewardsproof verify allocations");
+    group.bench_function("bytes", |b| b.iter(&mut verify_bytes));
+    group.bench_function("pre-parsed", |b| b.iter(&mut verify_parsed));
+    group.bench_function("prepared", |b| b.iter(&mut verify_prepared));
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -4,9 +4,12 @@ use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use curve25519_dalek::scalar::Scalar;
 use rand::Rng;
 use rewards_proof::api::{
-    rewards_proof_generation, rewards_proof_setup, rewards_proof_verification,
+    rewards_proof_generation, rewards_proof_generation_aggregated, rewards_proof_generation_sparse,
+    rewards_proof_setup, rewards_proof_verification, rewards_proof_verification_batch,
     rewards_proof_verification_multiple,
 };
+use rewards_proof::sparse::SparseState;
+use rewards_proof::verifier::PreparedVerifier;
 
 fn criterion_benchmark(c: &mut Criterion) {
     //benchmark_rangeproof(c);
@@ -14,6 +17,8 @@ fn criterion_benchmark(c: &mut Criterion) {
     benchmark_rewardsproof_generation(c);
     benchmark_rewardsproof_verification(c);
     benchmark_rewardsproof_verification_multiple_users(c, 1000, 64);
+    benchmark_rewardsproof_generation_sparse(c, 16);
+    benchmark_rewardsproof_verification_batch(c, 1000, 64);
 }
 
 /*#[allow(dead_code)]
@@ -110,6 +115,73 @@ fn benchmark_rewardsproof_generation(c: &mut Criterion) {
     group.finish();
 }
 
+#[allow(dead_code)]
+fn benchmark_rewardsproof_generation_sparse(c: &mut Criterion, non_zero_entries: usize) {
+    let mut group = c.benchmark_group("rewardsproof gen sparse");
+
+    let mut rng = rand::thread_rng();
+    group.sample_size(10);
+    group.measurement_time(Duration::new(20, 0));
+
+    for size in [1024, 2048, 4096, 8192, 16384].iter() {
+        //pre-processing
+        let policy_vector: Vec<u64> = (0..*size).map(|_| rng.gen_range(0, 10)).collect();
+        let policy_vector_scalar: Vec<Scalar> = policy_vector
+            .iter()
+            .map(|u64_value| Scalar::from(*u64_value))
+            .collect();
+        // private value, only a few incentives were interacted with
+        let entries: Vec<(usize, u64)> = (0..non_zero_entries)
+            .map(|_| (rng.gen_range(0, *size as usize), rng.gen_range(1, 10)))
+            .collect();
+        let sparse_state = SparseState::from_entries(*size, entries).unwrap();
+        let state_scalar = sparse_state.to_dense();
+
+        // reward = <state, policy_vector>
+        let reward: u64 = sparse_state
+            .iter()
+            .map(|(position, count)| count * policy_vector[position])
+            .sum();
+
+        // create variables for linear proof
+        let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(*size);
+
+        // generate rewards proof from the dense state
+        group.bench_with_input(BenchmarkId::new("dense-", size), size, |b, size| {
+            b.iter(|| {
+                rewards_proof_generation_aggregated(
+                    &pedersen_gens,
+                    &bulletproof_gens,
+                    &[reward],
+                    state_scalar.clone(),
+                    policy_vector_scalar.clone(),
+                    *size,
+                    None,
+                    None,
+                );
+            })
+        });
+
+        // generate rewards proof from the sparse state, only the commitment to
+        // the state is sparse, the linear and counter bounds proofs are
+        // computed over the dense state
+        group.bench_with_input(BenchmarkId::new("sparse-", size), size, |b, _size| {
+            b.iter(|| {
+                rewards_proof_generation_sparse(
+                    &pedersen_gens,
+                    &bulletproof_gens,
+                    &[reward],
+                    &sparse_state,
+                    policy_vector_scalar.clone(),
+                    None,
+                    None,
+                );
+            })
+        });
+    }
+    group.finish();
+}
+
 #[allow(dead_code)]
 fn benchmark_rewardsproof_verification(c: &mut Criterion) {
     let mut group = c.benchmark_group("rewardsproof verify");
@@ -146,14 +218,16 @@ fn benchmark_rewardsproof_verification(c: &mut Criterion) {
         let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(*size);
 
         // generate rewards proof
-        let (range_proof, linear_proof, range_comm, linear_comm) = rewards_proof_generation(
-            pedersen_gens.clone(),
-            bulletproof_gens.clone(),
-            reward,
-            state_scalar,
-            policy_vector_scalar.clone(),
-            *size,
-        );
+        let (range_proof, linear_proof, range_comm, linear_comm, bounds_proof) =
+            rewards_proof_generation(
+                pedersen_gens.clone(),
+                bulletproof_gens.clone(),
+                reward,
+                state_scalar,
+                policy_vector_scalar.clone(),
+                *size,
+            )
+            .unwrap();
 
         // verify rewards proof
         group.bench_with_input(BenchmarkId::new("rangeproof-", size), size, |b, _size| {
@@ -161,11 +235,37 @@ fn benchmark_rewardsproof_verification(c: &mut Criterion) {
                 rewards_proof_verification(
                     &pedersen_gens,
                     &bulletproof_gens,
-                    range_proof.clone(),
-                    range_comm.clone(),
-                    linear_proof.clone(),
-                    policy_vector_scalar.clone(),
-                    linear_comm.clone(),
+                    &range_proof,
+                    &range_comm,
+                    &linear_proof,
+                    &policy_vector_scalar,
+                    (
+                        &linear_comm.0,
+                        &linear_comm.1,
+                        &linear_comm.2,
+                        &linear_comm.3,
+                    ),
+                    &bounds_proof,
+                )
+            })
+        });
+
+        // verify rewards proof with a prepared verifier
+        let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, *size);
+        group.bench_with_input(BenchmarkId::new("prepared-", size), size, |b, _size| {
+            b.iter(|| {
+                verifier.verify_bytes(
+                    &range_proof,
+                    &range_comm,
+                    &linear_proof,
+                    &policy_vector_scalar,
+                    (
+                        &linear_comm.0,
+                        &linear_comm.1,
+                        &linear_comm.2,
+                        &linear_comm.3,
+                    ),
+                    &bounds_proof,
                 )
             })
         });
@@ -182,28 +282,17 @@ fn benchmark_rewardsproof_verification_multiple_users(
     // preprocessing
     let mut rng = rand::thread_rng();
 
-    let mut pedersen_gens = vec![];
-    let mut bulletproof_gens = vec![];
-
-    let mut range_proofs: Vec<Vec<u8>> = vec![];
-    let mut range_proof_commitments: Vec<Vec<u8>> = vec![];
-
-    let mut linear_proofs: Vec<Vec<u8>> = vec![];
-    let mut linear_proof_commitments: Vec<(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)> = vec![];
-
     //pre-processing
     let policy_vector: Vec<u64> = (0..incentive_size).map(|_| rng.gen_range(0, 10)).collect();
     let policy_vector_scalar: Vec<Scalar> = policy_vector
-        .clone()
-        .into_iter()
-        .map(|u64_value| Scalar::from(u64_value))
+        .iter()
+        .map(|u64_value| Scalar::from(*u64_value))
         .collect();
     // private value
     let state: Vec<u64> = (0..incentive_size).map(|_| rng.gen_range(0, 10)).collect();
     let state_scalar: Vec<Scalar> = state
-        .clone()
-        .into_iter()
-        .map(|u64_value| Scalar::from(u64_value))
+        .iter()
+        .map(|u64_value| Scalar::from(*u64_value))
         .collect();
 
     // reward = <state, policy_vector>
@@ -214,26 +303,25 @@ fn benchmark_rewardsproof_verification_multiple_users(
         .flatten()
         .sum();
 
-    // generate number_of_users proofs
-    for _x in 0..number_of_users {
-        // create generators
-        (pedersen_gens, bulletproof_gens) = rewards_proof_setup(incentive_size as u64);
+    // create generators
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(incentive_size as u64);
 
-        // generate rewards proof
-        let (range_proof, linear_proof, range_comm, linear_comm) = rewards_proof_generation(
-            pedersen_gens.clone(),
-            bulletproof_gens.clone(),
-            reward,
-            state_scalar.clone(),
-            policy_vector_scalar.clone(),
-            incentive_size as u64,
-        );
-
-        range_proofs.push(range_proof);
-        linear_proofs.push(linear_proof);
-        range_proof_commitments.push(range_comm);
-        linear_proof_commitments.push(linear_comm);
-    }
+    // generate number_of_users proofs
+    let proofs: Vec<_> = (0..number_of_users)
+        .map(|_| {
+            rewards_proof_generation_aggregated(
+                &pedersen_gens,
+                &bulletproof_gens,
+                &[reward],
+                state_scalar.clone(),
+                policy_vector_scalar.clone(),
+                incentive_size as u64,
+                None,
+                None,
+            )
+            .unwrap()
+        })
+        .collect();
 
     let mut group = c.benchmark_group("multiple reward proofs verification");
     group.sample_size(10);
@@ -246,13 +334,80 @@ fn benchmark_rewardsproof_verification_multiple_users(
             rewards_proof_verification_multiple(
                 &pedersen_gens,
                 &bulletproof_gens,
-                range_proofs.clone(),
-                range_proof_commitments.clone(),
-                linear_proofs.clone(),
+                &proofs,
+                &policy_vector_scalar,
+            )
+        })
+    });
+    group.finish();
+}
+
+#[allow(dead_code)]
+fn benchmark_rewardsproof_verification_batch(
+    c: &mut Criterion,
+    number_of_users: usize,
+    incentive_size: usize,
+) {
+    // preprocessing
+    let mut rng = rand::thread_rng();
+
+    //pre-processing
+    let policy_vector: Vec<u64> = (0..incentive_size).map(|_| rng.gen_range(0, 10)).collect();
+    let policy_vector_scalar: Vec<Scalar> = policy_vector
+        .iter()
+        .map(|u64_value| Scalar::from(*u64_value))
+        .collect();
+    // private value
+    let state: Vec<u64> = (0..incentive_size).map(|_| rng.gen_range(0, 10)).collect();
+    let state_scalar: Vec<Scalar> = state
+        .iter()
+        .map(|u64_value| Scalar::from(*u64_value))
+        .collect();
+
+    // reward = <state, policy_vector>
+    let reward: u64 = state
+        .iter()
+        .zip(policy_vector.iter())
+        .map(|(x, y)| x.checked_mul(*y))
+        .flatten()
+        .sum();
+
+    // create generators
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(incentive_size as u64);
+
+    // generate number_of_users proofs
+    let proofs: Vec<_> = (0..number_of_users)
+        .map(|_| {
+            rewards_proof_generation_aggregated(
+                &pedersen_gens,
+                &bulletproof_gens,
+                &[reward],
+                state_scalar.clone(),
                 policy_vector_scalar.clone(),
-                linear_proof_commitments.clone(),
-                number_of_users,
+                incentive_size as u64,
+                None,
+                None,
             )
+            .unwrap()
+        })
+        .collect();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, incentive_size as u64);
+
+    let mut group = c.benchmark_group("multiple reward proofs verification");
+    group.sample_size(10);
+    group.measurement_time(Duration::new(20, 0));
+
+    // verify rewards proofs, in parallel with the `parallel` feature
+    group.bench_function("batch_reward_proofs", |b| {
+        b.iter(|| rewards_proof_verification_batch(&verifier, &proofs, &policy_vector_scalar))
+    });
+    // verify rewards proofs one after another with the same verifier
+    group.bench_function("prepared_reward_proofs", |b| {
+        b.iter(|| {
+            proofs
+                .iter()
+                .map(|proof| verifier.verify(proof, &policy_vector_scalar, None, None))
+                .collect::<Vec<_>>()
         })
     });
     group.finish();
@@ -0,0 +1,13 @@
+fn main() {
+    // Generate the codecs of the protobuf messages, see `src/protobuf.rs`
+    #[cfg(feature = "protobuf")]
+    {
+        println!("cargo:rerun-if-changed=proto/rewards.proto");
+        std::env::set_var(
+            "PROTOC",
+            protoc_bin_vendored::protoc_bin_path().expect("No protoc binary for this platform"),
+        );
+        prost_build::compile_protos(&["proto/rewards.proto"], &["proto/"])
+            .expect("Failed to compile protobuf messages");
+    }
+}
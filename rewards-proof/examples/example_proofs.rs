@@ -149,24 +149,32 @@ fn rewards_proof_example() {
     println!("Reward: {:?}", reward);
 
     // generate rewards proof
-    let (range_proof, linear_proof, range_comm, linear_comm) = rewards_proof_generation(
-        pedersen_gens.clone(),
-        bulletproof_gens.clone(),
-        reward,
-        state_scalar,
-        policy_vector_scalar.clone(),
-        incentive_catalog_size,
-    );
+    let (range_proof, linear_proof, range_comm, linear_comm, bounds_proof) =
+        rewards_proof_generation(
+            pedersen_gens.clone(),
+            bulletproof_gens.clone(),
+            reward,
+            state_scalar,
+            policy_vector_scalar.clone(),
+            incentive_catalog_size,
+        )
+        .unwrap();
 
     // verify rewards proof
     if rewards_proof_verification(
         &pedersen_gens,
         &bulletproof_gens,
-        range_proof,
-        range_comm,
-        linear_proof,
-        policy_vector_scalar,
-        linear_comm,
+        &range_proof,
+        &range_comm,
+        &linear_proof,
+        &policy_vector_scalar,
+        (
+            &linear_comm.0,
+            &linear_comm.1,
+            &linear_comm.2,
+            &linear_comm.3,
+        ),
+        &bounds_proof,
     ) {
         println!("Rewards proof verification successfull!");
     } else {
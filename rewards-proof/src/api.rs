@@ -1,27 +1,244 @@
+use crate::bounds::{
+    counter_bounds_proof, counter_bounds_verify, BoundsPrecomputation, CounterBoundsProof,
+    CounterCaps, STATE_ENTRY_BITSIZE,
+};
+use crate::policy::{IssuerPublicKey, SignedPolicy};
+use crate::predicate::{predicate_proof, predicate_verify, PredicateProof, TierTable};
+use crate::sparse::SparseState;
+#[cfg(feature = "std")]
+use crate::verifier::PreparedVerifier;
+use alloc::vec;
+use alloc::vec::Vec;
 use bulletproofs::{inner_product, BulletproofGens, LinearProof, PedersenGens, RangeProof};
+use core::convert::TryInto;
 use core::iter;
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::{
     ristretto::{CompressedRistretto, RistrettoPoint},
-    traits::VartimeMultiscalarMul,
+    traits::{IsIdentity, VartimeMultiscalarMul, VartimePrecomputedMultiscalarMul},
 };
 use merlin::Transcript;
-use std::vec;
+use rand::{CryptoRng, RngCore};
+
+/// Maximum number of values that can be aggregated into a single range proof
+pub const MAX_RANGE_PROOF_VALUES: usize = 8;
+
+/// Bitsize of the range proof over the values, every value (e.g. the reward)
+/// must be smaller than `2^REWARD_BITSIZE`
+pub const REWARD_BITSIZE: usize = 16;
+
+/// Proofs and commitments of a rewards proof
+#[derive(Clone, Debug)]
+pub struct RewardsProof {
+    /// Aggregated range proof over all range checked values
+    pub range_proof: RangeProof,
+    /// Commitments to the range checked values (padded to a power of two)
+    pub range_proof_commitments: Vec<CompressedRistretto>,
+    /// Linear proof for reward = <state, policy_vector>
+    pub linear_proof: LinearProof,
+    /// Commitments of the linear proof (G, F, B, C)
+    pub linear_proof_commitments: (
+        Vec<RistrettoPoint>,
+        RistrettoPoint,
+        RistrettoPoint,
+        CompressedRistretto,
+    ),
+    /// Proof that every state entry is non-negative and within its
+    /// per-incentive cap (if configured)
+    pub counter_bounds_proof: CounterBoundsProof,
+    /// Optional proof that the score <state, policy_vector> falls into a tier
+    /// of a public tier table
+    pub predicate_proof: Option<PredicateProof>,
+}
+
+impl RewardsProof {
+    /// Converts proofs and commitments to byte vectors
+    ///
+    /// The predicate proof is not included, it is serialized separately with
+    /// `PredicateProof::to_bytes`.
+    pub fn to_bytes(
+        &self,
+    ) -> (
+        Vec<u8>,
+        Vec<u8>,
+        Vec<u8>,
+        (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>),
+        Vec<u8>,
+    ) {
+        let r_comm = self
+            .range_proof_commitments
+            .iter()
+            .flat_map(|point| point.to_bytes().to_vec())
+            .collect();
 
+        let l_comm = (
+            self.linear_proof_commitments
+                .0
+                .iter()
+                .flat_map(|point| point.compress().to_bytes().to_vec())
+                .collect(),
+            self.linear_proof_commitments
+                .1
+                .compress()
+                .to_bytes()
+                .to_vec(),
+            self.linear_proof_commitments
+                .2
+                .compress()
+                .to_bytes()
+                .to_vec(),
+            self.linear_proof_commitments.3.to_bytes().to_vec(),
+        );
+
+        (
+            self.range_proof.to_bytes(),
+            self.linear_proof.to_bytes(),
+            r_comm,
+            l_comm,
+            self.counter_bounds_proof.to_bytes(),
+        )
+    }
+
+    /// Reconstructs proofs and commitments from byte vectors
+    pub fn from_bytes(
+        range_proof: &[u8],
+        range_proof_commitments: &[u8],
+        linear_proof: &[u8],
+        linear_proof_commitments: (&[u8], &[u8], &[u8], &[u8]),
+        counter_bounds_proof: &[u8],
+    ) -> Option<RewardsProof> {
+        let point_size = CompressedRistretto::default().as_bytes().len();
+
+        let r_proof = RangeProof::from_bytes(range_proof).ok()?;
+        if range_proof_commitments.len() % point_size != 0 {
+            return None;
+        }
+        let r_comm: Vec<CompressedRistretto> = range_proof_commitments
+            .chunks(point_size)
+            .map(CompressedRistretto::from_slice)
+            .collect();
+
+        let l_proof = LinearProof::from_bytes(linear_proof).ok()?;
+        if linear_proof_commitments.0.len() % point_size != 0
+            || linear_proof_commitments.1.len() != point_size
+            || linear_proof_commitments.2.len() != point_size
+            || linear_proof_commitments.3.len() != point_size
+        {
+            return None;
+        }
+        let g = linear_proof_commitments
+            .0
+            .chunks(point_size)
+            .map(|chunk| CompressedRistretto::from_slice(chunk).decompress())
+            .collect::<Option<Vec<RistrettoPoint>>>()?;
+        let f = CompressedRistretto::from_slice(linear_proof_commitments.1).decompress()?;
+        let b = CompressedRistretto::from_slice(linear_proof_commitments.2).decompress()?;
+        let c = CompressedRistretto::from_slice(linear_proof_commitments.3);
+
+        let bounds_proof = CounterBoundsProof::from_bytes(counter_bounds_proof)?;
+
+        Some(RewardsProof {
+            range_proof: r_proof,
+            range_proof_commitments: r_comm,
+            linear_proof: l_proof,
+            linear_proof_commitments: (g, f, b, c),
+            counter_bounds_proof: bounds_proof,
+            predicate_proof: None,
+        })
+    }
+}
+
+/// Generates the generators for a catalog of `incentive_catalog_size`
+/// incentives
+///
+/// Besides the generators of the range proof over the values and of the
+/// linear proof, the setup contains `2 * STATE_ENTRY_BITSIZE *
+/// incentive_catalog_size.next_power_of_two()` generators for the mandatory
+/// range proof over the state entries. This range proof adds
+/// `2 * log2(STATE_ENTRY_BITSIZE * incentive_catalog_size.next_power_of_two()) + 9`
+/// group elements and scalars to every proof, and the proving and
+/// verification time grows linearly with the catalog size.
 pub fn rewards_proof_setup(
     incentive_catalog_size: u64,
 ) -> (Vec<PedersenGens>, Vec<BulletproofGens>) {
-    // Generate generators for the range proof
-    let (ps_gen, bp_gen) = setup(64);
-    // Generate generators for the linear proof
-    let (ps_gen_lin, bp_gen_lin) = setup(incentive_catalog_size as usize);
+    sized_setup(setup_capacities(incentive_catalog_size))
+}
+
+/// Generates the generators to verify proofs of at most `values` range
+/// checked values for a catalog of `incentive_catalog_size` incentives
+///
+/// The generators of every party of the Bulletproof generators do not depend
+/// on the capacities, so proofs generated with `rewards_proof_setup` verify
+/// with this smaller setup, which only contains the range proof generators
+/// for `REWARD_BITSIZE` bits of `values.next_power_of_two()` parties instead
+/// of 64 bits of `MAX_RANGE_PROOF_VALUES` parties. Proofs with more values are
+/// rejected. Use `WireFormat::for_verification_setup` to decode proofs with
+/// these generators.
+pub fn rewards_proof_verification_setup(
+    incentive_catalog_size: u64,
+    values: usize,
+) -> (Vec<PedersenGens>, Vec<BulletproofGens>) {
+    let mut capacities = setup_capacities(incentive_catalog_size);
+    capacities[0] = (REWARD_BITSIZE, values.next_power_of_two());
+    sized_setup(capacities)
+}
+
+/// Returns the generator and party capacities of the Bulletproof generators
+/// of `rewards_proof_setup`
+pub(crate) fn setup_capacities(incentive_catalog_size: u64) -> [(usize, usize); 3] {
+    [
+        // (aggregated) range proof
+        (64, MAX_RANGE_PROOF_VALUES),
+        // linear proof
+        (incentive_catalog_size as usize, 1),
+        // (aggregated) range proof over the state
+        (
+            STATE_ENTRY_BITSIZE,
+            (incentive_catalog_size as usize).next_power_of_two(),
+        ),
+    ]
+}
+
+/// Generates the generators of the setup with the given capacities
+fn sized_setup(capacities: [(usize, usize); 3]) -> (Vec<PedersenGens>, Vec<BulletproofGens>) {
+    let (ps_gen, bp_gen) = setup(capacities[0].0, capacities[0].1);
+    let (ps_gen_lin, bp_gen_lin) = setup(capacities[1].0, capacities[1].1);
+    let (_, bp_gen_state) = setup(capacities[2].0, capacities[2].1);
 
     let pedersen_gens = vec![ps_gen, ps_gen_lin];
-    let bulletproof_gens = vec![bp_gen, bp_gen_lin];
+    let bulletproof_gens = vec![bp_gen, bp_gen_lin, bp_gen_state];
     (pedersen_gens, bulletproof_gens)
 }
 
-/// Generates proofs and commitments for the entire rewards proof
+/// Computes the reward `<state, weights>`, returns `None` if the vectors
+/// differ in size, a count does not fit into `STATE_ENTRY_BITSIZE` bits or
+/// the reward does not fit into `REWARD_BITSIZE` bits
+///
+/// A state and a reward accepted here can be proven with
+/// `rewards_proof_generation_aggregated`.
+pub fn reward(state: &[u64], weights: &[u64]) -> Option<u64> {
+    if state.len() != weights.len() || state.iter().any(|count| *count >> STATE_ENTRY_BITSIZE != 0)
+    {
+        return None;
+    }
+    let reward = state
+        .iter()
+        .zip(weights.iter())
+        .try_fold(0u64, |sum, (x, y)| sum.checked_add(x.checked_mul(*y)?))?;
+    if reward >> REWARD_BITSIZE != 0 {
+        return None;
+    }
+    Some(reward)
+}
+
+/// Converts counts or weights to scalars
+pub fn to_scalars(values: &[u64]) -> Vec<Scalar> {
+    values.iter().map(|value| Scalar::from(*value)).collect()
+}
+
+/// Generates proofs and commitments for the entire rewards proof, returns
+/// `None` for invalid inputs, see `rewards_proof_generation_aggregated`
+#[cfg(feature = "std")]
 pub fn rewards_proof_generation(
     pedersen_gens: Vec<PedersenGens>,
     bulletproof_gens: Vec<BulletproofGens>,
@@ -29,98 +246,392 @@ pub fn rewards_proof_generation(
     private_value: Vec<Scalar>,
     public_value: Vec<Scalar>,
     incentive_catalog_size: u64,
-) -> (
+) -> Option<(
     Vec<u8>,
     Vec<u8>,
     Vec<u8>,
     (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>),
-) {
-    let limit_range_proof: usize = 16; // param?
+    Vec<u8>,
+)> {
+    rewards_proof_generation_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &[value],
+        private_value,
+        public_value,
+        incentive_catalog_size,
+        None,
+        None,
+    )
+    .map(|proof| proof.to_bytes())
+}
 
-    // Generate range proof
-    let (range_proof, range_proof_commitments) = range_proof(
-        &pedersen_gens.first().unwrap(),
-        &bulletproof_gens.first().unwrap(),
-        value,
-        limit_range_proof,
-    );
+/// Generates a rewards proof where all `values` (e.g. rewards in multiple
+/// currencies, min/max bounds or per-category caps) are range checked in a
+/// single aggregated range proof
+///
+/// Every entry of the state vector is proven to be a non-negative counter
+/// smaller than `2^STATE_ENTRY_BITSIZE`. If `counter_caps` are given, the
+/// entries are instead proven to lie within their per-incentive caps. If a
+/// `tier_table` is given, the rewards proof additionally proves into which
+/// tier the score <state, policy_vector> falls.
+///
+/// Returns `None` if the vectors do not match the catalog, more than
+/// `MAX_RANGE_PROOF_VALUES` values are given, a value does not fit into
+/// `REWARD_BITSIZE` bits, a state entry is not within its bounds or the score
+/// cannot be proven for the tier table.
+#[cfg(feature = "std")]
+pub fn rewards_proof_generation_aggregated(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    values: &[u64],
+    private_value: Vec<Scalar>,
+    public_value: Vec<Scalar>,
+    incentive_catalog_size: u64,
+    counter_caps: Option<&CounterCaps>,
+    tier_table: Option<&TierTable>,
+) -> Option<RewardsProof> {
+    rewards_proof_generation_aggregated_with_rng(
+        pedersen_gens,
+        bulletproof_gens,
+        values,
+        private_value,
+        public_value,
+        incentive_catalog_size,
+        counter_caps,
+        tier_table,
+        &mut rand::thread_rng(),
+    )
+}
 
-    // Generate linear proof
-    let (linear_proof, linear_proof_commitments) = linear_proof(
-        &pedersen_gens.last().unwrap(),
-        &bulletproof_gens.last().unwrap(),
-        private_value.clone(),
-        public_value.clone(),
-        incentive_catalog_size as usize,
-    );
-
-    // convert commitments to byte vectors
-    let l_comm = (
-        linear_proof_commitments
-            .0
-            .iter()
-            .flat_map(|point| point.compress().to_bytes().to_vec())
-            .collect(),
-        linear_proof_commitments.1.compress().to_bytes().to_vec(),
-        linear_proof_commitments.2.compress().to_bytes().to_vec(),
-        linear_proof_commitments.3.to_bytes().to_vec(),
-    );
-
-    // Return proofs as byte array + commitements?
-    (
-        range_proof.to_bytes(),
-        linear_proof.to_bytes(),
-        range_proof_commitments.to_bytes().to_vec(),
-        l_comm,
+/// Generates a rewards proof like `rewards_proof_generation_aggregated`, with
+/// the randomness of the prover drawn from `rng`
+pub fn rewards_proof_generation_aggregated_with_rng<R: RngCore + CryptoRng>(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    values: &[u64],
+    private_value: Vec<Scalar>,
+    public_value: Vec<Scalar>,
+    incentive_catalog_size: u64,
+    counter_caps: Option<&CounterCaps>,
+    tier_table: Option<&TierTable>,
+    rng: &mut R,
+) -> Option<RewardsProof> {
+    let n = incentive_catalog_size as usize;
+    let (g, f, b) = linear_generators(&pedersen_gens[1], &bulletproof_gens[1], n);
+    if g.len() != n || private_value.len() != n || public_value.len() != n {
+        return None;
+    }
+
+    // C = <a, G> + r * B + <a, b> * F
+    let r = Scalar::random(rng);
+    let c = linear_commitment(&private_value, &public_value, &g, &f, &b, &r);
+
+    rewards_proof_from_commitment(
+        pedersen_gens,
+        bulletproof_gens,
+        values,
+        private_value,
+        public_value,
+        (g, f, b, c),
+        r,
+        counter_caps,
+        tier_table,
+        rng,
+    )
+}
+
+/// Generates a rewards proof for a sparse state
+///
+/// Same as `rewards_proof_generation_aggregated`, but the commitment to the
+/// state in the linear proof is computed over the non-zero counters only. The
+/// linear proof and the counter bounds proof expand the state to the dense
+/// vector and remain linear in the size of the catalog. Returns `None` if
+/// `public_value` does not match the size of the catalog.
+#[cfg(feature = "std")]
+pub fn rewards_proof_generation_sparse(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    values: &[u64],
+    private_value: &SparseState,
+    public_value: Vec<Scalar>,
+    counter_caps: Option<&CounterCaps>,
+    tier_table: Option<&TierTable>,
+) -> Option<RewardsProof> {
+    rewards_proof_generation_sparse_with_rng(
+        pedersen_gens,
+        bulletproof_gens,
+        values,
+        private_value,
+        public_value,
+        counter_caps,
+        tier_table,
+        &mut rand::thread_rng(),
+    )
+}
+
+/// Generates a rewards proof like `rewards_proof_generation_sparse`, with the
+/// randomness of the prover drawn from `rng`
+pub fn rewards_proof_generation_sparse_with_rng<R: RngCore + CryptoRng>(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    values: &[u64],
+    private_value: &SparseState,
+    public_value: Vec<Scalar>,
+    counter_caps: Option<&CounterCaps>,
+    tier_table: Option<&TierTable>,
+    rng: &mut R,
+) -> Option<RewardsProof> {
+    let n = private_value.incentive_catalog_size() as usize;
+    let (g, f, b) = linear_generators(&pedersen_gens[1], &bulletproof_gens[1], n);
+
+    // C = <a, G> + r * B + <a, b> * F
+    let r = Scalar::random(rng);
+    let c = private_value.linear_commitment(&public_value, &g, &f, &b, &r)?;
+
+    rewards_proof_from_commitment(
+        pedersen_gens,
+        bulletproof_gens,
+        values,
+        private_value.to_dense(),
+        public_value,
+        (g, f, b, c),
+        r,
+        counter_caps,
+        tier_table,
+        rng,
+    )
+}
+
+/// Generates a rewards proof for a signed policy manifest
+///
+/// The signature of the issuer and the validity window of the policy are
+/// verified before proving, returns `None` if the manifest is not valid, does
+/// not match the size of the state or the inputs are invalid, see
+/// `rewards_proof_generation_aggregated`.
+#[cfg(feature = "std")]
+pub fn rewards_proof_generation_signed(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    values: &[u64],
+    private_value: Vec<Scalar>,
+    signed_policy: &SignedPolicy,
+    issuer_key: &IssuerPublicKey,
+    timestamp: u64,
+) -> Option<RewardsProof> {
+    rewards_proof_generation_signed_with_rng(
+        pedersen_gens,
+        bulletproof_gens,
+        values,
+        private_value,
+        signed_policy,
+        issuer_key,
+        timestamp,
+        &mut rand::thread_rng(),
+    )
+}
+
+/// Generates a rewards proof like `rewards_proof_generation_signed`, with the
+/// randomness of the prover drawn from `rng`
+pub fn rewards_proof_generation_signed_with_rng<R: RngCore + CryptoRng>(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    values: &[u64],
+    private_value: Vec<Scalar>,
+    signed_policy: &SignedPolicy,
+    issuer_key: &IssuerPublicKey,
+    timestamp: u64,
+    rng: &mut R,
+) -> Option<RewardsProof> {
+    let policy = signed_policy.policy();
+    if !signed_policy.verify(issuer_key, timestamp)
+        || private_value.len() as u64 != policy.incentive_catalog_size()
+    {
+        return None;
+    }
+
+    rewards_proof_generation_aggregated_with_rng(
+        pedersen_gens,
+        bulletproof_gens,
+        values,
+        private_value,
+        policy.policy_vector(),
+        policy.incentive_catalog_size(),
+        None,
+        None,
+        rng,
     )
 }
 
 /// Verifies the rewards proofs
+///
+/// All proofs and commitments are borrowed, to verify the same proof more
+/// than once, deserialize it once with `RewardsProof::from_bytes` and use
+/// `rewards_proof_verification_aggregated`.
+#[cfg(feature = "std")]
 pub fn rewards_proof_verification(
-    pedersen_gens: &Vec<PedersenGens>,
-    bulletproof_gens: &Vec<BulletproofGens>,
-    range_proof: Vec<u8>,
-    range_proof_commitments: Vec<u8>,
-    linear_proof: Vec<u8>,
-    public_value: Vec<Scalar>,
-    linear_proof_commitments: (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>),
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    range_proof: &[u8],
+    range_proof_commitments: &[u8],
+    linear_proof: &[u8],
+    public_value: &[Scalar],
+    linear_proof_commitments: (&[u8], &[u8], &[u8], &[u8]),
+    counter_bounds_proof: &[u8],
 ) -> bool {
-    let limit_range_proof: usize = 16; // param?
+    // Deserialize proofs and commitments
+    let proof = match RewardsProof::from_bytes(
+        range_proof,
+        range_proof_commitments,
+        linear_proof,
+        linear_proof_commitments,
+        counter_bounds_proof,
+    ) {
+        Some(proof) => proof,
+        None => return false,
+    };
 
-    // Deserialize range proof
-    let r_proof = RangeProof::from_bytes(range_proof.as_slice()).unwrap();
-    let r_proof_commitments = CompressedRistretto::from_slice(range_proof_commitments.as_slice());
+    rewards_proof_verification_aggregated(
+        pedersen_gens,
+        bulletproof_gens,
+        &proof,
+        public_value,
+        None,
+        None,
+    )
+}
 
-    // Verify range proof
-    if !range_verify(
+/// Verifies a rewards proof with an aggregated range proof
+///
+/// If `counter_caps` are given, the counter bounds proof is verified against
+/// these caps, otherwise against the default non-negativity bound. If a
+/// `tier_table` is given, the rewards proof must contain a predicate proof for
+/// this table, the payout of the proven tier is then available with
+/// `PredicateProof::payout`.
+#[cfg(feature = "std")]
+pub fn rewards_proof_verification_aggregated(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    proof: &RewardsProof,
+    public_value: &[Scalar],
+    counter_caps: Option<&CounterCaps>,
+    tier_table: Option<&TierTable>,
+) -> bool {
+    verify_rewards_proof(
+        pedersen_gens,
+        bulletproof_gens,
+        proof,
+        public_value,
+        counter_caps,
+        tier_table,
+        None,
+        &mut rand::thread_rng(),
+    )
+}
+
+/// Verifies a rewards proof like `rewards_proof_verification_aggregated`,
+/// with the random weights of the verifier drawn from `rng`, e.g. on targets
+/// without an operating system RNG
+pub fn rewards_proof_verification_aggregated_with_rng<R: RngCore + CryptoRng>(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    proof: &RewardsProof,
+    public_value: &[Scalar],
+    counter_caps: Option<&CounterCaps>,
+    tier_table: Option<&TierTable>,
+    rng: &mut R,
+) -> bool {
+    verify_rewards_proof(
+        pedersen_gens,
+        bulletproof_gens,
+        proof,
+        public_value,
+        counter_caps,
+        tier_table,
+        None,
+        rng,
+    )
+}
+
+/// Verifies a rewards proof, optionally with the precomputations of a prepared
+/// verifier for the fixed generators of the counter bounds proof and the
+/// linear proof
+///
+/// Without precomputed tables, the generators `G`, `F`, `B` of the proof are
+/// checked against the setup here, the prepared verifier checks them against
+/// the generators it derived once.
+pub(crate) fn verify_rewards_proof<R: RngCore + CryptoRng>(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    proof: &RewardsProof,
+    public_value: &[Scalar],
+    counter_caps: Option<&CounterCaps>,
+    tier_table: Option<&TierTable>,
+    precomputation: Option<Precomputation>,
+    rng: &mut R,
+) -> bool {
+    if precomputation.is_none()
+        && !has_setup_generators(pedersen_gens, bulletproof_gens, proof, public_value.len())
+    {
+        return false;
+    }
+
+    // Verify aggregated range proof
+    if !range_verify_multiple(
         &pedersen_gens.first().unwrap(),
         &bulletproof_gens.first().unwrap(),
-        r_proof,
-        r_proof_commitments,
-        limit_range_proof,
+        &proof.range_proof,
+        &proof.range_proof_commitments,
+        REWARD_BITSIZE,
+        rng,
     ) {
         return false;
     }
 
-    // Deserialise linear proof
-    let l_proof = LinearProof::from_bytes(linear_proof.as_slice()).unwrap();
-    let g: Vec<RistrettoPoint> = linear_proof_commitments
-        .0
-        .chunks(CompressedRistretto::default().as_bytes().len())
-        .map(|chunk| CompressedRistretto::from_slice(chunk).decompress().unwrap())
-        .collect();
-    let f = CompressedRistretto::from_slice(linear_proof_commitments.1.as_slice());
-    let b = CompressedRistretto::from_slice(linear_proof_commitments.2.as_slice());
-    let c = CompressedRistretto::from_slice(linear_proof_commitments.3.as_slice());
+    // Verify counter bounds proof
+    if !counter_bounds_verify(
+        &pedersen_gens[1],
+        &bulletproof_gens[2],
+        counter_caps,
+        &proof.counter_bounds_proof,
+        public_value,
+        &proof.linear_proof_commitments.0,
+        &proof.linear_proof_commitments.1,
+        &proof.linear_proof_commitments.2,
+        &proof.linear_proof_commitments.3,
+        precomputation.map(|precomputation| precomputation.tables),
+        rng,
+    ) {
+        return false;
+    }
+
+    // Verify predicate proof
+    if let Some(table) = tier_table {
+        let verified = match &proof.predicate_proof {
+            Some(predicate) => predicate_verify(
+                &pedersen_gens[1],
+                table,
+                predicate,
+                public_value,
+                proof.counter_bounds_proof.state_commitments(),
+                rng,
+            ),
+            None => false,
+        };
+        if !verified {
+            return false;
+        }
+    }
 
     // Verify linear proof
     if !linear_verify(
-        l_proof,
+        &proof.linear_proof,
         public_value,
-        g,
-        f.decompress().unwrap(),
-        b.decompress().unwrap(),
-        c,
+        &proof.linear_proof_commitments.0,
+        &proof.linear_proof_commitments.1,
+        &proof.linear_proof_commitments.2,
+        &proof.linear_proof_commitments.3,
+        precomputation,
     ) {
         return false;
     }
@@ -128,198 +639,448 @@ pub fn rewards_proof_verification(
     return true;
 }
 
-/// Verifies the rewards proofs
+/// Verifies multiple rewards proofs for the same policy vector, returns `true`
+/// if all proofs are valid
+///
+/// The proofs are verified one after another and the verification stops at
+/// the first invalid proof, use `rewards_proof_verification_batch` to obtain
+/// the result of every proof.
+#[cfg(feature = "std")]
 pub fn rewards_proof_verification_multiple(
-    pedersen_gens: &Vec<PedersenGens>,
-    bulletproof_gens: &Vec<BulletproofGens>,
-    range_proof: Vec<Vec<u8>>,
-    range_proof_commitments: Vec<Vec<u8>>,
-    linear_proof: Vec<Vec<u8>>,
-    public_value: Vec<Scalar>,
-    linear_proof_commitments: Vec<(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)>,
-    number_of_proofs: usize,
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    proofs: &[RewardsProof],
+    public_value: &[Scalar],
 ) -> bool {
-    for i in 0..number_of_proofs {
-        // verify individual range proofs
-        let result = rewards_proof_verification(
-            &pedersen_gens,
-            &bulletproof_gens,
-            range_proof[i].clone(),
-            range_proof_commitments[i].clone(),
-            linear_proof[i].clone(),
-            public_value.clone(),
-            linear_proof_commitments[i].clone(),
-        );
-        if result == false {
-            panic!("Verifying {}'th rewards proof failed!", i);
-        }
-    }
-    return true;
+    proofs.iter().all(|proof| {
+        rewards_proof_verification_aggregated(
+            pedersen_gens,
+            bulletproof_gens,
+            proof,
+            public_value,
+            None,
+            None,
+        )
+    })
+}
+
+/// Verifies multiple rewards proofs for the same policy vector and returns
+/// the result of every proof
+///
+/// The `verifier` is built once from the setup and reused across batches, as
+/// building it derives the generators and precomputed tables of the counter
+/// bounds proof. Everything that only depends on the policy vector is
+/// precomputed once for the batch, see `PreparedVerifier::prepare_policy`.
+/// With the `parallel` feature, the proofs are verified on all CPU cores. The
+/// results are the same as for the sequential verification.
+#[cfg(feature = "std")]
+pub fn rewards_proof_verification_batch(
+    verifier: &PreparedVerifier,
+    proofs: &[RewardsProof],
+    public_value: &[Scalar],
+) -> Vec<bool> {
+    verifier.verify_batch(proofs, public_value)
 }
 
 /// Setup for Pedersen Generators and BulletProofs Generators
-fn setup(gen_capacity: usize) -> (PedersenGens, BulletproofGens) {
+fn setup(gen_capacity: usize, party_capacity: usize) -> (PedersenGens, BulletproofGens) {
     let pedersen_generators = PedersenGens::default();
-    let bulletproof_generators = BulletproofGens::new(gen_capacity, 1);
+    let bulletproof_generators = BulletproofGens::new(gen_capacity, party_capacity);
     (pedersen_generators, bulletproof_generators)
 }
 
-/// Generates a proof and the commitments for a range proof
-fn range_proof(
+/// Generates an aggregated range proof and the commitments for all values,
+/// returns `None` if there are too many values or a value does not fit into
+/// `n` bits
+///
+/// The number of values is padded with zeros to the next power of two, as
+/// required for aggregation.
+fn range_proof_multiple<R: RngCore + CryptoRng>(
     ps_gen: &PedersenGens,
     bp_gen: &BulletproofGens,
-    value: u64,
+    values: &[u64],
     n: usize,
-) -> (RangeProof, CompressedRistretto) {
-    let mut rng = rand::thread_rng();
-    let blinding = Scalar::random(&mut rng);
+    rng: &mut R,
+) -> Option<(RangeProof, Vec<CompressedRistretto>)> {
+    if values.len() > MAX_RANGE_PROOF_VALUES || values.iter().any(|value| *value >> n != 0) {
+        return None;
+    }
+    let padded_values: Vec<u64> = values
+        .iter()
+        .cloned()
+        .chain(iter::repeat(0))
+        .take(values.len().next_power_of_two())
+        .collect();
+    let blindings: Vec<Scalar> = padded_values.iter().map(|_| Scalar::random(rng)).collect();
 
     let mut prover_transcript = Transcript::new(b"rangeproof");
-    let (proof, commitments) = RangeProof::prove_single(
+    RangeProof::prove_multiple_with_rng(
         &bp_gen,
         &ps_gen,
         &mut prover_transcript,
-        value,
-        &blinding,
+        &padded_values,
+        &blindings,
         n,
+        rng,
     )
-    .expect("Error when creating rangeproof");
-
-    (proof, commitments)
+    .ok()
 }
 
-/// Verifies a range proof
-fn range_verify(
+/// Verifies an aggregated range proof
+fn range_verify_multiple<R: RngCore + CryptoRng>(
     ps_gen: &PedersenGens,
     bp_gen: &BulletproofGens,
-    proof: RangeProof,
-    commitments: CompressedRistretto,
+    proof: &RangeProof,
+    commitments: &[CompressedRistretto],
     n: usize,
+    rng: &mut R,
 ) -> bool {
     let mut verifier_transcript = Transcript::new(b"rangeproof");
     proof
-        .verify_single(&bp_gen, &ps_gen, &mut verifier_transcript, &commitments, n)
+        .verify_multiple_with_rng(
+            &bp_gen,
+            &ps_gen,
+            &mut verifier_transcript,
+            commitments,
+            n,
+            rng,
+        )
         .is_ok()
 }
 
-// Verifies multiple range proofs
-/*fn range_verify_multiple(
-    ps_gen: Vec<PedersenGens>,
-    bp_gen: Vec<BulletproofGens>,
-    proofs: Vec<RangeProof>,
-    commitments: Vec<CompressedRistretto>,
-    n: usize,
-    number_of_proofs: usize,
-) -> bool {
-    for i in 0..number_of_proofs {
-        // verify individual proofs
-        let result = range_verify(&ps_gen[i], &bp_gen[i], proofs[i].clone(), commitments[i], n);
-
-        // shortcut if any of the proofs is false, stop immediately
-        if result == false {
-            // only for benchmarking
-            panic!("Verifying {}'th range proof failed!", i);
-            //return false;
-        }
-    }
-    return true;
-}*/
-
-/// Generates a linear proof
-fn linear_proof(
-    ps_gen: &PedersenGens,
-    bp_gen: &BulletproofGens,
+/// Generates all proofs of a rewards proof for the commitment `C` of the
+/// linear proof, returns `None` for invalid inputs
+fn rewards_proof_from_commitment<R: RngCore + CryptoRng>(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    values: &[u64],
     private_value: Vec<Scalar>,
     public_value: Vec<Scalar>,
-    n: usize,
-) -> (
-    LinearProof,
-    (
+    linear_proof_commitments: (
         Vec<RistrettoPoint>,
         RistrettoPoint,
         RistrettoPoint,
         CompressedRistretto,
     ),
-) {
-    let mut rng = rand::thread_rng();
-    let r = Scalar::random(&mut rng);
+    r: Scalar,
+    counter_caps: Option<&CounterCaps>,
+    tier_table: Option<&TierTable>,
+    rng: &mut R,
+) -> Option<RewardsProof> {
+    // Generate aggregated range proof
+    let (range_proof, range_proof_commitments) = range_proof_multiple(
+        &pedersen_gens.first().unwrap(),
+        &bulletproof_gens.first().unwrap(),
+        values,
+        REWARD_BITSIZE,
+        rng,
+    )?;
+
+    // Keep the state for the counter bounds proof
+    let state = private_value.clone();
+    let policy = public_value.clone();
+
+    // Generate linear proof
+    let linear_proof = linear_proof(
+        private_value,
+        public_value,
+        r,
+        &linear_proof_commitments.0,
+        &linear_proof_commitments.1,
+        &linear_proof_commitments.2,
+        &linear_proof_commitments.3,
+        rng,
+    )?;
+
+    // Generate counter bounds proof
+    let (counter_bounds_proof, state_blindings) = counter_bounds_proof(
+        &pedersen_gens[1],
+        &bulletproof_gens[2],
+        counter_caps,
+        &state,
+        &policy,
+        &linear_proof_commitments.0,
+        &linear_proof_commitments.1,
+        &linear_proof_commitments.2,
+        &linear_proof_commitments.3,
+        &r,
+        rng,
+    )?;
 
+    // Generate predicate proof
+    let predicate_proof = match tier_table {
+        Some(table) => Some(predicate_proof(
+            &pedersen_gens[1],
+            table,
+            &state,
+            &policy,
+            counter_bounds_proof.state_commitments(),
+            &state_blindings,
+            rng,
+        )?),
+        None => None,
+    };
+
+    Some(RewardsProof {
+        range_proof,
+        range_proof_commitments,
+        linear_proof,
+        linear_proof_commitments,
+        counter_bounds_proof,
+        predicate_proof,
+    })
+}
+
+/// Returns the generators of the linear proof (G, F, B)
+pub(crate) fn linear_generators(
+    ps_gen: &PedersenGens,
+    bp_gen: &BulletproofGens,
+    n: usize,
+) -> (Vec<RistrettoPoint>, RistrettoPoint, RistrettoPoint) {
     let g: Vec<RistrettoPoint> = bp_gen.share(0).G(n).cloned().collect();
-    let f = ps_gen.B;
-    let b = ps_gen.B_blinding;
+    (g, ps_gen.B, ps_gen.B_blinding)
+}
 
-    // C = <a, G> + r * B + <a, b> * F
-    let result_inner_product = inner_product(&private_value, &public_value);
-    let c = RistrettoPoint::vartime_multiscalar_mul(
+/// Checks that the proof uses the generators `G`, `F`, `B` of the setup for a
+/// catalog of size `n`
+fn has_setup_generators(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    proof: &RewardsProof,
+    n: usize,
+) -> bool {
+    let (g, f, b) = linear_generators(&pedersen_gens[1], &bulletproof_gens[1], n);
+    let (proof_g, proof_f, proof_b, _) = &proof.linear_proof_commitments;
+    *proof_g == g && *proof_f == f && *proof_b == b
+}
+
+/// Computes the commitment of the linear proof `C = <a, G> + r * B + <a, b> * F`
+fn linear_commitment(
+    private_value: &[Scalar],
+    public_value: &[Scalar],
+    g: &[RistrettoPoint],
+    f: &RistrettoPoint,
+    b: &RistrettoPoint,
+    r: &Scalar,
+) -> CompressedRistretto {
+    let result_inner_product = inner_product(private_value, public_value);
+    RistrettoPoint::vartime_multiscalar_mul(
         private_value
             .iter()
-            .chain(iter::once(&r))
+            .chain(iter::once(r))
             .chain(iter::once(&result_inner_product)),
-        g.iter().chain(Some(&b)).chain(iter::once(&f)),
+        g.iter().chain(Some(b)).chain(iter::once(f)),
     )
-    .compress();
+    .compress()
+}
 
+/// Generates a linear proof for the commitment `C`, returns `None` if the
+/// sizes of the vectors and generators differ
+fn linear_proof<R: RngCore + CryptoRng>(
+    private_value: Vec<Scalar>,
+    public_value: Vec<Scalar>,
+    r: Scalar,
+    g: &[RistrettoPoint],
+    f: &RistrettoPoint,
+    b: &RistrettoPoint,
+    c: &CompressedRistretto,
+    rng: &mut R,
+) -> Option<LinearProof> {
     let mut prover_transcript = Transcript::new(b"linear proof");
-    let proof = LinearProof::create(
+    LinearProof::create(
         &mut prover_transcript,
-        &mut rng,
-        &c,
+        rng,
+        c,
         r,
         private_value,
         public_value,
-        g.clone(),
-        &f,
-        &b,
+        g.to_vec(),
+        f,
+        b,
     )
-    .expect("Error creating linear proof");
+    .ok()
+}
 
-    (proof, (g, f, b, c))
+/// Precomputations of a prepared verifier for the fixed generators `G`, `F`,
+/// `B`, see `PreparedVerifier`
+#[derive(Clone, Copy)]
+pub(crate) struct Precomputation<'a> {
+    /// `G_0, .., G_{n-1}, F, B` compressed, as appended to the transcript of
+    /// the linear proof
+    pub(crate) compressed_generators: &'a [CompressedRistretto],
+    /// Tables of the counter bounds proof and the linear proof
+    pub(crate) tables: BoundsPrecomputation<'a>,
 }
 
-/// Verifies a linear proof
+/// Verifies a linear proof, with the precomputed tables of a prepared
+/// verifier if given
+///
+/// The verification equation of `LinearProof::verify` of the bulletproofs
+/// crate is checked here in one multiscalar multiplication, which borrows the
+/// policy vector instead of taking it by value: the verifier recomputes the
+/// challenges `x_j` of the rounds and `x` of the transcript of
+/// `LinearProof::create` and checks
+///
+/// ```text
+/// a * <s, G> + a * <s, b> * F + r * B - x * (C + sum x_j * L_j + x_j^-1 * R_j) - S == 0
+/// ```
+///
+/// where `s_i` is the product of the challenges `x_j` of the rounds in which
+/// `G_i` and `b_i` were folded from the right half, i.e. `<s, G>` and `<s, b>`
+/// are the folded generators and policy vector. With a prepared policy,
+/// `a * <s, G> + a * <s, b> * F = a * <s, G + b * F>` uses the precomputed
+/// generators with the policy vector folded in, so the policy vector is not
+/// folded for every proof.
 fn linear_verify(
-    proof: LinearProof,
-    public_value: Vec<Scalar>,
-    g: Vec<RistrettoPoint>,
-    f: RistrettoPoint,
-    b: RistrettoPoint,
-    c: CompressedRistretto,
+    proof: &LinearProof,
+    public_value: &[Scalar],
+    g: &[RistrettoPoint],
+    f: &RistrettoPoint,
+    b: &RistrettoPoint,
+    c: &CompressedRistretto,
+    precomputation: Option<Precomputation>,
 ) -> bool {
-    let mut verifier_transcript = Transcript::new(b"linear proof");
-    proof
-        .verify(&mut verifier_transcript, &c, &g, &f, &b, public_value)
-        .is_ok()
-}
+    let compressed: Vec<CompressedRistretto>;
+    let compressed_generators = match precomputation {
+        Some(precomputation) => precomputation.compressed_generators,
+        None => {
+            compressed = g
+                .iter()
+                .chain(iter::once(f))
+                .chain(iter::once(b))
+                .map(|point| point.compress())
+                .collect();
+            &compressed
+        }
+    };
 
-// Verifies multiple linear proofs
-/*fn linear_verify_multiple(
-    proofs: Vec<LinearProof>,
-    public_values: Vec<Vec<Scalar>>,
-    commitments: Vec<(
-        Vec<RistrettoPoint>,
-        RistrettoPoint,
-        RistrettoPoint,
-        CompressedRistretto,
-    )>,
-    number_of_proofs: usize,
-) -> bool {
-    for i in 0..number_of_proofs {
-        // verify individual proofs
-        let result = linear_verify(
-            proofs[i].clone(),
-            public_values[i].clone(),
-            commitments[i].0.clone(),
-            commitments[i].1,
-            commitments[i].2,
-            commitments[i].3,
-        );
+    // L_0 || R_0 || .. || L_{k-1} || R_{k-1} || S || a || r
+    let n = public_value.len();
+    let bytes = proof.to_bytes();
+    let chunks = bytes.len() / 32;
+    if chunks < 3 || (chunks - 3) % 2 != 0 {
+        return false;
+    }
+    let k = (chunks - 3) / 2;
+    if k >= 32 || n != 1 << k || compressed_generators.len() != n + 2 {
+        return false;
+    }
+    let chunk = |i: usize| -> [u8; 32] { bytes[i * 32..(i + 1) * 32].try_into().unwrap() };
+    let l_r: Vec<CompressedRistretto> = (0..2 * k).map(|i| CompressedRistretto(chunk(i))).collect();
+    let s_commitment = CompressedRistretto(chunk(2 * k));
+    let (a_star, r_star) = match (
+        Scalar::from_canonical_bytes(chunk(2 * k + 1)),
+        Scalar::from_canonical_bytes(chunk(2 * k + 2)),
+    ) {
+        (Some(a_star), Some(r_star)) => (a_star, r_star),
+        _ => return false,
+    };
+
+    // Transcript of `LinearProof::create`, all public inputs, then the rounds
+    let mut transcript = Transcript::new(b"linear proof");
+    transcript.append_message(b"dom-sep", b"ipp v1");
+    transcript.append_u64(b"n", n as u64);
+    transcript.append_message(b"C", c.as_bytes());
+    for b_i in public_value.iter() {
+        transcript.append_message(b"b_i", b_i.as_bytes());
+    }
+    let (compressed_g, compressed_f_b) = compressed_generators.split_at(n);
+    for g_i in compressed_g.iter() {
+        transcript.append_message(b"G_i", g_i.as_bytes());
+    }
+    transcript.append_message(b"F", compressed_f_b[0].as_bytes());
+    transcript.append_message(b"B", compressed_f_b[1].as_bytes());
 
-        // shortcut if any of the proofs is false, stop immediately
-        if result == false {
-            // only for benchmarking
-            panic!("Verifying {}'th linear proof failed!", i);
-            //return false;
+    let mut challenges = Vec::with_capacity(k);
+    for l_r_j in l_r.chunks(2) {
+        if l_r_j[0].is_identity() || l_r_j[1].is_identity() {
+            return false;
         }
+        transcript.append_message(b"L", l_r_j[0].as_bytes());
+        transcript.append_message(b"R", l_r_j[1].as_bytes());
+        challenges.push(transcript_challenge(&mut transcript, b"x_j"));
     }
-    return true;
-}*/
+    transcript.append_message(b"S", s_commitment.as_bytes());
+    let x = transcript_challenge(&mut transcript, b"x_star");
+
+    // s_i = prod x_j over the rounds j that folded G_i from the right half,
+    // the first round folds the upper half of the indices
+    let mut s = Vec::with_capacity(n);
+    s.push(Scalar::one());
+    for i in 1..n {
+        let lg_i = (usize::BITS - 1 - i.leading_zeros()) as usize;
+        s.push(s[i - (1 << lg_i)] * challenges[k - 1 - lg_i]);
+    }
+
+    let mut dynamic_scalars = Vec::with_capacity(2 * k + 2);
+    dynamic_scalars.push(-x);
+    for x_j in challenges.iter() {
+        dynamic_scalars.push(-(x * x_j));
+        dynamic_scalars.push(-(x * x_j.invert()));
+    }
+    dynamic_scalars.push(-Scalar::one());
+    let dynamic_points = iter::once(c)
+        .chain(l_r.iter())
+        .chain(iter::once(&s_commitment))
+        .map(|point| point.decompress());
+
+    // <s, b> is already folded into the generators of a policy precomputation
+    let b_0: Option<Scalar> = match precomputation {
+        Some(Precomputation {
+            tables: BoundsPrecomputation::Policy(_),
+            ..
+        }) => None,
+        _ => Some(
+            s.iter()
+                .zip(public_value.iter())
+                .map(|(s_i, b_i)| s_i * b_i)
+                .sum(),
+        ),
+    };
+    // Scalars of the fixed generators `G`, `F`, `B`
+    let static_scalars = s
+        .iter()
+        .map(|s_i| a_star * s_i)
+        .chain(b_0.map(|b_0| a_star * b_0))
+        .chain(iter::once(r_star));
+
+    let check = match precomputation {
+        // in the order of `bounds_precomputation` (or `policy_precomputation`
+        // without F), which ends with `B` of the Pedersen commitments
+        Some(Precomputation {
+            tables: BoundsPrecomputation::Generators(table),
+            ..
+        })
+        | Some(Precomputation {
+            tables: BoundsPrecomputation::Policy(table),
+            ..
+        }) => table.optional_mixed_multiscalar_mul(
+            static_scalars.chain(iter::once(Scalar::zero())),
+            dynamic_scalars,
+            dynamic_points,
+        ),
+        _ => {
+            let static_points = g
+                .iter()
+                .map(|point| Some(*point))
+                .chain(iter::once(Some(*f)))
+                .chain(iter::once(Some(*b)));
+            RistrettoPoint::optional_multiscalar_mul(
+                static_scalars.chain(dynamic_scalars),
+                static_points.chain(dynamic_points),
+            )
+        }
+    };
+    match check {
+        Some(check) => check.is_identity(),
+        None => false,
+    }
+}
+
+/// Derives a challenge scalar from the transcript, like the transcript
+/// protocol of the bulletproofs crate
+fn transcript_challenge(transcript: &mut Transcript, label: &'static [u8]) -> Scalar {
+    let mut buf = [0u8; 64];
+    transcript.challenge_bytes(label, &mut buf);
+    Scalar::from_bytes_mod_order_wide(&buf)
+}
@@ -0,0 +1,562 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+use core::convert::TryInto;
+use core::iter;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::{
+    ristretto::{CompressedRistretto, RistrettoPoint, VartimeRistrettoPrecomputation},
+    traits::{Identity, IsIdentity, VartimeMultiscalarMul, VartimePrecomputedMultiscalarMul},
+};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+/// Bitsize of the range proof for the state entries if no caps are configured
+///
+/// Every state entry must be a non-negative counter smaller than
+/// `2^STATE_ENTRY_BITSIZE`, otherwise a "negative" counter `p - k` could
+/// cancel other terms in `<state, policy_vector>`.
+pub const STATE_ENTRY_BITSIZE: usize = 16;
+
+/// Per-incentive caps for the entries of the private state vector
+///
+/// Caps are configured once per incentive catalog, e.g. at most 10 ad views
+/// per campaign. Every cap must be smaller than `2^bitsize`.
+#[derive(Clone)]
+pub struct CounterCaps {
+    caps: Vec<u64>,
+    bitsize: usize,
+    bp_gens: BulletproofGens,
+}
+
+impl CounterCaps {
+    /// Creates caps for a catalog, returns `None` if the bitsize is not
+    /// supported by the range proof or a cap does not fit into it
+    pub fn new(caps: Vec<u64>, bitsize: usize) -> Option<CounterCaps> {
+        if !(bitsize == 8 || bitsize == 16 || bitsize == 32 || bitsize == 64) {
+            return None;
+        }
+        if bitsize < 64 && caps.iter().any(|cap| *cap >> bitsize != 0) {
+            return None;
+        }
+        let number_of_values = (2 * caps.len()).next_power_of_two();
+        let bp_gens = BulletproofGens::new(bitsize, number_of_values);
+        Some(CounterCaps {
+            caps,
+            bitsize,
+            bp_gens,
+        })
+    }
+
+    /// Creates the same cap for every incentive of the catalog
+    pub fn uniform(cap: u64, incentive_catalog_size: u64, bitsize: usize) -> Option<CounterCaps> {
+        CounterCaps::new(vec![cap; incentive_catalog_size as usize], bitsize)
+    }
+
+    /// Returns the cap for each entry of the state vector
+    pub fn caps(&self) -> &[u64] {
+        &self.caps
+    }
+
+    /// Returns the bitsize of the range proofs
+    pub fn bitsize(&self) -> usize {
+        self.bitsize
+    }
+}
+
+/// Proof that every entry of the private state vector is a small non-negative
+/// counter within its cap
+///
+/// Each entry `a_i` is committed as `V_i = a_i * B + r_i * B_blinding`. An
+/// aggregated range proof shows that `a_i` (and `cap_i - a_i` if caps are
+/// configured) lies in `[0, 2^bitsize)`, and a linking proof shows that the
+/// `V_i` commit to the same vector as the commitment `C` of the linear proof.
+#[derive(Clone, Debug)]
+pub struct CounterBoundsProof {
+    range_proof: RangeProof,
+    state_commitments: Vec<CompressedRistretto>,
+    link_commitments: Vec<CompressedRistretto>,
+    link_commitment: CompressedRistretto,
+    link_responses: Vec<Scalar>,
+    link_blinding_responses: Vec<Scalar>,
+    link_blinding_response: Scalar,
+}
+
+impl CounterBoundsProof {
+    /// Returns the commitments `V_i` to the state entries
+    pub fn state_commitments(&self) -> &[CompressedRistretto] {
+        &self.state_commitments
+    }
+
+    /// Serializes the proof as
+    /// `n || V_0..V_{n-1} || A_0..A_{n-1} || A_C || z_0..z_{n-1} || w_0..w_{n-1} || w || range proof`
+    /// where `n` is a 32-bit little endian integer
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let n = self.state_commitments.len();
+        let mut buf = Vec::with_capacity(4 + (4 * n + 2) * 32);
+        buf.extend_from_slice(&(n as u32).to_le_bytes());
+        for point in self.state_commitments.iter() {
+            buf.extend_from_slice(point.as_bytes());
+        }
+        for point in self.link_commitments.iter() {
+            buf.extend_from_slice(point.as_bytes());
+        }
+        buf.extend_from_slice(self.link_commitment.as_bytes());
+        for scalar in self.link_responses.iter() {
+            buf.extend_from_slice(scalar.as_bytes());
+        }
+        for scalar in self.link_blinding_responses.iter() {
+            buf.extend_from_slice(scalar.as_bytes());
+        }
+        buf.extend_from_slice(self.link_blinding_response.as_bytes());
+        buf.extend_from_slice(&self.range_proof.to_bytes());
+        buf
+    }
+
+    /// Deserializes a proof created with `to_bytes`
+    pub fn from_bytes(slice: &[u8]) -> Option<CounterBoundsProof> {
+        if slice.len() < 4 {
+            return None;
+        }
+        let n = u32::from_le_bytes(slice[..4].try_into().unwrap()) as usize;
+        // `n` is untrusted, the length overflows on 32-bit targets
+        let length = n.checked_mul(4)?.checked_add(2)?.checked_mul(32)?;
+        if slice.len() - 4 < length {
+            return None;
+        }
+        let mut chunks = slice[4..4 + length].chunks(32);
+
+        let mut read_point = || chunks.next().map(CompressedRistretto::from_slice);
+        let state_commitments = (0..n)
+            .map(|_| read_point())
+            .collect::<Option<Vec<CompressedRistretto>>>()?;
+        let link_commitments = (0..n)
+            .map(|_| read_point())
+            .collect::<Option<Vec<CompressedRistretto>>>()?;
+        let link_commitment = read_point()?;
+
+        let mut read_scalar = || Scalar::from_canonical_bytes(chunks.next()?.try_into().ok()?);
+        let link_responses = (0..n)
+            .map(|_| read_scalar())
+            .collect::<Option<Vec<Scalar>>>()?;
+        let link_blinding_responses = (0..n)
+            .map(|_| read_scalar())
+            .collect::<Option<Vec<Scalar>>>()?;
+        let link_blinding_response = read_scalar()?;
+
+        let range_proof = RangeProof::from_bytes(&slice[4 + length..]).ok()?;
+
+        Some(CounterBoundsProof {
+            range_proof,
+            state_commitments,
+            link_commitments,
+            link_commitment,
+            link_responses,
+            link_blinding_responses,
+            link_blinding_response,
+        })
+    }
+}
+
+/// Generates a counter bounds proof for the state vector committed in the
+/// linear proof commitment `C = <a, G> + r * B + <a, b> * F`
+///
+/// Without caps, every entry is proven to lie in `[0, 2^STATE_ENTRY_BITSIZE)`
+/// using the generators `bp_gen`. Returns the proof and the blindings of the
+/// state commitments `V_i`, or `None` if an entry is not within its bounds or
+/// the sizes of the vectors and caps differ.
+pub(crate) fn counter_bounds_proof<R: RngCore + CryptoRng>(
+    ps_gen: &PedersenGens,
+    bp_gen: &BulletproofGens,
+    counter_caps: Option<&CounterCaps>,
+    private_value: &[Scalar],
+    public_value: &[Scalar],
+    g: &[RistrettoPoint],
+    f: &RistrettoPoint,
+    b: &RistrettoPoint,
+    c: &CompressedRistretto,
+    r: &Scalar,
+    rng: &mut R,
+) -> Option<(CounterBoundsProof, Vec<Scalar>)> {
+    let n = private_value.len();
+    let (bp_gen, bitsize) = bounds_parameters(bp_gen, counter_caps);
+    if public_value.len() != n
+        || g.len() != n
+        || counter_caps.map_or(false, |caps| caps.caps.len() != n)
+    {
+        return None;
+    }
+
+    // every entry must be a small non-negative integer within its cap
+    let values = private_value
+        .iter()
+        .map(|a| scalar_to_u64(a).filter(|a| bitsize == 64 || *a >> bitsize == 0))
+        .collect::<Option<Vec<u64>>>()?;
+    let cap_differences: Vec<u64> = match counter_caps {
+        Some(caps) => values
+            .iter()
+            .zip(caps.caps.iter())
+            .map(|(a, cap)| cap.checked_sub(*a))
+            .collect::<Option<Vec<u64>>>()?,
+        None => vec![],
+    };
+
+    let blindings: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+    let state_commitments: Vec<CompressedRistretto> = values
+        .iter()
+        .zip(blindings.iter())
+        .map(|(a, r_i)| ps_gen.commit(Scalar::from(*a), *r_i).compress())
+        .collect();
+
+    let mut transcript = Transcript::new(b"counter bounds proof");
+    append_statement(
+        &mut transcript,
+        n,
+        bitsize,
+        counter_caps,
+        c,
+        &state_commitments,
+    );
+
+    // Linking proof: knowledge of a_i, r_i, r with
+    // V_i = a_i * B + r_i * B_blinding and C = <a, G + b * F> + r * B
+    let k: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+    let t: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+    let t_c = Scalar::random(rng);
+
+    let link_commitments: Vec<CompressedRistretto> = k
+        .iter()
+        .zip(t.iter())
+        .map(|(k_i, t_i)| ps_gen.commit(*k_i, *t_i).compress())
+        .collect();
+    let k_b: Scalar = k
+        .iter()
+        .zip(public_value.iter())
+        .map(|(k_i, b_i)| k_i * b_i)
+        .sum();
+    let link_commitment = RistrettoPoint::vartime_multiscalar_mul(
+        k.iter().chain(iter::once(&k_b)).chain(iter::once(&t_c)),
+        g.iter().chain(iter::once(f)).chain(iter::once(b)),
+    )
+    .compress();
+
+    for point in link_commitments.iter() {
+        transcript.append_message(b"A_i", point.as_bytes());
+    }
+    transcript.append_message(b"A_C", link_commitment.as_bytes());
+    let e = challenge_scalar(&mut transcript);
+
+    let link_responses: Vec<Scalar> = k
+        .iter()
+        .zip(private_value.iter())
+        .map(|(k_i, a_i)| k_i + e * a_i)
+        .collect();
+    let link_blinding_responses: Vec<Scalar> = t
+        .iter()
+        .zip(blindings.iter())
+        .map(|(t_i, r_i)| t_i + e * r_i)
+        .collect();
+    let link_blinding_response = t_c + e * r;
+
+    // Aggregated range proof over a_i and cap_i - a_i, where cap_i - a_i is
+    // committed with blinding -r_i so that the verifier can derive it as
+    // cap_i * B - V_i. Padding values are zero with zero blinding.
+    let number_of_values = (values.len() + cap_differences.len()).next_power_of_two();
+    let range_values: Vec<u64> = values
+        .iter()
+        .chain(cap_differences.iter())
+        .cloned()
+        .chain(iter::repeat(0))
+        .take(number_of_values)
+        .collect();
+    let range_blindings: Vec<Scalar> = blindings
+        .iter()
+        .cloned()
+        .chain(blindings.iter().take(cap_differences.len()).map(|r_i| -r_i))
+        .chain(iter::repeat(Scalar::zero()))
+        .take(number_of_values)
+        .collect();
+
+    let (range_proof, _) = RangeProof::prove_multiple_with_rng(
+        bp_gen,
+        ps_gen,
+        &mut transcript,
+        &range_values,
+        &range_blindings,
+        bitsize,
+        rng,
+    )
+    .ok()?;
+
+    let proof = CounterBoundsProof {
+        range_proof,
+        state_commitments,
+        link_commitments,
+        link_commitment,
+        link_responses,
+        link_blinding_responses,
+        link_blinding_response,
+    };
+    Some((proof, blindings))
+}
+
+/// Verifies a counter bounds proof against the linear proof commitments
+pub(crate) fn counter_bounds_verify<R: RngCore + CryptoRng>(
+    ps_gen: &PedersenGens,
+    bp_gen: &BulletproofGens,
+    counter_caps: Option<&CounterCaps>,
+    proof: &CounterBoundsProof,
+    public_value: &[Scalar],
+    g: &[RistrettoPoint],
+    f: &RistrettoPoint,
+    b: &RistrettoPoint,
+    c: &CompressedRistretto,
+    precomputation: Option<BoundsPrecomputation>,
+    rng: &mut R,
+) -> bool {
+    let n = g.len();
+    let (bp_gen, bitsize) = bounds_parameters(bp_gen, counter_caps);
+    if counter_caps.map_or(false, |caps| caps.caps.len() != n)
+        || proof.state_commitments.len() != n
+        || proof.link_commitments.len() != n
+        || proof.link_responses.len() != n
+        || proof.link_blinding_responses.len() != n
+        || public_value.len() != n
+    {
+        return false;
+    }
+    // B_blinding of the Pedersen commitments coincides with B of the linear
+    // proof, which allows to check all equations in one multiscalar mul
+    if *b != ps_gen.B_blinding {
+        return false;
+    }
+
+    let mut transcript = Transcript::new(b"counter bounds proof");
+    append_statement(
+        &mut transcript,
+        n,
+        bitsize,
+        counter_caps,
+        c,
+        &proof.state_commitments,
+    );
+    for point in proof.link_commitments.iter() {
+        transcript.append_message(b"A_i", point.as_bytes());
+    }
+    transcript.append_message(b"A_C", proof.link_commitment.as_bytes());
+    let e = challenge_scalar(&mut transcript);
+
+    // Check all linking equations at once with random weights rho_i:
+    //   sum rho_i * (z_i * B + w_i * B_blinding - A_i - e * V_i)
+    //   + <z, G> + <z, b> * F + w * B - A_C - e * C == 0
+    let rho: Vec<Scalar> = (0..n).map(|_| Scalar::random(rng)).collect();
+
+    let z = &proof.link_responses;
+    let w = &proof.link_blinding_responses;
+    let rho_z: Scalar = rho
+        .iter()
+        .zip(z.iter())
+        .map(|(rho_i, z_i)| rho_i * z_i)
+        .sum();
+    let rho_w: Scalar = rho
+        .iter()
+        .zip(w.iter())
+        .map(|(rho_i, w_i)| rho_i * w_i)
+        .sum();
+    // <z, b> is already folded into the generators of a policy precomputation
+    let z_b: Option<Scalar> = match precomputation {
+        Some(BoundsPrecomputation::Policy(_)) => None,
+        _ => Some(
+            z.iter()
+                .zip(public_value.iter())
+                .map(|(z_i, b_i)| z_i * b_i)
+                .sum(),
+        ),
+    };
+
+    // Scalars of the fixed generators G, F, B and B of the Pedersen
+    // commitments, in the order of `bounds_precomputation` (or
+    // `policy_precomputation` without F)
+    let static_scalars = z
+        .iter()
+        .cloned()
+        .chain(z_b)
+        .chain(iter::once(rho_w + proof.link_blinding_response))
+        .chain(iter::once(rho_z));
+    let dynamic_scalars = iter::once(-Scalar::one())
+        .chain(iter::once(-e))
+        .chain(rho.iter().map(|rho_i| -rho_i))
+        .chain(rho.iter().map(|rho_i| -(rho_i * e)));
+    let dynamic_points = iter::once(proof.link_commitment.decompress())
+        .chain(iter::once(c.decompress()))
+        .chain(
+            proof
+                .link_commitments
+                .iter()
+                .map(|point| point.decompress()),
+        )
+        .chain(
+            proof
+                .state_commitments
+                .iter()
+                .map(|point| point.decompress()),
+        );
+
+    let check = match precomputation {
+        Some(BoundsPrecomputation::Generators(table))
+        | Some(BoundsPrecomputation::Policy(table)) => {
+            table.optional_mixed_multiscalar_mul(static_scalars, dynamic_scalars, dynamic_points)
+        }
+        None => {
+            let static_points = g
+                .iter()
+                .map(|point| Some(*point))
+                .chain(iter::once(Some(*f)))
+                .chain(iter::once(Some(*b)))
+                .chain(iter::once(Some(ps_gen.B)));
+            RistrettoPoint::optional_multiscalar_mul(
+                static_scalars.chain(dynamic_scalars),
+                static_points.chain(dynamic_points),
+            )
+        }
+    };
+    match check {
+        Some(check) if check.is_identity() => {}
+        _ => return false,
+    }
+
+    // Commitments of the aggregated range proof: V_i, cap_i * B - V_i and
+    // the identity for padding
+    let mut range_commitments: Vec<CompressedRistretto> = proof.state_commitments.clone();
+    if let Some(caps) = counter_caps {
+        for (v_i, cap) in proof.state_commitments.iter().zip(caps.caps.iter()) {
+            match v_i.decompress() {
+                Some(v_i) => {
+                    range_commitments.push((Scalar::from(*cap) * ps_gen.B - v_i).compress())
+                }
+                None => return false,
+            }
+        }
+    }
+    range_commitments.resize(
+        range_commitments.len().next_power_of_two(),
+        RistrettoPoint::identity().compress(),
+    );
+
+    proof
+        .range_proof
+        .verify_multiple_with_rng(
+            bp_gen,
+            ps_gen,
+            &mut transcript,
+            &range_commitments,
+            bitsize,
+            rng,
+        )
+        .is_ok()
+}
+
+/// Precomputed tables for the fixed generators of the linking equations, also
+/// used for the linear proof
+#[derive(Clone, Copy)]
+pub(crate) enum BoundsPrecomputation<'a> {
+    /// Tables for `G`, `F`, `B` and `B` of the Pedersen commitments, see
+    /// `bounds_precomputation`
+    Generators(&'a VartimeRistrettoPrecomputation),
+    /// Tables with the policy vector folded into the generators, see
+    /// `policy_precomputation`
+    Policy(&'a VartimeRistrettoPrecomputation),
+}
+
+/// Precomputes tables for the fixed generators of the linking equations,
+/// `G`, `F`, `B` and `B` of the Pedersen commitments
+pub(crate) fn bounds_precomputation(
+    ps_gen: &PedersenGens,
+    g: &[RistrettoPoint],
+    f: &RistrettoPoint,
+    b: &RistrettoPoint,
+) -> VartimeRistrettoPrecomputation {
+    VartimeRistrettoPrecomputation::new(
+        g.iter()
+            .chain(iter::once(f))
+            .chain(iter::once(b))
+            .chain(iter::once(&ps_gen.B)),
+    )
+}
+
+/// Precomputes tables for the fixed generators of the linking equations and the
+/// linear proof for a fixed policy vector, `G_i + b_i * F`, `B` and `B` of the
+/// Pedersen commitments
+///
+/// Since `<z, G> + <z, b> * F = sum z_i * (G_i + b_i * F)`, the policy vector
+/// is folded into the generators once for all proofs against the same policy.
+pub(crate) fn policy_precomputation(
+    ps_gen: &PedersenGens,
+    g: &[RistrettoPoint],
+    f: &RistrettoPoint,
+    b: &RistrettoPoint,
+    public_value: &[Scalar],
+) -> VartimeRistrettoPrecomputation {
+    let folded: Vec<RistrettoPoint> = g
+        .iter()
+        .zip(public_value.iter())
+        .map(|(g_i, b_i)| g_i + b_i * f)
+        .collect();
+    VartimeRistrettoPrecomputation::new(
+        folded
+            .iter()
+            .chain(iter::once(b))
+            .chain(iter::once(&ps_gen.B)),
+    )
+}
+
+/// Selects generators and bitsize, either from the caps or the defaults for
+/// the non-negativity proof
+fn bounds_parameters<'a>(
+    bp_gen: &'a BulletproofGens,
+    counter_caps: Option<&'a CounterCaps>,
+) -> (&'a BulletproofGens, usize) {
+    match counter_caps {
+        Some(caps) => (&caps.bp_gens, caps.bitsize),
+        None => (bp_gen, STATE_ENTRY_BITSIZE),
+    }
+}
+
+/// Appends the public statement of the counter bounds proof to the transcript
+fn append_statement(
+    transcript: &mut Transcript,
+    n: usize,
+    bitsize: usize,
+    counter_caps: Option<&CounterCaps>,
+    c: &CompressedRistretto,
+    state_commitments: &[CompressedRistretto],
+) {
+    transcript.append_message(b"dom-sep", b"counter-bounds-proof v1");
+    transcript.append_u64(b"n", n as u64);
+    transcript.append_u64(b"bitsize", bitsize as u64);
+    if let Some(caps) = counter_caps {
+        for cap in caps.caps.iter() {
+            transcript.append_u64(b"cap", *cap);
+        }
+    }
+    transcript.append_message(b"C", c.as_bytes());
+    for point in state_commitments.iter() {
+        transcript.append_message(b"V_i", point.as_bytes());
+    }
+}
+
+/// Derives a challenge scalar from the transcript
+fn challenge_scalar(transcript: &mut Transcript) -> Scalar {
+    let mut buf = [0u8; 64];
+    transcript.challenge_bytes(b"e", &mut buf);
+    Scalar::from_bytes_mod_order_wide(&buf)
+}
+
+/// Converts a scalar to an u64, if it is a small non-negative integer
+pub(crate) fn scalar_to_u64(scalar: &Scalar) -> Option<u64> {
+    let bytes = scalar.as_bytes();
+    if bytes[8..].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    Some(u64::from_le_bytes(bytes[..8].try_into().unwrap()))
+}
@@ -0,0 +1,162 @@
+use crate::policy::Policy;
+use crate::sparse::SparseState;
+use curve25519_dalek::scalar::Scalar;
+use std::collections::HashMap;
+
+/// Incentive of a catalog
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Incentive {
+    id: String,
+    retired: bool,
+}
+
+/// Mapping of human-readable incentive IDs (e.g. UUIDs) to the positions of
+/// the state and policy vectors
+///
+/// Positions are stable across versions: new incentives are appended, and
+/// retired incentives keep their position (with weight and count zero) so
+/// that the vectors of older and newer versions stay compatible. Every
+/// addition or retirement increments the version of the catalog.
+#[derive(Clone, Debug, Default)]
+pub struct IncentiveCatalog {
+    version: u32,
+    incentives: Vec<Incentive>,
+    positions: HashMap<String, usize>,
+}
+
+impl IncentiveCatalog {
+    /// Creates an empty catalog
+    pub fn new() -> IncentiveCatalog {
+        IncentiveCatalog::default()
+    }
+
+    /// Returns the version of the catalog
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the size of the catalog, including retired incentives
+    pub fn incentive_catalog_size(&self) -> u64 {
+        self.incentives.len() as u64
+    }
+
+    /// Adds an incentive and returns its position, returns `None` if the ID
+    /// is already in use (also by a retired incentive)
+    pub fn add(&mut self, id: &str) -> Option<usize> {
+        if self.positions.contains_key(id) {
+            return None;
+        }
+        let position = self.incentives.len();
+        self.incentives.push(Incentive {
+            id: id.to_string(),
+            retired: false,
+        });
+        self.positions.insert(id.to_string(), position);
+        self.version += 1;
+        Some(position)
+    }
+
+    /// Retires an incentive, returns `false` if the ID is unknown or already
+    /// retired
+    pub fn retire(&mut self, id: &str) -> bool {
+        match self.positions.get(id) {
+            Some(position) if !self.incentives[*position].retired => {
+                self.incentives[*position].retired = true;
+                self.version += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the position of an incentive
+    pub fn position(&self, id: &str) -> Option<usize> {
+        self.positions.get(id).cloned()
+    }
+
+    /// Returns the ID of the incentive at a position
+    pub fn id(&self, position: usize) -> Option<&str> {
+        self.incentives
+            .get(position)
+            .map(|incentive| incentive.id.as_str())
+    }
+
+    /// Checks if an incentive is known and not retired
+    pub fn is_active(&self, id: &str) -> bool {
+        self.positions
+            .get(id)
+            .map_or(false, |position| !self.incentives[*position].retired)
+    }
+
+    /// Builds the state vector for `rewards_proof_generation` from a map of
+    /// incentive ID to the number of interactions
+    ///
+    /// Interactions with retired incentives are not rewarded and set to zero.
+    /// Returns `None` if an ID is unknown.
+    pub fn state_vector(&self, counts: &HashMap<String, u64>) -> Option<Vec<Scalar>> {
+        let mut state = vec![Scalar::zero(); self.incentives.len()];
+        for (id, count) in counts.iter() {
+            let position = self.position(id)?;
+            if !self.incentives[position].retired {
+                state[position] = Scalar::from(*count);
+            }
+        }
+        Some(state)
+    }
+
+    /// Builds the sparse state for `rewards_proof_generation_sparse` from a
+    /// map of incentive ID to the number of interactions
+    ///
+    /// Interactions with retired incentives are not rewarded and dropped.
+    /// Returns `None` if an ID is unknown.
+    pub fn sparse_state(&self, counts: &HashMap<String, u64>) -> Option<SparseState> {
+        let mut state = SparseState::new(self.incentive_catalog_size());
+        for (id, count) in counts.iter() {
+            let position = self.position(id)?;
+            if !self.incentives[position].retired {
+                state.set(position, *count);
+            }
+        }
+        Some(state)
+    }
+
+    /// Builds the policy vector for `rewards_proof_generation` from a map of
+    /// incentive ID to weight
+    ///
+    /// Returns `None` if an ID is unknown or retired.
+    pub fn policy_vector(&self, weights: &HashMap<String, u64>) -> Option<Vec<Scalar>> {
+        Some(
+            self.weights(weights)?
+                .into_iter()
+                .map(Scalar::from)
+                .collect(),
+        )
+    }
+
+    /// Builds a policy for the catalog from a map of incentive ID to weight
+    ///
+    /// Returns `None` if an ID is unknown or retired, or the validity window
+    /// is empty.
+    pub fn policy(
+        &self,
+        id: u64,
+        version: u32,
+        weights: &HashMap<String, u64>,
+        valid_from: u64,
+        valid_until: u64,
+    ) -> Option<Policy> {
+        Policy::new(id, version, self.weights(weights)?, valid_from, valid_until)
+    }
+
+    /// Maps the weights to the positions of the catalog
+    fn weights(&self, weights: &HashMap<String, u64>) -> Option<Vec<u64>> {
+        let mut policy = vec![0u64; self.incentives.len()];
+        for (id, weight) in weights.iter() {
+            if !self.is_active(id) {
+                return None;
+            }
+            policy[self.position(id)?] = *weight;
+        }
+        Some(policy)
+    }
+}
@@ -0,0 +1,511 @@
+use crate::bounds::{CounterCaps, STATE_ENTRY_BITSIZE};
+use crate::predicate::{TierTable, PREDICATE_BITSIZE};
+use bulletproofs::r1cs::{
+    ConstraintSystem, LinearCombination, Prover, R1CSError, R1CSProof, Variable, Verifier,
+};
+use bulletproofs::{BulletproofGens, PedersenGens};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+use std::convert::TryInto;
+
+/// Non-linear bonus rule, adds `weight * state[left] * state[right]` to the
+/// score, e.g. a bonus for users that engaged with two related incentives
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BonusRule {
+    pub left: usize,
+    pub right: usize,
+    pub weight: u64,
+}
+
+/// Public description of a rewards circuit
+///
+/// The circuit computes the score
+/// `<state, policy_vector> + sum weight * state[left] * state[right]`
+/// over the committed state, constrains every state entry to be a
+/// non-negative counter (within its cap, if configured), and outputs either
+/// the score or the payout of the tier the score falls into.
+#[derive(Clone)]
+pub struct RewardsCircuit {
+    policy_vector: Vec<u64>,
+    counter_caps: Option<CounterCaps>,
+    bonus_rules: Vec<BonusRule>,
+    tier_table: Option<TierTable>,
+}
+
+impl RewardsCircuit {
+    /// Creates a rewards circuit, returns `None` if the caps do not match the
+    /// size of the policy vector or a bonus rule refers to a non-existing entry
+    pub fn new(
+        policy_vector: Vec<u64>,
+        counter_caps: Option<CounterCaps>,
+        bonus_rules: Vec<BonusRule>,
+        tier_table: Option<TierTable>,
+    ) -> Option<RewardsCircuit> {
+        let n = policy_vector.len();
+        if counter_caps
+            .as_ref()
+            .map_or(false, |caps| caps.caps().len() != n)
+            || bonus_rules
+                .iter()
+                .any(|rule| rule.left >= n || rule.right >= n)
+        {
+            return None;
+        }
+        Some(RewardsCircuit {
+            policy_vector,
+            counter_caps,
+            bonus_rules,
+            tier_table,
+        })
+    }
+
+    /// Returns the policy vector
+    pub fn policy_vector(&self) -> &[u64] {
+        &self.policy_vector
+    }
+
+    /// Returns the per-incentive caps
+    pub fn counter_caps(&self) -> Option<&CounterCaps> {
+        self.counter_caps.as_ref()
+    }
+
+    /// Returns the bonus rules
+    pub fn bonus_rules(&self) -> &[BonusRule] {
+        &self.bonus_rules
+    }
+
+    /// Returns the tier table
+    pub fn tier_table(&self) -> Option<&TierTable> {
+        self.tier_table.as_ref()
+    }
+
+    /// Computes the reward for a state, returns `None` if the state does not
+    /// satisfy the constraints of the circuit
+    ///
+    /// With a tier table, the threshold gadgets range check the difference
+    /// between the score and every threshold below it to
+    /// `[0, 2^PREDICATE_BITSIZE)`, so scores can only be proven up to
+    /// `first threshold + 2^PREDICATE_BITSIZE - 1`.
+    pub fn reward(&self, state: &[u64]) -> Option<u64> {
+        let score = self.score(state)?;
+        match &self.tier_table {
+            Some(table) => {
+                if let Some(first) = table.thresholds().first() {
+                    if score >= *first && (score - first) >> PREDICATE_BITSIZE != 0 {
+                        return None;
+                    }
+                }
+                table.payout(table.tier(score))
+            }
+            None => Some(score),
+        }
+    }
+
+    /// Generates the Bulletproof generators for the circuit
+    pub fn generators(&self) -> BulletproofGens {
+        BulletproofGens::new(self.multipliers().next_power_of_two(), 1)
+    }
+
+    /// Returns the bitsize of the state entries
+    fn state_bitsize(&self) -> usize {
+        self.counter_caps
+            .as_ref()
+            .map_or(STATE_ENTRY_BITSIZE, |caps| caps.bitsize())
+    }
+
+    /// Returns the size of the state vector
+    pub fn incentive_catalog_size(&self) -> usize {
+        self.policy_vector.len()
+    }
+
+    /// Returns the number of multipliers of the circuit
+    pub(crate) fn multipliers(&self) -> usize {
+        let ranges_per_entry = match self.counter_caps {
+            Some(_) => 2,
+            None => 1,
+        };
+        let thresholds = self
+            .tier_table
+            .as_ref()
+            .map_or(0, |table| table.thresholds().len());
+        self.policy_vector.len() * ranges_per_entry * self.state_bitsize()
+            + self.bonus_rules.len()
+            + thresholds * (PREDICATE_BITSIZE + 2)
+    }
+
+    /// Computes the score of a state, returns `None` if a state entry is out
+    /// of range or the score overflows
+    fn score(&self, state: &[u64]) -> Option<u64> {
+        if state.len() != self.policy_vector.len() {
+            return None;
+        }
+        let bitsize = self.state_bitsize();
+        let in_range = match &self.counter_caps {
+            Some(caps) => state
+                .iter()
+                .zip(caps.caps().iter())
+                .all(|(a, cap)| a <= cap),
+            None => bitsize == 64 || state.iter().all(|a| *a >> bitsize == 0),
+        };
+        if !in_range {
+            return None;
+        }
+
+        let mut score: u64 = 0;
+        for (a, b) in state.iter().zip(self.policy_vector.iter()) {
+            score = score.checked_add(a.checked_mul(*b)?)?;
+        }
+        for rule in self.bonus_rules.iter() {
+            let bonus = state[rule.left]
+                .checked_mul(state[rule.right])?
+                .checked_mul(rule.weight)?;
+            score = score.checked_add(bonus)?;
+        }
+        Some(score)
+    }
+
+    /// Appends the public description of the circuit to the transcript
+    pub(crate) fn append_to_transcript(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"dom-sep", b"rewards-circuit v1");
+        transcript.append_u64(b"n", self.policy_vector.len() as u64);
+        for b_i in self.policy_vector.iter() {
+            transcript.append_u64(b"policy", *b_i);
+        }
+        transcript.append_u64(b"bitsize", self.state_bitsize() as u64);
+        if let Some(caps) = &self.counter_caps {
+            for cap in caps.caps().iter() {
+                transcript.append_u64(b"cap", *cap);
+            }
+        }
+        for rule in self.bonus_rules.iter() {
+            transcript.append_u64(b"bonus left", rule.left as u64);
+            transcript.append_u64(b"bonus right", rule.right as u64);
+            transcript.append_u64(b"bonus weight", rule.weight);
+        }
+        if let Some(table) = &self.tier_table {
+            for threshold in table.thresholds().iter() {
+                transcript.append_u64(b"threshold", *threshold);
+            }
+            for payout in table.payouts().iter() {
+                transcript.append_u64(b"payout", *payout);
+            }
+        }
+    }
+}
+
+/// R1CS proof for a rewards circuit with the commitments to the state entries
+/// and the reward
+#[derive(Clone, Debug)]
+pub struct CircuitProof {
+    proof: R1CSProof,
+    state_commitments: Vec<CompressedRistretto>,
+    reward_commitment: CompressedRistretto,
+}
+
+impl CircuitProof {
+    /// Returns the commitment to the reward
+    pub fn reward_commitment(&self) -> &CompressedRistretto {
+        &self.reward_commitment
+    }
+
+    /// Serializes the proof as `n || V_0..V_{n-1} || V_reward || R1CS proof`,
+    /// where `n` is a 32-bit little endian integer
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = (self.state_commitments.len() as u32).to_le_bytes().to_vec();
+        for point in self.state_commitments.iter() {
+            buf.extend_from_slice(point.as_bytes());
+        }
+        buf.extend_from_slice(self.reward_commitment.as_bytes());
+        buf.extend_from_slice(&self.proof.to_bytes());
+        buf
+    }
+
+    /// Deserializes a proof created with `to_bytes`
+    pub fn from_bytes(slice: &[u8]) -> Option<CircuitProof> {
+        if slice.len() < 4 {
+            return None;
+        }
+        let n = u32::from_le_bytes(slice[..4].try_into().unwrap()) as usize;
+        // `n` is untrusted, the length overflows on 32-bit targets
+        let length = n.checked_add(1)?.checked_mul(32)?;
+        if slice.len() - 4 < length {
+            return None;
+        }
+        let mut points = slice[4..4 + length]
+            .chunks(32)
+            .map(CompressedRistretto::from_slice);
+        let state_commitments = (0..n)
+            .map(|_| points.next())
+            .collect::<Option<Vec<CompressedRistretto>>>()?;
+        let reward_commitment = points.next()?;
+        let proof = R1CSProof::from_bytes(&slice[4 + length..]).ok()?;
+        Some(CircuitProof {
+            proof,
+            state_commitments,
+            reward_commitment,
+        })
+    }
+}
+
+/// Generates an R1CS proof that the committed reward is the output of the
+/// rewards circuit on the committed state
+///
+/// Returns the proof and the blinding of the reward commitment, so that the
+/// client can open the reward to the issuer.
+pub fn circuit_proof_generation(
+    pedersen_gens: &PedersenGens,
+    bulletproof_gens: &BulletproofGens,
+    circuit: &RewardsCircuit,
+    state: &[u64],
+) -> Result<(CircuitProof, Scalar), R1CSError> {
+    circuit_proof_generation_with_rng(
+        pedersen_gens,
+        bulletproof_gens,
+        circuit,
+        state,
+        &mut rand::thread_rng(),
+    )
+}
+
+/// Generates an R1CS proof like `circuit_proof_generation`, with the
+/// blindings of the commitments drawn from `rng`
+///
+/// The R1CS prover of the bulletproofs crate still seeds its internal
+/// transcript RNG from the thread RNG.
+pub fn circuit_proof_generation_with_rng<R: RngCore + CryptoRng>(
+    pedersen_gens: &PedersenGens,
+    bulletproof_gens: &BulletproofGens,
+    circuit: &RewardsCircuit,
+    state: &[u64],
+    rng: &mut R,
+) -> Result<(CircuitProof, Scalar), R1CSError> {
+    let reward = circuit.reward(state).ok_or(R1CSError::GadgetError {
+        description: "State does not satisfy the rewards circuit".to_string(),
+    })?;
+
+    let mut transcript = Transcript::new(b"rewards circuit");
+    circuit.append_to_transcript(&mut transcript);
+    let mut prover = Prover::new(pedersen_gens, &mut transcript);
+
+    let (state_commitments, state_vars): (Vec<CompressedRistretto>, Vec<Variable>) = state
+        .iter()
+        .map(|a| prover.commit(Scalar::from(*a), Scalar::random(rng)))
+        .unzip();
+    let reward_blinding = Scalar::random(rng);
+    let (reward_commitment, reward_var) = prover.commit(Scalar::from(reward), reward_blinding);
+
+    rewards_gadget(&mut prover, circuit, &state_vars, Some(state), reward_var)?;
+    let proof = prover.prove(bulletproof_gens)?;
+
+    Ok((
+        CircuitProof {
+            proof,
+            state_commitments,
+            reward_commitment,
+        },
+        reward_blinding,
+    ))
+}
+
+/// Verifies an R1CS proof for a rewards circuit
+pub fn circuit_proof_verification(
+    pedersen_gens: &PedersenGens,
+    bulletproof_gens: &BulletproofGens,
+    circuit: &RewardsCircuit,
+    proof: &CircuitProof,
+) -> bool {
+    if proof.state_commitments.len() != circuit.policy_vector.len() {
+        return false;
+    }
+
+    let mut transcript = Transcript::new(b"rewards circuit");
+    circuit.append_to_transcript(&mut transcript);
+    let mut verifier = Verifier::new(&mut transcript);
+
+    let state_vars: Vec<Variable> = proof
+        .state_commitments
+        .iter()
+        .map(|point| verifier.commit(*point))
+        .collect();
+    let reward_var = verifier.commit(proof.reward_commitment);
+
+    if rewards_gadget(&mut verifier, circuit, &state_vars, None, reward_var).is_err() {
+        return false;
+    }
+    verifier
+        .verify(&proof.proof, pedersen_gens, bulletproof_gens)
+        .is_ok()
+}
+
+/// Rewards gadget, constrains `reward` to be the output of the circuit on
+/// the committed `state`
+pub fn rewards_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    circuit: &RewardsCircuit,
+    state: &[Variable],
+    state_assignment: Option<&[u64]>,
+    reward: Variable,
+) -> Result<(), R1CSError> {
+    let output = circuit_output_gadget(cs, circuit, state, state_assignment)?;
+    cs.constrain(output - reward);
+    Ok(())
+}
+
+/// Circuit output gadget, returns the output of the circuit on the committed
+/// `state`
+pub fn circuit_output_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    circuit: &RewardsCircuit,
+    state: &[Variable],
+    state_assignment: Option<&[u64]>,
+) -> Result<LinearCombination, R1CSError> {
+    let bitsize = circuit.state_bitsize();
+
+    // every state entry is a non-negative counter within its cap
+    for (i, a_i) in state.iter().enumerate() {
+        let a_assignment = state_assignment.map(|a| a[i]);
+        match &circuit.counter_caps {
+            Some(caps) => cap_gadget(cs, *a_i, a_assignment, caps.caps()[i], bitsize)?,
+            None => range_gadget(cs, (*a_i).into(), a_assignment, bitsize)?,
+        }
+    }
+
+    // score = <state, policy_vector> + non-linear bonus rules
+    let mut score = inner_product_gadget(state, &circuit.policy_vector);
+    for rule in circuit.bonus_rules.iter() {
+        score = score + bonus_gadget(cs, state[rule.left], state[rule.right], rule.weight);
+    }
+    let score_assignment = state_assignment.and_then(|a| circuit.score(a));
+
+    match &circuit.tier_table {
+        Some(table) => tier_gadget(
+            cs,
+            score,
+            score_assignment,
+            table.thresholds(),
+            table.payouts(),
+            PREDICATE_BITSIZE,
+        ),
+        None => Ok(score),
+    }
+}
+
+/// Range gadget, constrains `v` to `[0, 2^n)` by a bit decomposition
+pub fn range_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    mut v: LinearCombination,
+    v_assignment: Option<u64>,
+    n: usize,
+) -> Result<(), R1CSError> {
+    let mut exp_2 = Scalar::one();
+    for i in 0..n {
+        // a * b = 0 and a = 1 - b, so b is a bit
+        let (a, b, o) = cs.allocate_multiplier(v_assignment.map(|v| {
+            let bit: u64 = (v >> i) & 1;
+            ((1 - bit).into(), bit.into())
+        }))?;
+        cs.constrain(o.into());
+        cs.constrain(a + (b - 1u64));
+
+        v = v - b * exp_2;
+        exp_2 = exp_2 + exp_2;
+    }
+    cs.constrain(v);
+    Ok(())
+}
+
+/// Cap gadget, constrains `0 <= a <= cap`
+pub fn cap_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    a: Variable,
+    a_assignment: Option<u64>,
+    cap: u64,
+    n: usize,
+) -> Result<(), R1CSError> {
+    range_gadget(cs, a.into(), a_assignment, n)?;
+    range_gadget(
+        cs,
+        LinearCombination::from(cap) - a,
+        a_assignment.map(|a| cap.wrapping_sub(a)),
+        n,
+    )
+}
+
+/// Inner product gadget, returns `<state, policy_vector>` for a public policy
+/// vector
+pub fn inner_product_gadget(state: &[Variable], policy_vector: &[u64]) -> LinearCombination {
+    state
+        .iter()
+        .zip(policy_vector.iter())
+        .map(|(a_i, b_i)| (*a_i, Scalar::from(*b_i)))
+        .collect()
+}
+
+/// Bonus gadget, returns `weight * left * right`
+pub fn bonus_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    left: Variable,
+    right: Variable,
+    weight: u64,
+) -> LinearCombination {
+    let (_, _, product) = cs.multiply(left.into(), right.into());
+    product * Scalar::from(weight)
+}
+
+/// Threshold gadget, returns a bit that is `1` if and only if
+/// `score >= threshold`
+///
+/// With `x = score - threshold` the gadget constrains
+/// `d = ge * x - (1 - ge) * (x + 1)` to `[0, 2^n)`, i.e. `x >= 0` if the bit
+/// is set and `x < 0` otherwise.
+pub fn threshold_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    score: LinearCombination,
+    score_assignment: Option<u64>,
+    threshold: u64,
+    n: usize,
+) -> Result<Variable, R1CSError> {
+    // ge * (1 - ge) = 0
+    let (ge, one_minus_ge, zero) = cs.allocate_multiplier(score_assignment.map(|s| {
+        let ge = Scalar::from((s >= threshold) as u64);
+        (ge, Scalar::one() - ge)
+    }))?;
+    cs.constrain(zero.into());
+    cs.constrain(ge + one_minus_ge - 1u64);
+
+    // d = 2 * ge * x - x - 1 + ge
+    let x = score - threshold;
+    let (_, _, ge_x) = cs.multiply(ge.into(), x.clone());
+    let d = ge_x * Scalar::from(2u64) - x + ge - 1u64;
+    let d_assignment = score_assignment.map(|s| {
+        if s >= threshold {
+            s - threshold
+        } else {
+            threshold - 1 - s
+        }
+    });
+    range_gadget(cs, d, d_assignment, n)?;
+
+    Ok(ge)
+}
+
+/// Tier gadget, returns the payout of the tier the score falls into
+pub fn tier_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    score: LinearCombination,
+    score_assignment: Option<u64>,
+    thresholds: &[u64],
+    payouts: &[u64],
+    n: usize,
+) -> Result<LinearCombination, R1CSError> {
+    // payout = payouts[0] + sum ge_j * (payouts[j + 1] - payouts[j])
+    let mut payout = LinearCombination::from(payouts[0]);
+    for (j, threshold) in thresholds.iter().enumerate() {
+        let ge = threshold_gadget(cs, score.clone(), score_assignment, *threshold, n)?;
+        payout = payout + ge * (Scalar::from(payouts[j + 1]) - Scalar::from(payouts[j]));
+    }
+    Ok(payout)
+}
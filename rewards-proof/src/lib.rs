@@ -1 +1,23 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub mod api;
+pub mod bounds;
+#[cfg(feature = "std")]
+pub mod catalog;
+#[cfg(feature = "r1cs")]
+pub mod circuits;
+#[cfg(feature = "r1cs")]
+pub mod noise;
+pub mod policy;
+pub mod predicate;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod sparse;
+#[cfg(feature = "std")]
+pub mod stream;
+pub mod verifier;
+pub mod wire;
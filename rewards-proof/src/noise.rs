@@ -0,0 +1,399 @@
+use crate::circuits::{circuit_output_gadget, RewardsCircuit};
+use bulletproofs::r1cs::{
+    ConstraintSystem, LinearCombination, Prover, R1CSError, R1CSProof, Variable, Verifier,
+};
+use bulletproofs::{BulletproofGens, PedersenGens};
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::{CryptoRng, Rng, RngCore};
+use std::convert::TryInto;
+
+/// Maximum number of coin flips of the noise distribution
+pub const MAX_NOISE_COINS: usize = 4096;
+
+/// Configuration of the noise distribution
+///
+/// The noise is the sum of `coins` fair coin flips, i.e. binomially
+/// distributed in `[0, coins]` with mean `coins / 2`, a discrete
+/// approximation of the Gaussian mechanism. Each coin is the XOR of a secret
+/// client coin and a public issuer coin, so it is fair as long as one of
+/// both parties is honest.
+///
+/// The noise is not centered, so that the noisy reward stays a non-negative
+/// integer: a noisy reward exceeds the reward by `coins / 2` on average. The
+/// issuer corrects the bias by subtracting `mean` from every noisy reward it
+/// aggregates, e.g. `m * mean()` from the sum of `m` noisy rewards.
+#[derive(Clone, Copy, Debug)]
+pub struct NoiseConfig {
+    coins: usize,
+}
+
+impl NoiseConfig {
+    /// Creates a noise configuration, returns `None` if the number of coins
+    /// is zero or exceeds `MAX_NOISE_COINS`
+    pub fn new(coins: usize) -> Option<NoiseConfig> {
+        if coins == 0 || coins > MAX_NOISE_COINS {
+            return None;
+        }
+        Some(NoiseConfig { coins })
+    }
+
+    /// Returns the number of coin flips
+    pub fn coins(&self) -> usize {
+        self.coins
+    }
+
+    /// Returns the inclusive range `(min, max)` of the noise
+    pub fn range(&self) -> (u64, u64) {
+        (0, self.coins as u64)
+    }
+
+    /// Returns the mean `coins / 2` of the noise, the bias of every noisy
+    /// reward
+    pub fn mean(&self) -> f64 {
+        self.coins as f64 / 2.0
+    }
+
+    /// Generates the Bulletproof generators for a noisy rewards proof
+    pub fn generators(&self, circuit: &RewardsCircuit) -> BulletproofGens {
+        BulletproofGens::new((circuit.multipliers() + self.coins).next_power_of_two(), 1)
+    }
+}
+
+/// Commitments to the secret client coins, sent to the issuer before the
+/// issuer contributes its randomness
+#[derive(Clone, Debug)]
+pub struct NoiseCommitment {
+    commitments: Vec<CompressedRistretto>,
+}
+
+impl NoiseCommitment {
+    /// Serializes the commitments as concatenated compressed points
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.commitments
+            .iter()
+            .flat_map(|point| point.to_bytes().to_vec())
+            .collect()
+    }
+
+    /// Deserializes commitments created with `to_bytes`
+    pub fn from_bytes(slice: &[u8]) -> Option<NoiseCommitment> {
+        if slice.len() % 32 != 0 {
+            return None;
+        }
+        let commitments = slice
+            .chunks(32)
+            .map(CompressedRistretto::from_slice)
+            .collect();
+        Some(NoiseCommitment { commitments })
+    }
+}
+
+/// Secret client coins and blindings of a `NoiseCommitment`, kept by the client
+#[derive(Clone, Debug)]
+pub struct NoiseOpening {
+    coins: Vec<bool>,
+    blindings: Vec<Scalar>,
+}
+
+/// Proof of a noisy reward, revealing `reward + noise` but not the reward
+#[derive(Clone, Debug)]
+pub struct NoisyRewardProof {
+    proof: R1CSProof,
+    state_commitments: Vec<CompressedRistretto>,
+    noisy_reward: u64,
+}
+
+impl NoisyRewardProof {
+    /// Returns the noisy reward, which is biased by `NoiseConfig::mean`
+    pub fn noisy_reward(&self) -> u64 {
+        self.noisy_reward
+    }
+
+    /// Serializes the proof as `noisy reward || n || V_0..V_{n-1} || R1CS proof`,
+    /// where `noisy reward` is a 64-bit and `n` a 32-bit little endian integer
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = self.noisy_reward.to_le_bytes().to_vec();
+        buf.extend_from_slice(&(self.state_commitments.len() as u32).to_le_bytes());
+        for point in self.state_commitments.iter() {
+            buf.extend_from_slice(point.as_bytes());
+        }
+        buf.extend_from_slice(&self.proof.to_bytes());
+        buf
+    }
+
+    /// Deserializes a proof created with `to_bytes`
+    pub fn from_bytes(slice: &[u8]) -> Option<NoisyRewardProof> {
+        if slice.len() < 12 {
+            return None;
+        }
+        let noisy_reward = u64::from_le_bytes(slice[..8].try_into().unwrap());
+        let n = u32::from_le_bytes(slice[8..12].try_into().unwrap()) as usize;
+        // `n` is untrusted, the length overflows on 32-bit targets
+        let length = n.checked_mul(32)?;
+        if slice.len() - 12 < length {
+            return None;
+        }
+        let state_commitments = slice[12..12 + length]
+            .chunks(32)
+            .map(CompressedRistretto::from_slice)
+            .collect();
+        let proof = R1CSProof::from_bytes(&slice[12 + length..]).ok()?;
+        Some(NoisyRewardProof {
+            proof,
+            state_commitments,
+            noisy_reward,
+        })
+    }
+}
+
+/// Samples the secret client coins and commits to them
+///
+/// This is the first step of the noise protocol, run on the client. The
+/// commitment is sent to the issuer, which replies with fresh
+/// `issuer_randomness` for `noisy_reward_proof_generation`.
+pub fn noise_commitment(
+    pedersen_gens: &PedersenGens,
+    config: &NoiseConfig,
+) -> (NoiseCommitment, NoiseOpening) {
+    noise_commitment_with_rng(pedersen_gens, config, &mut rand::thread_rng())
+}
+
+/// Samples the secret client coins like `noise_commitment`, with the coins and
+/// blindings drawn from `rng`
+pub fn noise_commitment_with_rng<R: RngCore + CryptoRng>(
+    pedersen_gens: &PedersenGens,
+    config: &NoiseConfig,
+    rng: &mut R,
+) -> (NoiseCommitment, NoiseOpening) {
+    let coins: Vec<bool> = (0..config.coins).map(|_| rng.gen()).collect();
+    let blindings: Vec<Scalar> = coins.iter().map(|_| Scalar::random(rng)).collect();
+    let commitments = coins
+        .iter()
+        .zip(blindings.iter())
+        .map(|(coin, blinding)| {
+            pedersen_gens
+                .commit(Scalar::from(*coin as u64), *blinding)
+                .compress()
+        })
+        .collect();
+    (
+        NoiseCommitment { commitments },
+        NoiseOpening { coins, blindings },
+    )
+}
+
+/// Generates a proof that the revealed noisy reward is the output of the
+/// rewards circuit on the committed state plus noise drawn from the
+/// configured distribution
+pub fn noisy_reward_proof_generation(
+    pedersen_gens: &PedersenGens,
+    bulletproof_gens: &BulletproofGens,
+    circuit: &RewardsCircuit,
+    config: &NoiseConfig,
+    state: &[u64],
+    noise_commitment: &NoiseCommitment,
+    noise_opening: &NoiseOpening,
+    issuer_randomness: &[u8; 32],
+) -> Result<NoisyRewardProof, R1CSError> {
+    noisy_reward_proof_generation_with_rng(
+        pedersen_gens,
+        bulletproof_gens,
+        circuit,
+        config,
+        state,
+        noise_commitment,
+        noise_opening,
+        issuer_randomness,
+        &mut rand::thread_rng(),
+    )
+}
+
+/// Generates a noisy reward proof like `noisy_reward_proof_generation`, with
+/// the blindings of the state commitments drawn from `rng`
+///
+/// The R1CS prover of the bulletproofs crate still seeds its internal
+/// transcript RNG from the thread RNG.
+pub fn noisy_reward_proof_generation_with_rng<R: RngCore + CryptoRng>(
+    pedersen_gens: &PedersenGens,
+    bulletproof_gens: &BulletproofGens,
+    circuit: &RewardsCircuit,
+    config: &NoiseConfig,
+    state: &[u64],
+    noise_commitment: &NoiseCommitment,
+    noise_opening: &NoiseOpening,
+    issuer_randomness: &[u8; 32],
+    rng: &mut R,
+) -> Result<NoisyRewardProof, R1CSError> {
+    let reward = circuit.reward(state).ok_or(R1CSError::GadgetError {
+        description: "State does not satisfy the rewards circuit".to_string(),
+    })?;
+    if noise_opening.coins.len() != config.coins
+        || noise_commitment.commitments.len() != config.coins
+    {
+        return Err(R1CSError::GadgetError {
+            description: "Noise commitment does not match the noise configuration".to_string(),
+        });
+    }
+
+    let mut transcript = Transcript::new(b"noisy rewards");
+    let issuer_coins = issuer_coins(
+        &mut transcript,
+        circuit,
+        config,
+        noise_commitment,
+        issuer_randomness,
+    );
+
+    // noise = sum client_coin XOR issuer_coin
+    let noise = noise_opening
+        .coins
+        .iter()
+        .zip(issuer_coins.iter())
+        .filter(|(client, issuer)| client != issuer)
+        .count() as u64;
+    let noisy_reward = reward.checked_add(noise).ok_or(R1CSError::GadgetError {
+        description: "Noisy reward overflows".to_string(),
+    })?;
+
+    let mut prover = Prover::new(pedersen_gens, &mut transcript);
+    let (state_commitments, state_vars): (Vec<CompressedRistretto>, Vec<Variable>) = state
+        .iter()
+        .map(|a| prover.commit(Scalar::from(*a), Scalar::random(rng)))
+        .unzip();
+    let coin_vars: Vec<Variable> = noise_opening
+        .coins
+        .iter()
+        .zip(noise_opening.blindings.iter())
+        .map(|(coin, blinding)| prover.commit(Scalar::from(*coin as u64), *blinding).1)
+        .collect();
+
+    noisy_reward_gadget(
+        &mut prover,
+        circuit,
+        &state_vars,
+        Some(state),
+        &coin_vars,
+        &issuer_coins,
+        noisy_reward,
+    )?;
+    let proof = prover.prove(bulletproof_gens)?;
+
+    Ok(NoisyRewardProof {
+        proof,
+        state_commitments,
+        noisy_reward,
+    })
+}
+
+/// Verifies a noisy reward proof for the noise commitment received from the
+/// client and the randomness contributed by the issuer
+pub fn noisy_reward_proof_verification(
+    pedersen_gens: &PedersenGens,
+    bulletproof_gens: &BulletproofGens,
+    circuit: &RewardsCircuit,
+    config: &NoiseConfig,
+    noise_commitment: &NoiseCommitment,
+    issuer_randomness: &[u8; 32],
+    proof: &NoisyRewardProof,
+) -> bool {
+    if proof.state_commitments.len() != circuit.incentive_catalog_size()
+        || noise_commitment.commitments.len() != config.coins
+    {
+        return false;
+    }
+
+    let mut transcript = Transcript::new(b"noisy rewards");
+    let issuer_coins = issuer_coins(
+        &mut transcript,
+        circuit,
+        config,
+        noise_commitment,
+        issuer_randomness,
+    );
+
+    let mut verifier = Verifier::new(&mut transcript);
+    let state_vars: Vec<Variable> = proof
+        .state_commitments
+        .iter()
+        .map(|point| verifier.commit(*point))
+        .collect();
+    let coin_vars: Vec<Variable> = noise_commitment
+        .commitments
+        .iter()
+        .map(|point| verifier.commit(*point))
+        .collect();
+
+    if noisy_reward_gadget(
+        &mut verifier,
+        circuit,
+        &state_vars,
+        None,
+        &coin_vars,
+        &issuer_coins,
+        proof.noisy_reward,
+    )
+    .is_err()
+    {
+        return false;
+    }
+    verifier
+        .verify(&proof.proof, pedersen_gens, bulletproof_gens)
+        .is_ok()
+}
+
+/// Noisy reward gadget, constrains
+/// `noisy_reward = output + sum client_coin XOR issuer_coin`, where every
+/// committed client coin is a bit
+pub fn noisy_reward_gadget<CS: ConstraintSystem>(
+    cs: &mut CS,
+    circuit: &RewardsCircuit,
+    state: &[Variable],
+    state_assignment: Option<&[u64]>,
+    client_coins: &[Variable],
+    issuer_coins: &[bool],
+    noisy_reward: u64,
+) -> Result<(), R1CSError> {
+    let output = circuit_output_gadget(cs, circuit, state, state_assignment)?;
+
+    let mut noise = LinearCombination::from(Scalar::zero());
+    for (coin, issuer_coin) in client_coins.iter().zip(issuer_coins.iter()) {
+        // coin * (1 - coin) = 0
+        let (_, _, zero) = cs.multiply((*coin).into(), LinearCombination::from(1u64) - *coin);
+        cs.constrain(zero.into());
+
+        // coin XOR issuer_coin is linear for a public issuer coin
+        noise = if *issuer_coin {
+            noise + (LinearCombination::from(1u64) - *coin)
+        } else {
+            noise + *coin
+        };
+    }
+
+    cs.constrain(output + noise - noisy_reward);
+    Ok(())
+}
+
+/// Derives the issuer coins from the transcript after binding the circuit,
+/// the client commitments and the issuer randomness
+fn issuer_coins(
+    transcript: &mut Transcript,
+    circuit: &RewardsCircuit,
+    config: &NoiseConfig,
+    noise_commitment: &NoiseCommitment,
+    issuer_randomness: &[u8; 32],
+) -> Vec<bool> {
+    circuit.append_to_transcript(transcript);
+    transcript.append_u64(b"coins", config.coins as u64);
+    for point in noise_commitment.commitments.iter() {
+        transcript.append_message(b"client coin", point.as_bytes());
+    }
+    transcript.append_message(b"issuer randomness", issuer_randomness);
+
+    let mut buf = vec![0u8; (config.coins + 7) / 8];
+    transcript.challenge_bytes(b"issuer coins", &mut buf);
+    (0..config.coins)
+        .map(|j| (buf[j / 8] >> (j % 8)) & 1 == 1)
+        .collect()
+}
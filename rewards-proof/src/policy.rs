@@ -0,0 +1,346 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+/// Magic bytes of a serialized policy manifest
+const POLICY_MANIFEST_MAGIC: &[u8; 4] = b"RPPM";
+/// Version of the policy manifest format
+const POLICY_MANIFEST_VERSION: u8 = 1;
+/// Size of the fixed header of a policy manifest
+const POLICY_MANIFEST_HEADER_SIZE: usize = 4 + 1 + 8 + 4 + 8 + 8 + 8;
+
+/// Policy vector of an issuer
+///
+/// A policy defines how interactions with the incentives of a catalog are
+/// rewarded, `reward = <state, weights>`. Policies are identified by an ID and
+/// a version, and are only valid within a validity window (unix timestamps in
+/// seconds, `valid_until` is exclusive).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Policy {
+    id: u64,
+    version: u32,
+    weights: Vec<u64>,
+    valid_from: u64,
+    valid_until: u64,
+}
+
+impl Policy {
+    /// Creates a policy, returns `None` if the validity window is empty
+    pub fn new(
+        id: u64,
+        version: u32,
+        weights: Vec<u64>,
+        valid_from: u64,
+        valid_until: u64,
+    ) -> Option<Policy> {
+        if valid_from >= valid_until {
+            return None;
+        }
+        Some(Policy {
+            id,
+            version,
+            weights,
+            valid_from,
+            valid_until,
+        })
+    }
+
+    /// Returns the ID of the policy
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the version of the policy
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the size of the incentive catalog the policy applies to
+    pub fn incentive_catalog_size(&self) -> u64 {
+        self.weights.len() as u64
+    }
+
+    /// Returns the weights of the policy
+    pub fn weights(&self) -> &[u64] {
+        &self.weights
+    }
+
+    /// Returns the policy vector as used by the rewards proof
+    pub fn policy_vector(&self) -> Vec<Scalar> {
+        self.weights.iter().map(|w| Scalar::from(*w)).collect()
+    }
+
+    /// Returns the validity window `(valid_from, valid_until)`
+    pub fn validity_window(&self) -> (u64, u64) {
+        (self.valid_from, self.valid_until)
+    }
+
+    /// Checks if the policy is valid at a unix timestamp
+    pub fn is_valid_at(&self, timestamp: u64) -> bool {
+        self.valid_from <= timestamp && timestamp < self.valid_until
+    }
+
+    /// Serializes the policy as canonical manifest
+    ///
+    /// `"RPPM" || format version (1 byte) || id || version (4 bytes) ||
+    /// catalog size || valid_from || valid_until || weights`, where all
+    /// integers are little endian and 8 bytes unless stated otherwise.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(POLICY_MANIFEST_HEADER_SIZE + 8 * self.weights.len());
+        buf.extend_from_slice(POLICY_MANIFEST_MAGIC);
+        buf.push(POLICY_MANIFEST_VERSION);
+        buf.extend_from_slice(&self.id.to_le_bytes());
+        buf.extend_from_slice(&self.version.to_le_bytes());
+        buf.extend_from_slice(&(self.weights.len() as u64).to_le_bytes());
+        buf.extend_from_slice(&self.valid_from.to_le_bytes());
+        buf.extend_from_slice(&self.valid_until.to_le_bytes());
+        for weight in self.weights.iter() {
+            buf.extend_from_slice(&weight.to_le_bytes());
+        }
+        buf
+    }
+
+    /// Deserializes a canonical manifest, rejects unknown format versions,
+    /// wrong lengths and trailing bytes
+    pub fn from_bytes(slice: &[u8]) -> Option<Policy> {
+        if slice.len() < POLICY_MANIFEST_HEADER_SIZE
+            || &slice[..4] != POLICY_MANIFEST_MAGIC
+            || slice[4] != POLICY_MANIFEST_VERSION
+        {
+            return None;
+        }
+        let read_u64 =
+            |offset: usize| u64::from_le_bytes(slice[offset..offset + 8].try_into().unwrap());
+        let id = read_u64(5);
+        let version = u32::from_le_bytes(slice[13..17].try_into().unwrap());
+        let size = read_u64(17);
+        let valid_from = read_u64(25);
+        let valid_until = read_u64(33);
+
+        let weights_bytes = &slice[POLICY_MANIFEST_HEADER_SIZE..];
+        if size.checked_mul(8) != Some(weights_bytes.len() as u64) {
+            return None;
+        }
+        let weights = weights_bytes
+            .chunks(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+
+        Policy::new(id, version, weights, valid_from, valid_until)
+    }
+}
+
+/// Public key of an issuer, used to verify signed policy manifests
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IssuerPublicKey(CompressedRistretto);
+
+impl IssuerPublicKey {
+    /// Serializes the public key as compressed point
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+
+    /// Deserializes a public key, returns `None` for invalid points
+    pub fn from_bytes(slice: &[u8]) -> Option<IssuerPublicKey> {
+        if slice.len() != 32 {
+            return None;
+        }
+        let point = CompressedRistretto::from_slice(slice);
+        point.decompress()?;
+        Some(IssuerPublicKey(point))
+    }
+}
+
+/// Signing key of an issuer, a Schnorr key over Ristretto
+#[derive(Clone)]
+pub struct IssuerSigningKey {
+    secret: Scalar,
+    public: IssuerPublicKey,
+}
+
+impl IssuerSigningKey {
+    /// Generates a new signing key
+    pub fn generate<T: RngCore + CryptoRng>(rng: &mut T) -> IssuerSigningKey {
+        let secret = Scalar::random(rng);
+        let public = IssuerPublicKey((secret * RISTRETTO_BASEPOINT_POINT).compress());
+        IssuerSigningKey { secret, public }
+    }
+
+    /// Returns the public key
+    pub fn public_key(&self) -> IssuerPublicKey {
+        self.public
+    }
+
+    /// Serializes the secret key
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    /// Deserializes a secret key, returns `None` for non-canonical scalars
+    pub fn from_bytes(slice: &[u8]) -> Option<IssuerSigningKey> {
+        let secret = Scalar::from_canonical_bytes(slice.try_into().ok()?)?;
+        let public = IssuerPublicKey((secret * RISTRETTO_BASEPOINT_POINT).compress());
+        Some(IssuerSigningKey { secret, public })
+    }
+
+    /// Signs the canonical manifest of a policy
+    #[cfg(feature = "std")]
+    pub fn sign(&self, policy: &Policy) -> SignedPolicy {
+        self.sign_with_rng(policy, &mut rand::thread_rng())
+    }
+
+    /// Signs the canonical manifest of a policy with the nonce drawn from `rng`
+    pub fn sign_with_rng<T: RngCore + CryptoRng>(
+        &self,
+        policy: &Policy,
+        rng: &mut T,
+    ) -> SignedPolicy {
+        let manifest = policy.to_bytes();
+
+        let k = Scalar::random(rng);
+        let r = (k * RISTRETTO_BASEPOINT_POINT).compress();
+        let e = signature_challenge(&self.public, &r, &manifest);
+        let s = k + e * self.secret;
+
+        SignedPolicy {
+            policy: policy.clone(),
+            signature: (r, s),
+        }
+    }
+}
+
+/// Policy manifest with the signature of the issuer
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedPolicy {
+    policy: Policy,
+    signature: (CompressedRistretto, Scalar),
+}
+
+impl SignedPolicy {
+    /// Returns the policy, without verifying the signature
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Verifies the signature of the issuer and the validity window, should
+    /// be run by the client before proving with the policy
+    pub fn verify(&self, issuer_key: &IssuerPublicKey, timestamp: u64) -> bool {
+        self.verify_signature(issuer_key) && self.policy.is_valid_at(timestamp)
+    }
+
+    /// Verifies the signature of the issuer
+    pub fn verify_signature(&self, issuer_key: &IssuerPublicKey) -> bool {
+        let public = match issuer_key.0.decompress() {
+            Some(point) => point,
+            None => return false,
+        };
+        let (r, s) = &self.signature;
+        let e = signature_challenge(issuer_key, r, &self.policy.to_bytes());
+
+        // s * B - e * P == R
+        RistrettoPoint::vartime_double_scalar_mul_basepoint(&-e, &public, s).compress() == *r
+    }
+
+    /// Serializes the signed policy as `R || s || manifest`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = self.signature.0.to_bytes().to_vec();
+        buf.extend_from_slice(self.signature.1.as_bytes());
+        buf.extend_from_slice(&self.policy.to_bytes());
+        buf
+    }
+
+    /// Deserializes a signed policy created with `to_bytes`
+    pub fn from_bytes(slice: &[u8]) -> Option<SignedPolicy> {
+        if slice.len() < 64 {
+            return None;
+        }
+        let r = CompressedRistretto::from_slice(&slice[..32]);
+        let s = Scalar::from_canonical_bytes(slice[32..64].try_into().unwrap())?;
+        let policy = Policy::from_bytes(&slice[64..])?;
+        Some(SignedPolicy {
+            policy,
+            signature: (r, s),
+        })
+    }
+}
+
+/// Registry of signed policies of an issuer, indexed by ID and version
+#[derive(Clone, Debug)]
+pub struct PolicyRegistry {
+    issuer_key: IssuerPublicKey,
+    policies: BTreeMap<u64, BTreeMap<u32, SignedPolicy>>,
+}
+
+impl PolicyRegistry {
+    /// Creates an empty registry for policies signed by `issuer_key`
+    pub fn new(issuer_key: IssuerPublicKey) -> PolicyRegistry {
+        PolicyRegistry {
+            issuer_key,
+            policies: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a signed policy, returns `false` if the signature is invalid or a
+    /// different policy with the same ID and version is already registered
+    pub fn insert(&mut self, signed_policy: SignedPolicy) -> bool {
+        if !signed_policy.verify_signature(&self.issuer_key) {
+            return false;
+        }
+        let versions = self
+            .policies
+            .entry(signed_policy.policy.id)
+            .or_insert_with(BTreeMap::new);
+        match versions.get(&signed_policy.policy.version) {
+            Some(existing) => existing.policy == signed_policy.policy,
+            None => {
+                versions.insert(signed_policy.policy.version, signed_policy);
+                true
+            }
+        }
+    }
+
+    /// Returns a specific version of a policy
+    pub fn get(&self, id: u64, version: u32) -> Option<&SignedPolicy> {
+        self.policies.get(&id)?.get(&version)
+    }
+
+    /// Returns the latest version of a policy that is valid at a timestamp
+    pub fn latest(&self, id: u64, timestamp: u64) -> Option<&SignedPolicy> {
+        self.policies
+            .get(&id)?
+            .values()
+            .rev()
+            .find(|signed_policy| signed_policy.policy.is_valid_at(timestamp))
+    }
+}
+
+/// Returns the current unix timestamp in seconds, or `0` if the system time is
+/// before the unix epoch
+#[cfg(feature = "std")]
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Computes the Schnorr challenge for a policy manifest
+fn signature_challenge(
+    issuer_key: &IssuerPublicKey,
+    r: &CompressedRistretto,
+    manifest: &[u8],
+) -> Scalar {
+    let mut transcript = Transcript::new(b"policy manifest signature");
+    transcript.append_message(b"P", issuer_key.0.as_bytes());
+    transcript.append_message(b"R", r.as_bytes());
+    transcript.append_message(b"manifest", manifest);
+
+    let mut buf = [0u8; 64];
+    transcript.challenge_bytes(b"e", &mut buf);
+    Scalar::from_bytes_mod_order_wide(&buf)
+}
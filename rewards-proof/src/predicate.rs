@@ -0,0 +1,276 @@
+use crate::bounds::scalar_to_u64;
+use alloc::vec;
+use alloc::vec::Vec;
+use bulletproofs::{inner_product, BulletproofGens, PedersenGens, RangeProof};
+use core::convert::TryInto;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::{
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    traits::{Identity, VartimeMultiscalarMul},
+};
+use merlin::Transcript;
+use rand::{CryptoRng, RngCore};
+
+/// Bitsize of the range proofs over the differences between score and
+/// thresholds, all thresholds must be smaller than `2^PREDICATE_BITSIZE`
+pub const PREDICATE_BITSIZE: usize = 32;
+
+/// Public threshold table for tiered payouts
+///
+/// A score `s = <state, policy_vector>` falls into tier `j` if
+/// `thresholds[j - 1] <= s < thresholds[j]`, where tier `0` covers all scores
+/// below the first threshold and the last tier all scores above the last
+/// threshold. Tier `j` pays out `payouts[j]`.
+///
+/// Scores of the last tier can only be proven up to
+/// `last threshold + 2^PREDICATE_BITSIZE - 1`.
+#[derive(Clone)]
+pub struct TierTable {
+    thresholds: Vec<u64>,
+    payouts: Vec<u64>,
+    bp_gens: BulletproofGens,
+}
+
+impl TierTable {
+    /// Creates a tier table, returns `None` if the thresholds are not strictly
+    /// increasing, not positive, too large for the range proofs, or if there
+    /// is not exactly one payout more than thresholds
+    pub fn new(thresholds: Vec<u64>, payouts: Vec<u64>) -> Option<TierTable> {
+        if payouts.len() != thresholds.len() + 1 {
+            return None;
+        }
+        if thresholds.windows(2).any(|pair| pair[0] >= pair[1])
+            || thresholds
+                .iter()
+                .any(|threshold| *threshold == 0 || *threshold >> PREDICATE_BITSIZE != 0)
+        {
+            return None;
+        }
+        let bp_gens = BulletproofGens::new(PREDICATE_BITSIZE, 2);
+        Some(TierTable {
+            thresholds,
+            payouts,
+            bp_gens,
+        })
+    }
+
+    /// Creates a table that pays out `reward` if the score is at least
+    /// `threshold`, and `0` otherwise
+    pub fn threshold(threshold: u64, reward: u64) -> Option<TierTable> {
+        TierTable::new(vec![threshold], vec![0, reward])
+    }
+
+    /// Returns the tier of a score
+    pub fn tier(&self, score: u64) -> usize {
+        self.thresholds
+            .iter()
+            .take_while(|threshold| **threshold <= score)
+            .count()
+    }
+
+    /// Returns the payout of a tier
+    pub fn payout(&self, tier: usize) -> Option<u64> {
+        self.payouts.get(tier).cloned()
+    }
+
+    /// Returns the thresholds
+    pub fn thresholds(&self) -> &[u64] {
+        &self.thresholds
+    }
+
+    /// Returns the payouts of all tiers
+    pub fn payouts(&self) -> &[u64] {
+        &self.payouts
+    }
+
+    /// Returns the inclusive lower and upper bound of the scores of a tier
+    fn bounds(&self, tier: usize) -> (u64, Option<u64>) {
+        let lower = match tier {
+            0 => 0,
+            _ => self.thresholds[tier - 1],
+        };
+        let upper = self.thresholds.get(tier).map(|threshold| threshold - 1);
+        (lower, upper)
+    }
+}
+
+/// Proof that the committed score `<state, policy_vector>` falls into a tier
+/// of a public tier table
+///
+/// The score is committed as `S = sum b_i * V_i`, where `V_i` are the
+/// commitments to the state entries of the counter bounds proof and `b_i` the
+/// entries of the policy vector. An aggregated range proof shows that
+/// `s - lower` and `upper - s` lie in `[0, 2^PREDICATE_BITSIZE)`.
+#[derive(Clone, Debug)]
+pub struct PredicateProof {
+    tier: u32,
+    range_proof: RangeProof,
+}
+
+impl PredicateProof {
+    /// Returns the proven tier
+    pub fn tier(&self) -> usize {
+        self.tier as usize
+    }
+
+    /// Returns the payout of the proven tier
+    pub fn payout(&self, tier_table: &TierTable) -> Option<u64> {
+        tier_table.payout(self.tier())
+    }
+
+    /// Serializes the proof as `tier || range proof`, where `tier` is a 32-bit
+    /// little endian integer
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = self.tier.to_le_bytes().to_vec();
+        buf.extend_from_slice(&self.range_proof.to_bytes());
+        buf
+    }
+
+    /// Deserializes a proof created with `to_bytes`
+    pub fn from_bytes(slice: &[u8]) -> Option<PredicateProof> {
+        if slice.len() < 4 {
+            return None;
+        }
+        let tier = u32::from_le_bytes(slice[..4].try_into().unwrap());
+        let range_proof = RangeProof::from_bytes(&slice[4..]).ok()?;
+        Some(PredicateProof { tier, range_proof })
+    }
+}
+
+/// Generates a predicate proof for the score `<private_value, public_value>`
+///
+/// `blindings` are the blindings of the state commitments `V_i` of the
+/// counter bounds proof. Returns `None` if the sizes of the vectors differ,
+/// the score is not a 64-bit integer, or the score exceeds the last threshold
+/// by `2^PREDICATE_BITSIZE` or more.
+pub(crate) fn predicate_proof<R: RngCore + CryptoRng>(
+    ps_gen: &PedersenGens,
+    tier_table: &TierTable,
+    private_value: &[Scalar],
+    public_value: &[Scalar],
+    state_commitments: &[CompressedRistretto],
+    blindings: &[Scalar],
+    rng: &mut R,
+) -> Option<PredicateProof> {
+    let n = private_value.len();
+    if public_value.len() != n || state_commitments.len() != n || blindings.len() != n {
+        return None;
+    }
+    let score = scalar_to_u64(&inner_product(private_value, public_value))?;
+    let tier = tier_table.tier(score);
+    let (lower, upper) = tier_table.bounds(tier);
+    if (score - lower) >> PREDICATE_BITSIZE != 0 {
+        return None;
+    }
+
+    // blinding of S = sum b_i * V_i
+    let rho: Scalar = blindings
+        .iter()
+        .zip(public_value.iter())
+        .map(|(r_i, b_i)| r_i * b_i)
+        .sum();
+    let s = score_commitment(public_value, state_commitments)?;
+
+    // s - lower is committed with blinding rho and upper - s with blinding
+    // -rho, the top tier has no upper bound and is padded with zero
+    let values = match upper {
+        Some(upper) => vec![score - lower, upper - score],
+        None => vec![score - lower, 0],
+    };
+    let range_blindings = match upper {
+        Some(_) => vec![rho, -rho],
+        None => vec![rho, Scalar::zero()],
+    };
+
+    let mut transcript = Transcript::new(b"predicate proof");
+    append_statement(&mut transcript, tier_table, &s, tier as u32);
+    let (range_proof, _) = RangeProof::prove_multiple_with_rng(
+        &tier_table.bp_gens,
+        ps_gen,
+        &mut transcript,
+        &values,
+        &range_blindings,
+        PREDICATE_BITSIZE,
+        rng,
+    )
+    .ok()?;
+
+    Some(PredicateProof {
+        tier: tier as u32,
+        range_proof,
+    })
+}
+
+/// Verifies a predicate proof against the state commitments of the counter
+/// bounds proof
+pub(crate) fn predicate_verify<R: RngCore + CryptoRng>(
+    ps_gen: &PedersenGens,
+    tier_table: &TierTable,
+    proof: &PredicateProof,
+    public_value: &[Scalar],
+    state_commitments: &[CompressedRistretto],
+    rng: &mut R,
+) -> bool {
+    let tier = proof.tier();
+    if tier >= tier_table.payouts.len() || public_value.len() != state_commitments.len() {
+        return false;
+    }
+    let s = match score_commitment(public_value, state_commitments) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    // Commitments to s - lower and upper - s
+    let (lower, upper) = tier_table.bounds(tier);
+    let commitments = vec![
+        (s - Scalar::from(lower) * ps_gen.B).compress(),
+        match upper {
+            Some(upper) => (Scalar::from(upper) * ps_gen.B - s).compress(),
+            None => RistrettoPoint::identity().compress(),
+        },
+    ];
+
+    let mut transcript = Transcript::new(b"predicate proof");
+    append_statement(&mut transcript, tier_table, &s, proof.tier);
+    proof
+        .range_proof
+        .verify_multiple_with_rng(
+            &tier_table.bp_gens,
+            ps_gen,
+            &mut transcript,
+            &commitments,
+            PREDICATE_BITSIZE,
+            rng,
+        )
+        .is_ok()
+}
+
+/// Computes the commitment to the score `S = sum b_i * V_i`
+fn score_commitment(
+    public_value: &[Scalar],
+    state_commitments: &[CompressedRistretto],
+) -> Option<RistrettoPoint> {
+    RistrettoPoint::optional_multiscalar_mul(
+        public_value,
+        state_commitments.iter().map(|point| point.decompress()),
+    )
+}
+
+/// Appends the public statement of the predicate proof to the transcript
+fn append_statement(
+    transcript: &mut Transcript,
+    tier_table: &TierTable,
+    s: &RistrettoPoint,
+    tier: u32,
+) {
+    transcript.append_message(b"dom-sep", b"predicate-proof v1");
+    transcript.append_u64(b"tiers", tier_table.payouts.len() as u64);
+    for threshold in tier_table.thresholds.iter() {
+        transcript.append_u64(b"threshold", *threshold);
+    }
+    for payout in tier_table.payouts.iter() {
+        transcript.append_u64(b"payout", *payout);
+    }
+    transcript.append_message(b"S", s.compress().as_bytes());
+    transcript.append_u64(b"tier", tier as u64);
+}
@@ -0,0 +1,194 @@
+use crate::api::RewardsProof;
+use crate::bounds::CounterCaps;
+use crate::policy::{Policy, SignedPolicy};
+use crate::predicate::{PredicateProof, TierTable};
+use crate::wire::SetupParameters;
+use bulletproofs::{BulletproofGens, PedersenGens};
+use std::convert::TryInto;
+
+pub use prost::Message;
+
+/// Generated protobuf messages of `proto/rewards.proto`
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/rewards_proof.v1.rs"));
+}
+
+/// Size of a compressed point
+const POINT_SIZE: usize = 32;
+
+/// Returns the setup parameters of an incentive catalog
+pub fn setup_parameters(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    incentive_catalog_size: u64,
+) -> proto::SetupParameters {
+    let parameters = SetupParameters::new(pedersen_gens, bulletproof_gens, incentive_catalog_size);
+    proto::SetupParameters {
+        incentive_catalog_size,
+        fingerprint: parameters.fingerprint().to_vec(),
+    }
+}
+
+/// Runs `rewards_proof_setup` for the setup parameters, returns `None` if the
+/// fingerprint does not match the derived generators
+pub fn setup_from_parameters(
+    parameters: &proto::SetupParameters,
+) -> Option<(Vec<PedersenGens>, Vec<BulletproofGens>)> {
+    let fingerprint = parameters.fingerprint[..].try_into().ok()?;
+    SetupParameters::from_parts(parameters.incentive_catalog_size, fingerprint).setup()
+}
+
+impl RewardsProof {
+    /// Converts the rewards proof to its protobuf message
+    pub fn to_protobuf(&self) -> proto::RewardsProof {
+        let (g, f, b, c) = &self.linear_proof_commitments;
+        proto::RewardsProof {
+            range_proof: self.range_proof.to_bytes(),
+            range_proof_commitments: self
+                .range_proof_commitments
+                .iter()
+                .map(|point| point.to_bytes().to_vec())
+                .collect(),
+            linear_proof: self.linear_proof.to_bytes(),
+            g: g.iter()
+                .map(|point| point.compress().to_bytes().to_vec())
+                .collect(),
+            f: f.compress().to_bytes().to_vec(),
+            b: b.compress().to_bytes().to_vec(),
+            c: c.to_bytes().to_vec(),
+            counter_bounds_proof: self.counter_bounds_proof.to_bytes(),
+            predicate_proof: self
+                .predicate_proof
+                .as_ref()
+                .map(|predicate_proof| predicate_proof.to_bytes()),
+        }
+    }
+
+    /// Reconstructs a rewards proof from its protobuf message
+    pub fn from_protobuf(message: &proto::RewardsProof) -> Option<RewardsProof> {
+        let range_proof_commitments = concat_points(&message.range_proof_commitments)?;
+        let g = concat_points(&message.g)?;
+        let mut proof = RewardsProof::from_bytes(
+            &message.range_proof,
+            &range_proof_commitments,
+            &message.linear_proof,
+            (&g, &message.f, &message.b, &message.c),
+            &message.counter_bounds_proof,
+        )?;
+        proof.predicate_proof = match &message.predicate_proof {
+            Some(predicate_proof) => Some(PredicateProof::from_bytes(predicate_proof)?),
+            None => None,
+        };
+        Some(proof)
+    }
+}
+
+impl Policy {
+    /// Converts the policy to its protobuf message
+    pub fn to_protobuf(&self) -> proto::Policy {
+        let (valid_from, valid_until) = self.validity_window();
+        proto::Policy {
+            id: self.id(),
+            version: self.version(),
+            weights: self.weights().to_vec(),
+            valid_from,
+            valid_until,
+        }
+    }
+
+    /// Reconstructs a policy from its protobuf message
+    pub fn from_protobuf(message: &proto::Policy) -> Option<Policy> {
+        Policy::new(
+            message.id,
+            message.version,
+            message.weights.clone(),
+            message.valid_from,
+            message.valid_until,
+        )
+    }
+}
+
+impl SignedPolicy {
+    /// Converts the signed policy to its protobuf message
+    pub fn to_protobuf(&self) -> proto::SignedPolicy {
+        let mut signature = self.to_bytes();
+        signature.truncate(2 * POINT_SIZE);
+        proto::SignedPolicy {
+            policy: Some(self.policy().to_protobuf()),
+            signature,
+        }
+    }
+
+    /// Reconstructs a signed policy from its protobuf message, the signature
+    /// is not verified
+    pub fn from_protobuf(message: &proto::SignedPolicy) -> Option<SignedPolicy> {
+        let policy = Policy::from_protobuf(message.policy.as_ref()?)?;
+        if message.signature.len() != 2 * POINT_SIZE {
+            return None;
+        }
+        let mut bytes = message.signature.clone();
+        bytes.extend_from_slice(&policy.to_bytes());
+        SignedPolicy::from_bytes(&bytes)
+    }
+}
+
+impl CounterCaps {
+    /// Converts the caps to their protobuf message
+    pub fn to_protobuf(&self) -> proto::CounterCaps {
+        proto::CounterCaps {
+            caps: self.caps().to_vec(),
+            bitsize: self.bitsize() as u32,
+        }
+    }
+
+    /// Reconstructs caps from their protobuf message
+    pub fn from_protobuf(message: &proto::CounterCaps) -> Option<CounterCaps> {
+        CounterCaps::new(message.caps.clone(), message.bitsize.try_into().ok()?)
+    }
+}
+
+impl TierTable {
+    /// Converts the tier table to its protobuf message
+    pub fn to_protobuf(&self) -> proto::TierTable {
+        proto::TierTable {
+            thresholds: self.thresholds().to_vec(),
+            payouts: self.payouts().to_vec(),
+        }
+    }
+
+    /// Reconstructs a tier table from its protobuf message
+    pub fn from_protobuf(message: &proto::TierTable) -> Option<TierTable> {
+        TierTable::new(message.thresholds.clone(), message.payouts.clone())
+    }
+}
+
+/// Returns the verification result of the proof at `index`, with the tier of
+/// the predicate proof if the proof is valid
+///
+/// The tier is only reported if `predicate_verified` is set, i.e. the
+/// predicate proof was verified against a tier table, otherwise the tier is
+/// just a claim of the prover.
+pub fn verification_result(
+    index: u64,
+    proof: &RewardsProof,
+    valid: bool,
+    predicate_verified: bool,
+) -> proto::VerificationResult {
+    let tier = match &proof.predicate_proof {
+        Some(predicate_proof) if valid && predicate_verified => Some(predicate_proof.tier() as u32),
+        _ => None,
+    };
+    proto::VerificationResult { index, valid, tier }
+}
+
+/// Concatenates compressed points, returns `None` if a point is not 32 bytes
+fn concat_points(points: &[Vec<u8>]) -> Option<Vec<u8>> {
+    let mut buf = Vec::with_capacity(points.len() * POINT_SIZE);
+    for point in points.iter() {
+        if point.len() != POINT_SIZE {
+            return None;
+        }
+        buf.extend_from_slice(point);
+    }
+    Some(buf)
+}
@@ -0,0 +1,491 @@
+use crate::api::RewardsProof;
+use crate::bounds::{CounterBoundsProof, CounterCaps};
+use crate::catalog::IncentiveCatalog;
+#[cfg(feature = "r1cs")]
+use crate::circuits::{BonusRule, CircuitProof, RewardsCircuit};
+#[cfg(feature = "r1cs")]
+use crate::noise::{NoiseCommitment, NoiseConfig, NoisyRewardProof};
+use crate::policy::{IssuerPublicKey, Policy, SignedPolicy};
+use crate::predicate::{PredicateProof, TierTable};
+use crate::sparse::SparseState;
+use crate::wire::SetupParameters;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::convert::TryInto;
+use std::fmt;
+
+/// Byte string, encoded as hex string in human-readable formats (e.g. JSON)
+/// and as bytes otherwise (e.g. CBOR, bincode)
+struct Bytes(Vec<u8>);
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(&self.0))
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+        if deserializer.is_human_readable() {
+            let encoded = String::deserialize(deserializer)?;
+            hex::decode(encoded).map(Bytes).map_err(de::Error::custom)
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+}
+
+/// Visitor for byte strings, also accepts sequences of bytes
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Bytes;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Bytes, E> {
+        Ok(Bytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Bytes, E> {
+        Ok(Bytes(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Bytes, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(Bytes(bytes))
+    }
+}
+
+/// Implements `Serialize` and `Deserialize` with the `to_bytes` and
+/// `from_bytes` encoding of a type
+macro_rules! serde_with_bytes {
+    ($type:ident) => {
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                Bytes(self.to_bytes().to_vec()).serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<$type, D::Error> {
+                let bytes = Bytes::deserialize(deserializer)?;
+                $type::from_bytes(&bytes.0)
+                    .ok_or_else(|| de::Error::custom(concat!("invalid ", stringify!($type))))
+            }
+        }
+    };
+}
+
+serde_with_bytes!(CounterBoundsProof);
+serde_with_bytes!(PredicateProof);
+#[cfg(feature = "r1cs")]
+serde_with_bytes!(CircuitProof);
+#[cfg(feature = "r1cs")]
+serde_with_bytes!(NoiseCommitment);
+#[cfg(feature = "r1cs")]
+serde_with_bytes!(NoisyRewardProof);
+serde_with_bytes!(IssuerPublicKey);
+
+/// Serialized form of a `RewardsProof`
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "RewardsProof")]
+struct RewardsProofRepr {
+    range_proof: Bytes,
+    range_proof_commitments: Bytes,
+    linear_proof: Bytes,
+    linear_proof_commitments: (Bytes, Bytes, Bytes, Bytes),
+    counter_bounds_proof: Bytes,
+    predicate_proof: Option<PredicateProof>,
+}
+
+impl Serialize for RewardsProof {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (range_proof, linear_proof, range_comm, linear_comm, bounds_proof) = self.to_bytes();
+        RewardsProofRepr {
+            range_proof: Bytes(range_proof),
+            range_proof_commitments: Bytes(range_comm),
+            linear_proof: Bytes(linear_proof),
+            linear_proof_commitments: (
+                Bytes(linear_comm.0),
+                Bytes(linear_comm.1),
+                Bytes(linear_comm.2),
+                Bytes(linear_comm.3),
+            ),
+            counter_bounds_proof: Bytes(bounds_proof),
+            predicate_proof: self.predicate_proof.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RewardsProof {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RewardsProof, D::Error> {
+        let repr = RewardsProofRepr::deserialize(deserializer)?;
+        let mut proof = RewardsProof::from_bytes(
+            &repr.range_proof.0,
+            &repr.range_proof_commitments.0,
+            &repr.linear_proof.0,
+            (
+                &repr.linear_proof_commitments.0 .0,
+                &repr.linear_proof_commitments.1 .0,
+                &repr.linear_proof_commitments.2 .0,
+                &repr.linear_proof_commitments.3 .0,
+            ),
+            &repr.counter_bounds_proof.0,
+        )
+        .ok_or_else(|| de::Error::custom("invalid RewardsProof"))?;
+        proof.predicate_proof = repr.predicate_proof;
+        Ok(proof)
+    }
+}
+
+/// Serialized form of a `Policy`
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Policy")]
+struct PolicyRepr {
+    id: u64,
+    version: u32,
+    weights: Vec<u64>,
+    valid_from: u64,
+    valid_until: u64,
+}
+
+impl Serialize for Policy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (valid_from, valid_until) = self.validity_window();
+        PolicyRepr {
+            id: self.id(),
+            version: self.version(),
+            weights: self.weights().to_vec(),
+            valid_from,
+            valid_until,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Policy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Policy, D::Error> {
+        let repr = PolicyRepr::deserialize(deserializer)?;
+        Policy::new(
+            repr.id,
+            repr.version,
+            repr.weights,
+            repr.valid_from,
+            repr.valid_until,
+        )
+        .ok_or_else(|| de::Error::custom("invalid Policy"))
+    }
+}
+
+/// Serialized form of a `SignedPolicy`, the signature is `R || s`
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "SignedPolicy")]
+struct SignedPolicyRepr {
+    policy: Policy,
+    signature: Bytes,
+}
+
+impl Serialize for SignedPolicy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut signature = self.to_bytes();
+        signature.truncate(64);
+        SignedPolicyRepr {
+            policy: self.policy().clone(),
+            signature: Bytes(signature),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SignedPolicy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SignedPolicy, D::Error> {
+        let repr = SignedPolicyRepr::deserialize(deserializer)?;
+        if repr.signature.0.len() != 64 {
+            return Err(de::Error::custom("invalid SignedPolicy signature"));
+        }
+        let mut bytes = repr.signature.0;
+        bytes.extend_from_slice(&repr.policy.to_bytes());
+        SignedPolicy::from_bytes(&bytes).ok_or_else(|| de::Error::custom("invalid SignedPolicy"))
+    }
+}
+
+/// Serialized form of `CounterCaps`
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "CounterCaps")]
+struct CounterCapsRepr {
+    caps: Vec<u64>,
+    bitsize: usize,
+}
+
+impl Serialize for CounterCaps {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CounterCapsRepr {
+            caps: self.caps().to_vec(),
+            bitsize: self.bitsize(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CounterCaps {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<CounterCaps, D::Error> {
+        let repr = CounterCapsRepr::deserialize(deserializer)?;
+        CounterCaps::new(repr.caps, repr.bitsize)
+            .ok_or_else(|| de::Error::custom("invalid CounterCaps"))
+    }
+}
+
+/// Serialized form of a `TierTable`
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "TierTable")]
+struct TierTableRepr {
+    thresholds: Vec<u64>,
+    payouts: Vec<u64>,
+}
+
+impl Serialize for TierTable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TierTableRepr {
+            thresholds: self.thresholds().to_vec(),
+            payouts: self.payouts().to_vec(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TierTable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TierTable, D::Error> {
+        let repr = TierTableRepr::deserialize(deserializer)?;
+        TierTable::new(repr.thresholds, repr.payouts)
+            .ok_or_else(|| de::Error::custom("invalid TierTable"))
+    }
+}
+
+/// Serialized form of a `NoiseConfig`
+#[cfg(feature = "r1cs")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "NoiseConfig")]
+struct NoiseConfigRepr {
+    coins: usize,
+}
+
+#[cfg(feature = "r1cs")]
+impl Serialize for NoiseConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        NoiseConfigRepr {
+            coins: self.coins(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "r1cs")]
+impl<'de> Deserialize<'de> for NoiseConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<NoiseConfig, D::Error> {
+        let repr = NoiseConfigRepr::deserialize(deserializer)?;
+        NoiseConfig::new(repr.coins).ok_or_else(|| de::Error::custom("invalid NoiseConfig"))
+    }
+}
+
+/// Serialized form of a `RewardsCircuit`
+#[cfg(feature = "r1cs")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "RewardsCircuit")]
+struct RewardsCircuitRepr {
+    policy_vector: Vec<u64>,
+    counter_caps: Option<CounterCaps>,
+    bonus_rules: Vec<BonusRule>,
+    tier_table: Option<TierTable>,
+}
+
+#[cfg(feature = "r1cs")]
+impl Serialize for RewardsCircuit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RewardsCircuitRepr {
+            policy_vector: self.policy_vector().to_vec(),
+            counter_caps: self.counter_caps().cloned(),
+            bonus_rules: self.bonus_rules().to_vec(),
+            tier_table: self.tier_table().cloned(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "r1cs")]
+impl<'de> Deserialize<'de> for RewardsCircuit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RewardsCircuit, D::Error> {
+        let repr = RewardsCircuitRepr::deserialize(deserializer)?;
+        RewardsCircuit::new(
+            repr.policy_vector,
+            repr.counter_caps,
+            repr.bonus_rules,
+            repr.tier_table,
+        )
+        .ok_or_else(|| de::Error::custom("invalid RewardsCircuit"))
+    }
+}
+
+/// Serialized form of an incentive of an `IncentiveCatalog`
+#[derive(Serialize, Deserialize)]
+struct IncentiveRepr {
+    id: String,
+    retired: bool,
+}
+
+/// Serialized form of an `IncentiveCatalog`, incentives are ordered by their
+/// positions
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "IncentiveCatalog")]
+struct IncentiveCatalogRepr {
+    version: u32,
+    incentives: Vec<IncentiveRepr>,
+}
+
+impl Serialize for IncentiveCatalog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let incentives = (0..self.incentive_catalog_size() as usize)
+            .filter_map(|position| self.id(position))
+            .map(|id| IncentiveRepr {
+                id: id.to_string(),
+                retired: !self.is_active(id),
+            })
+            .collect();
+        IncentiveCatalogRepr {
+            version: self.version(),
+            incentives,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for IncentiveCatalog {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<IncentiveCatalog, D::Error> {
+        let repr = IncentiveCatalogRepr::deserialize(deserializer)?;
+
+        // Replay additions and retirements, every operation increments the
+        // version
+        let mut catalog = IncentiveCatalog::new();
+        for incentive in repr.incentives.iter() {
+            if catalog.add(&incentive.id).is_none() {
+                return Err(de::Error::custom("duplicate incentive ID"));
+            }
+        }
+        for incentive in repr.incentives.iter().filter(|incentive| incentive.retired) {
+            catalog.retire(&incentive.id);
+        }
+        if catalog.version() != repr.version {
+            return Err(de::Error::custom("invalid IncentiveCatalog version"));
+        }
+        Ok(catalog)
+    }
+}
+
+/// Serialized form of a `SparseState`, the non-zero counters are
+/// `(position, count)` pairs
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "SparseState")]
+struct SparseStateRepr {
+    incentive_catalog_size: u64,
+    entries: Vec<(usize, u64)>,
+}
+
+impl Serialize for SparseState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SparseStateRepr {
+            incentive_catalog_size: self.incentive_catalog_size(),
+            entries: self.iter().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SparseState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SparseState, D::Error> {
+        let repr = SparseStateRepr::deserialize(deserializer)?;
+        SparseState::from_entries(repr.incentive_catalog_size, repr.entries)
+            .ok_or_else(|| de::Error::custom("invalid SparseState"))
+    }
+}
+
+/// Serialized form of `SetupParameters`
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "SetupParameters")]
+struct SetupParametersRepr {
+    incentive_catalog_size: u64,
+    fingerprint: Bytes,
+}
+
+impl Serialize for SetupParameters {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SetupParametersRepr {
+            incentive_catalog_size: self.incentive_catalog_size(),
+            fingerprint: Bytes(self.fingerprint().to_vec()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SetupParameters {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SetupParameters, D::Error> {
+        let repr = SetupParametersRepr::deserialize(deserializer)?;
+        let fingerprint = repr.fingerprint.0[..]
+            .try_into()
+            .map_err(|_| de::Error::custom("invalid SetupParameters"))?;
+        Ok(SetupParameters::from_parts(
+            repr.incentive_catalog_size,
+            fingerprint,
+        ))
+    }
+}
+
+/// Serde helpers for vectors of scalars (e.g. policy vectors), encoded as
+/// concatenated canonical 32-byte scalars
+///
+/// Use with `#[serde(with = "rewards_proof::serialization::scalars")]`.
+pub mod scalars {
+    use super::Bytes;
+    use curve25519_dalek::scalar::Scalar;
+    use serde::de::{self, Deserializer};
+    use serde::{Deserialize, Serialize, Serializer};
+    use std::convert::TryInto;
+
+    /// Serializes a vector of scalars
+    pub fn serialize<S: Serializer>(scalars: &[Scalar], serializer: S) -> Result<S::Ok, S::Error> {
+        Bytes(
+            scalars
+                .iter()
+                .flat_map(|scalar| scalar.to_bytes().to_vec())
+                .collect(),
+        )
+        .serialize(serializer)
+    }
+
+    /// Deserializes a vector of scalars, rejects non-canonical scalars
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Scalar>, D::Error> {
+        let bytes = Bytes::deserialize(deserializer)?;
+        if bytes.0.len() % 32 != 0 {
+            return Err(de::Error::custom("invalid length of scalars"));
+        }
+        bytes
+            .0
+            .chunks(32)
+            .map(|chunk| {
+                Scalar::from_canonical_bytes(chunk.try_into().unwrap())
+                    .ok_or_else(|| de::Error::custom("non-canonical scalar"))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+
+/// Sparse state vector, mapping the positions of the incentive catalog to the
+/// number of interactions
+///
+/// Users typically interact with few incentives of a large catalog. Only the
+/// non-zero counters are stored, and the commitment to the state in the
+/// linear proof is computed with a multiscalar multiplication over the
+/// non-zero entries only. The linear proof itself and the counter bounds
+/// proof still work on the dense state vector (`to_dense`), so their cost
+/// stays linear in the size of the catalog.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SparseState {
+    size: usize,
+    entries: BTreeMap<usize, u64>,
+}
+
+impl SparseState {
+    /// Creates an all-zero state for a catalog of `incentive_catalog_size`
+    pub fn new(incentive_catalog_size: u64) -> SparseState {
+        SparseState {
+            size: incentive_catalog_size as usize,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a state from `(position, count)` pairs, returns `None` if a
+    /// position is out of range
+    pub fn from_entries<I>(incentive_catalog_size: u64, entries: I) -> Option<SparseState>
+    where
+        I: IntoIterator<Item = (usize, u64)>,
+    {
+        let mut state = SparseState::new(incentive_catalog_size);
+        for (position, count) in entries {
+            if !state.set(position, count) {
+                return None;
+            }
+        }
+        Some(state)
+    }
+
+    /// Sets the counter at a position, returns `false` if the position is out
+    /// of range
+    pub fn set(&mut self, position: usize, count: u64) -> bool {
+        if position >= self.size {
+            return false;
+        }
+        if count == 0 {
+            self.entries.remove(&position);
+        } else {
+            self.entries.insert(position, count);
+        }
+        true
+    }
+
+    /// Returns the counter at a position
+    pub fn get(&self, position: usize) -> u64 {
+        self.entries.get(&position).cloned().unwrap_or(0)
+    }
+
+    /// Returns the size of the incentive catalog
+    pub fn incentive_catalog_size(&self) -> u64 {
+        self.size as u64
+    }
+
+    /// Returns the number of non-zero counters
+    pub fn non_zero_entries(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Iterates over the non-zero counters as `(position, count)` pairs in
+    /// ascending order of positions
+    pub fn iter(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.entries
+            .iter()
+            .map(|(position, count)| (*position, *count))
+    }
+
+    /// Expands the state to the dense state vector
+    pub fn to_dense(&self) -> Vec<Scalar> {
+        let mut state = vec![Scalar::zero(); self.size];
+        for (position, count) in self.iter() {
+            state[position] = Scalar::from(count);
+        }
+        state
+    }
+
+    /// Computes the inner product `<state, public_value>` over the non-zero
+    /// counters, returns `None` if `public_value` does not match the size of
+    /// the catalog
+    pub fn inner_product(&self, public_value: &[Scalar]) -> Option<Scalar> {
+        if public_value.len() != self.size {
+            return None;
+        }
+        Some(
+            self.iter()
+                .map(|(position, count)| Scalar::from(count) * public_value[position])
+                .sum(),
+        )
+    }
+
+    /// Computes the commitment of the linear proof
+    /// `C = <a, G> + r * B + <a, b> * F` over the non-zero counters, returns
+    /// `None` if the policy vector or the generators do not match the size of
+    /// the catalog
+    pub(crate) fn linear_commitment(
+        &self,
+        public_value: &[Scalar],
+        g: &[RistrettoPoint],
+        f: &RistrettoPoint,
+        b: &RistrettoPoint,
+        r: &Scalar,
+    ) -> Option<CompressedRistretto> {
+        if g.len() != self.size {
+            return None;
+        }
+        let result_inner_product = self.inner_product(public_value)?;
+        let c = RistrettoPoint::vartime_multiscalar_mul(
+            self.iter()
+                .map(|(_, count)| Scalar::from(count))
+                .chain(iter::once(*r))
+                .chain(iter::once(result_inner_product)),
+            self.iter()
+                .map(|(position, _)| &g[position])
+                .chain(iter::once(b))
+                .chain(iter::once(f)),
+        );
+        Some(c.compress())
+    }
+}
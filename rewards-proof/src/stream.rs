@@ -0,0 +1,194 @@
+use crate::api::RewardsProof;
+use crate::verifier::PreparedVerifier;
+use curve25519_dalek::scalar::Scalar;
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+
+/// Maximum size of a single record in a proof stream
+pub const MAX_STREAM_RECORD_SIZE: usize = 1 << 24;
+
+/// Number of sections of a record, see `write_rewards_proof`
+const STREAM_RECORD_SECTIONS: usize = 8;
+
+/// Number of proofs and results of a stream verification
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreamSummary {
+    /// Number of proofs that verified
+    pub valid: u64,
+    /// Number of proofs that did not verify or could not be decoded
+    pub invalid: u64,
+}
+
+/// Verifier for streams of rewards proofs that do not fit into memory
+///
+/// Proofs are read from any `Read` as length-prefixed records (see
+/// `write_rewards_proof`), verified in chunks of at most `chunk_size` proofs
+/// against the same policy vector, and a verdict line `<index> valid` or
+/// `<index> invalid` is written for every proof. Records that cannot be
+/// decoded are reported as invalid, the stream continues with the next record.
+pub struct StreamingVerifier {
+    verifier: PreparedVerifier,
+    chunk_size: usize,
+}
+
+impl StreamingVerifier {
+    /// Creates a streaming verifier, returns `None` if `chunk_size` is zero
+    pub fn new(verifier: PreparedVerifier, chunk_size: usize) -> Option<StreamingVerifier> {
+        if chunk_size == 0 {
+            return None;
+        }
+        Some(StreamingVerifier {
+            verifier,
+            chunk_size,
+        })
+    }
+
+    /// Verifies all proofs of `reader` and writes the verdicts to `writer`
+    ///
+    /// The policy vector is prepared once for the whole stream. Returns an
+    /// error if the policy vector does not match the incentive catalog, if
+    /// reading or writing fails, or if the stream ends within a record or
+    /// contains a record larger than `MAX_STREAM_RECORD_SIZE`.
+    pub fn verify<R: Read, W: Write>(
+        &self,
+        mut reader: R,
+        mut writer: W,
+        public_value: &[Scalar],
+    ) -> io::Result<StreamSummary> {
+        let policy = self.verifier.prepare_policy(public_value).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Policy vector does not match the incentive catalog",
+            )
+        })?;
+        let mut summary = StreamSummary::default();
+        let mut index: u64 = 0;
+        let mut chunk: Vec<Option<RewardsProof>> = Vec::with_capacity(self.chunk_size);
+
+        loop {
+            chunk.clear();
+            while chunk.len() < self.chunk_size {
+                match read_record(&mut reader)? {
+                    Some(record) => chunk.push(decode_record(&record)),
+                    None => break,
+                }
+            }
+            if chunk.is_empty() {
+                break;
+            }
+
+            let decoded: Vec<Option<&RewardsProof>> = chunk.iter().map(Option::as_ref).collect();
+            for verified in self
+                .verifier
+                .verify_decoded_batch_with_policy(&decoded, &policy)
+            {
+                if verified {
+                    summary.valid += 1;
+                    writeln!(writer, "{} valid", index)?;
+                } else {
+                    summary.invalid += 1;
+                    writeln!(writer, "{} invalid", index)?;
+                }
+                index += 1;
+            }
+        }
+        writer.flush()?;
+        Ok(summary)
+    }
+}
+
+/// Writes a rewards proof as record of a proof stream
+///
+/// A record is `length || section_0 || .. || section_7`, where every section
+/// is `length || bytes` and all lengths are 32-bit little endian integers. The
+/// sections are the range proof, the range proof commitments, the linear
+/// proof, `G`, `F`, `B`, `C` and the counter bounds proof, as returned by
+/// `RewardsProof::to_bytes`.
+pub fn write_rewards_proof<W: Write>(writer: &mut W, proof: &RewardsProof) -> io::Result<()> {
+    let (range_proof, linear_proof, range_comm, linear_comm, bounds_proof) = proof.to_bytes();
+    let sections = [
+        range_proof,
+        range_comm,
+        linear_proof,
+        linear_comm.0,
+        linear_comm.1,
+        linear_comm.2,
+        linear_comm.3,
+        bounds_proof,
+    ];
+
+    let length: usize = sections.iter().map(|section| 4 + section.len()).sum();
+    if length > MAX_STREAM_RECORD_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Rewards proof exceeds the maximum record size",
+        ));
+    }
+    writer.write_all(&(length as u32).to_le_bytes())?;
+    for section in sections.iter() {
+        writer.write_all(&(section.len() as u32).to_le_bytes())?;
+        writer.write_all(section)?;
+    }
+    Ok(())
+}
+
+/// Reads a single rewards proof record, returns `Ok(None)` at the end of the
+/// stream and `Ok(Some(None))` for records that cannot be decoded
+pub fn read_rewards_proof<R: Read>(reader: &mut R) -> io::Result<Option<Option<RewardsProof>>> {
+    Ok(read_record(reader)?.map(|record| decode_record(&record)))
+}
+
+/// Reads the bytes of the next record, returns `Ok(None)` at the end of the
+/// stream
+fn read_record<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0u8; 4];
+    let mut read = 0;
+    while read < length.len() {
+        match reader.read(&mut length[read..]) {
+            Ok(0) if read == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_STREAM_RECORD_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Record exceeds the maximum record size",
+        ));
+    }
+    let mut record = vec![0u8; length];
+    reader.read_exact(&mut record)?;
+    Ok(Some(record))
+}
+
+/// Decodes the sections of a record into a rewards proof
+fn decode_record(record: &[u8]) -> Option<RewardsProof> {
+    let mut sections: Vec<&[u8]> = Vec::with_capacity(STREAM_RECORD_SECTIONS);
+    let mut rest = record;
+    while sections.len() < STREAM_RECORD_SECTIONS {
+        if rest.len() < 4 {
+            return None;
+        }
+        let length = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+        if rest.len() - 4 < length {
+            return None;
+        }
+        sections.push(&rest[4..4 + length]);
+        rest = &rest[4 + length..];
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+
+    RewardsProof::from_bytes(
+        sections[0],
+        sections[1],
+        sections[2],
+        (sections[3], sections[4], sections[5], sections[6]),
+        sections[7],
+    )
+}
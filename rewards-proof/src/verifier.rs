@@ -0,0 +1,374 @@
+use crate::api::{linear_generators, verify_rewards_proof, Precomputation, RewardsProof};
+use crate::bounds::{
+    bounds_precomputation, policy_precomputation, BoundsPrecomputation, CounterCaps,
+};
+use crate::policy::{IssuerPublicKey, PolicyRegistry, SignedPolicy};
+use crate::predicate::TierTable;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use bulletproofs::{BulletproofGens, PedersenGens};
+use core::iter;
+use curve25519_dalek::ristretto::{
+    CompressedRistretto, RistrettoPoint, VartimeRistrettoPrecomputation,
+};
+use curve25519_dalek::scalar::Scalar;
+use rand::{CryptoRng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Verifier for a fixed incentive catalog, prepared once from the setup
+/// parameters
+///
+/// The generators of the linear proof `G`, `F`, `B` are derived and compressed
+/// once, and the multiscalar multiplications of the counter bounds proof and
+/// the linear proof use precomputed tables for them, which amortizes across
+/// many verifications. Proofs with generators that differ from the setup are
+/// rejected. The range proofs are verified by the bulletproofs crate with the
+/// Bulletproof generators of the setup, without precomputed tables.
+pub struct PreparedVerifier {
+    pedersen_gens: Vec<PedersenGens>,
+    bulletproof_gens: Vec<BulletproofGens>,
+    g: Vec<RistrettoPoint>,
+    f: RistrettoPoint,
+    b: RistrettoPoint,
+    compressed_generators: Vec<CompressedRistretto>,
+    precomputation: VartimeRistrettoPrecomputation,
+}
+
+impl PreparedVerifier {
+    /// Prepares a verifier from the output of `rewards_proof_setup`
+    pub fn new(
+        pedersen_gens: &[PedersenGens],
+        bulletproof_gens: &[BulletproofGens],
+        incentive_catalog_size: u64,
+    ) -> PreparedVerifier {
+        let (g, f, b) = linear_generators(
+            &pedersen_gens[1],
+            &bulletproof_gens[1],
+            incentive_catalog_size as usize,
+        );
+        let compressed_generators = g
+            .iter()
+            .chain(iter::once(&f))
+            .chain(iter::once(&b))
+            .map(|point| point.compress())
+            .collect();
+        let precomputation = bounds_precomputation(&pedersen_gens[1], &g, &f, &b);
+        PreparedVerifier {
+            pedersen_gens: pedersen_gens.to_vec(),
+            bulletproof_gens: bulletproof_gens.to_vec(),
+            g,
+            f,
+            b,
+            compressed_generators,
+            precomputation,
+        }
+    }
+
+    /// Returns the size of the incentive catalog
+    pub fn incentive_catalog_size(&self) -> u64 {
+        self.g.len() as u64
+    }
+
+    /// Verifies a rewards proof, see `rewards_proof_verification_aggregated`
+    #[cfg(feature = "std")]
+    pub fn verify(
+        &self,
+        proof: &RewardsProof,
+        public_value: &[Scalar],
+        counter_caps: Option<&CounterCaps>,
+        tier_table: Option<&TierTable>,
+    ) -> bool {
+        self.verify_with_rng(
+            proof,
+            public_value,
+            counter_caps,
+            tier_table,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Verifies a rewards proof like `verify`, with the random weights of the
+    /// verifier drawn from `rng`
+    pub fn verify_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        proof: &RewardsProof,
+        public_value: &[Scalar],
+        counter_caps: Option<&CounterCaps>,
+        tier_table: Option<&TierTable>,
+        rng: &mut R,
+    ) -> bool {
+        if !self.has_setup_generators(proof) {
+            return false;
+        }
+        verify_rewards_proof(
+            &self.pedersen_gens,
+            &self.bulletproof_gens,
+            proof,
+            public_value,
+            counter_caps,
+            tier_table,
+            Some(Precomputation {
+                compressed_generators: &self.compressed_generators,
+                tables: BoundsPrecomputation::Generators(&self.precomputation),
+            }),
+            rng,
+        )
+    }
+
+    /// Precomputes everything that only depends on the policy vector, returns
+    /// `None` if the size of the policy vector does not match the catalog
+    pub fn prepare_policy(&self, public_value: &[Scalar]) -> Option<PreparedPolicy> {
+        if public_value.len() != self.g.len() {
+            return None;
+        }
+        let precomputation = policy_precomputation(
+            &self.pedersen_gens[1],
+            &self.g,
+            &self.f,
+            &self.b,
+            public_value,
+        );
+        Some(PreparedPolicy {
+            public_value: public_value.to_vec(),
+            precomputation,
+        })
+    }
+
+    /// Verifies a rewards proof against a prepared policy vector
+    #[cfg(feature = "std")]
+    pub fn verify_with_policy(
+        &self,
+        proof: &RewardsProof,
+        policy: &PreparedPolicy,
+        counter_caps: Option<&CounterCaps>,
+        tier_table: Option<&TierTable>,
+    ) -> bool {
+        self.verify_with_policy_and_rng(
+            proof,
+            policy,
+            counter_caps,
+            tier_table,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Verifies a rewards proof like `verify_with_policy`, with the random
+    /// weights of the verifier drawn from `rng`
+    pub fn verify_with_policy_and_rng<R: RngCore + CryptoRng>(
+        &self,
+        proof: &RewardsProof,
+        policy: &PreparedPolicy,
+        counter_caps: Option<&CounterCaps>,
+        tier_table: Option<&TierTable>,
+        rng: &mut R,
+    ) -> bool {
+        // a policy prepared by a verifier for another catalog
+        if policy.public_value.len() != self.g.len() || !self.has_setup_generators(proof) {
+            return false;
+        }
+        verify_rewards_proof(
+            &self.pedersen_gens,
+            &self.bulletproof_gens,
+            proof,
+            &policy.public_value,
+            counter_caps,
+            tier_table,
+            Some(Precomputation {
+                compressed_generators: &self.compressed_generators,
+                tables: BoundsPrecomputation::Policy(&policy.precomputation),
+            }),
+            rng,
+        )
+    }
+
+    /// Verifies multiple rewards proofs for the same policy vector and returns
+    /// the result of every proof
+    ///
+    /// The policy vector is prepared once for the batch. With the `parallel`
+    /// feature, the proofs are verified on all CPU cores.
+    #[cfg(feature = "std")]
+    pub fn verify_batch(&self, proofs: &[RewardsProof], public_value: &[Scalar]) -> Vec<bool> {
+        match self.prepare_policy(public_value) {
+            Some(policy) => {
+                let proofs: Vec<&RewardsProof> = proofs.iter().collect();
+                self.verify_batch_with_policy(&proofs, &policy)
+            }
+            None => vec![false; proofs.len()],
+        }
+    }
+
+    /// Verifies multiple rewards proofs against a prepared policy vector, e.g.
+    /// for chunks of a large batch, see `verify_batch`
+    #[cfg(feature = "std")]
+    pub fn verify_batch_with_policy(
+        &self,
+        proofs: &[&RewardsProof],
+        policy: &PreparedPolicy,
+    ) -> Vec<bool> {
+        #[cfg(feature = "parallel")]
+        let proofs = proofs.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let proofs = proofs.iter();
+
+        proofs
+            .map(|proof| self.verify_with_policy(proof, policy, None, None))
+            .collect()
+    }
+
+    /// Verifies multiple decoded rewards proofs against a prepared policy
+    /// vector like `verify_batch_with_policy`, proofs that could not be
+    /// decoded are not valid
+    #[cfg(feature = "std")]
+    pub fn verify_decoded_batch_with_policy(
+        &self,
+        proofs: &[Option<&RewardsProof>],
+        policy: &PreparedPolicy,
+    ) -> Vec<bool> {
+        #[cfg(feature = "parallel")]
+        let proofs = proofs.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let proofs = proofs.iter();
+
+        proofs
+            .map(|proof| match proof {
+                Some(proof) => self.verify_with_policy(proof, policy, None, None),
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Checks that the proof uses the generators of the setup
+    fn has_setup_generators(&self, proof: &RewardsProof) -> bool {
+        let (g, f, b, _) = &proof.linear_proof_commitments;
+        *g == self.g && *f == self.f && *b == self.b
+    }
+
+    /// Verifies a serialized rewards proof, see `rewards_proof_verification`
+    #[cfg(feature = "std")]
+    pub fn verify_bytes(
+        &self,
+        range_proof: &[u8],
+        range_proof_commitments: &[u8],
+        linear_proof: &[u8],
+        public_value: &[Scalar],
+        linear_proof_commitments: (&[u8], &[u8], &[u8], &[u8]),
+        counter_bounds_proof: &[u8],
+    ) -> bool {
+        match RewardsProof::from_bytes(
+            range_proof,
+            range_proof_commitments,
+            linear_proof,
+            linear_proof_commitments,
+            counter_bounds_proof,
+        ) {
+            Some(proof) => self.verify(&proof, public_value, None, None),
+            None => false,
+        }
+    }
+}
+
+/// Policy vector prepared for the verification of many proofs, see
+/// `PreparedVerifier::prepare_policy`
+///
+/// The policy vector is folded into the generators of the counter bounds
+/// proof and the linear proof, `G_i + b_i * F`, with precomputed tables for
+/// the folded generators.
+pub struct PreparedPolicy {
+    public_value: Vec<Scalar>,
+    precomputation: VartimeRistrettoPrecomputation,
+}
+
+impl PreparedPolicy {
+    /// Returns the policy vector
+    pub fn public_value(&self) -> &[Scalar] {
+        &self.public_value
+    }
+}
+
+/// Prepared verifier with the signed policies of an issuer, for verification
+/// services
+///
+/// The policy vector of every registered policy is prepared once, see
+/// `PreparedVerifier::prepare_policy`.
+pub struct PolicyVerifier {
+    verifier: PreparedVerifier,
+    registry: PolicyRegistry,
+    policies: BTreeMap<(u64, u32), PreparedPolicy>,
+}
+
+impl PolicyVerifier {
+    /// Creates a verifier without policies for policies signed by
+    /// `issuer_key`
+    pub fn new(verifier: PreparedVerifier, issuer_key: IssuerPublicKey) -> PolicyVerifier {
+        PolicyVerifier {
+            verifier,
+            registry: PolicyRegistry::new(issuer_key),
+            policies: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the prepared verifier
+    pub fn verifier(&self) -> &PreparedVerifier {
+        &self.verifier
+    }
+
+    /// Registers a signed policy and prepares its policy vector, returns
+    /// `false` if the signature is invalid, the policy does not match the
+    /// catalog, or a different policy with the same ID and version is
+    /// registered
+    pub fn add_policy(&mut self, signed_policy: SignedPolicy) -> bool {
+        let policy = signed_policy.policy();
+        let key = (policy.id(), policy.version());
+        let prepared = match self.verifier.prepare_policy(&policy.policy_vector()) {
+            Some(prepared) => prepared,
+            None => return false,
+        };
+        if !self.registry.insert(signed_policy) {
+            return false;
+        }
+        self.policies.entry(key).or_insert(prepared);
+        true
+    }
+
+    /// Returns a registered policy that is valid at a timestamp, the latest
+    /// valid version if no version is given
+    pub fn valid_policy(
+        &self,
+        id: u64,
+        version: Option<u32>,
+        timestamp: u64,
+    ) -> Option<&SignedPolicy> {
+        match version {
+            Some(version) => self
+                .registry
+                .get(id, version)
+                .filter(|signed_policy| signed_policy.policy().is_valid_at(timestamp)),
+            None => self.registry.latest(id, timestamp),
+        }
+    }
+
+    /// Returns the prepared policy vector of a registered policy
+    pub fn prepared_policy(&self, id: u64, version: u32) -> Option<&PreparedPolicy> {
+        self.policies.get(&(id, version))
+    }
+
+    /// Verifies decoded proofs against a registered policy, proofs that could
+    /// not be decoded are not valid
+    ///
+    /// Returns `None` if the policy is not registered. The validity window of
+    /// the policy is not checked, see `valid_policy`.
+    #[cfg(feature = "std")]
+    pub fn verify_batch(
+        &self,
+        id: u64,
+        version: u32,
+        proofs: &[Option<&RewardsProof>],
+    ) -> Option<Vec<bool>> {
+        let policy = self.prepared_policy(id, version)?;
+        Some(
+            self.verifier
+                .verify_decoded_batch_with_policy(proofs, policy),
+        )
+    }
+}
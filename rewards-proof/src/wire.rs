@@ -0,0 +1,375 @@
+use crate::api::{linear_generators, rewards_proof_setup, setup_capacities, RewardsProof};
+use crate::bounds::CounterBoundsProof;
+use crate::predicate::PredicateProof;
+use alloc::vec::Vec;
+use bulletproofs::{BulletproofGens, LinearProof, PedersenGens, RangeProof};
+use core::convert::TryInto;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+
+/// Magic bytes of the wire format
+pub const WIRE_MAGIC: &[u8; 4] = b"RPWF";
+/// Version of the wire format
+pub const WIRE_VERSION: u8 = 1;
+
+/// Flag for a rewards proof with a predicate proof
+const WIRE_FLAG_PREDICATE: u8 = 0x01;
+/// Size of the fixed header, magic, version, flags and fingerprint
+const WIRE_HEADER_SIZE: usize = 4 + 1 + 1 + 32;
+
+/// Canonical binary wire format of rewards proofs for a fixed set of
+/// parameters
+///
+/// A rewards proof is encoded as
+///
+/// ```text
+/// "RPWF" || version (1 byte) || flags (1 byte) || fingerprint (32 bytes)
+///   || section(range proof) || section(range proof commitments)
+///   || section(linear proof) || C (32 bytes)
+///   || section(counter bounds proof) || [section(predicate proof)]
+/// ```
+///
+/// where every section is `length || bytes` with a 32-bit little endian
+/// length, and the predicate proof is only present if bit 0 of the flags is
+/// set. The generators `G`, `F`, `B` of the linear proof are not transmitted,
+/// they are derived from the parameters, which are identified by the
+/// fingerprint. The decoder rejects other versions, unknown flags, a different
+/// fingerprint, trailing bytes, non-canonical scalars and non-canonical point
+/// encodings.
+pub struct WireFormat {
+    fingerprint: [u8; 32],
+    g: Vec<RistrettoPoint>,
+    f: RistrettoPoint,
+    b: RistrettoPoint,
+}
+
+impl WireFormat {
+    /// Creates the wire format for the output of `rewards_proof_setup`
+    pub fn new(
+        pedersen_gens: &[PedersenGens],
+        bulletproof_gens: &[BulletproofGens],
+        incentive_catalog_size: u64,
+    ) -> WireFormat {
+        let (g, f, b) = linear_generators(
+            &pedersen_gens[1],
+            &bulletproof_gens[1],
+            incentive_catalog_size as usize,
+        );
+        let capacities: Vec<(usize, usize)> = bulletproof_gens
+            .iter()
+            .map(|bp_gen| (bp_gen.gens_capacity, bp_gen.party_capacity))
+            .collect();
+        let fingerprint = parameter_fingerprint(pedersen_gens, &capacities, &g);
+        WireFormat {
+            fingerprint,
+            g,
+            f,
+            b,
+        }
+    }
+
+    /// Creates the wire format for the output of
+    /// `rewards_proof_verification_setup`, with the fingerprint of the full
+    /// setup of `rewards_proof_setup`
+    pub fn for_verification_setup(
+        pedersen_gens: &[PedersenGens],
+        bulletproof_gens: &[BulletproofGens],
+        incentive_catalog_size: u64,
+    ) -> WireFormat {
+        let (g, f, b) = linear_generators(
+            &pedersen_gens[1],
+            &bulletproof_gens[1],
+            incentive_catalog_size as usize,
+        );
+        let capacities = setup_capacities(incentive_catalog_size);
+        let fingerprint = parameter_fingerprint(pedersen_gens, &capacities, &g);
+        WireFormat {
+            fingerprint,
+            g,
+            f,
+            b,
+        }
+    }
+
+    /// Returns the fingerprint of the parameters
+    pub fn fingerprint(&self) -> &[u8; 32] {
+        &self.fingerprint
+    }
+
+    /// Returns the setup parameters of the wire format
+    pub fn parameters(&self) -> SetupParameters {
+        SetupParameters {
+            incentive_catalog_size: self.g.len() as u64,
+            fingerprint: self.fingerprint,
+        }
+    }
+
+    /// Encodes a rewards proof, returns `None` if the proof was not generated
+    /// with the parameters of the wire format
+    pub fn encode(&self, proof: &RewardsProof) -> Option<Vec<u8>> {
+        let (g, f, b, c) = &proof.linear_proof_commitments;
+        if *g != self.g || *f != self.f || *b != self.b {
+            return None;
+        }
+
+        let flags = match proof.predicate_proof {
+            Some(_) => WIRE_FLAG_PREDICATE,
+            None => 0,
+        };
+        let mut buf = WIRE_MAGIC.to_vec();
+        buf.push(WIRE_VERSION);
+        buf.push(flags);
+        buf.extend_from_slice(&self.fingerprint);
+
+        write_section(&mut buf, &proof.range_proof.to_bytes());
+        let range_comm: Vec<u8> = proof
+            .range_proof_commitments
+            .iter()
+            .flat_map(|point| point.to_bytes().to_vec())
+            .collect();
+        write_section(&mut buf, &range_comm);
+        write_section(&mut buf, &proof.linear_proof.to_bytes());
+        buf.extend_from_slice(c.as_bytes());
+        write_section(&mut buf, &proof.counter_bounds_proof.to_bytes());
+        if let Some(predicate_proof) = &proof.predicate_proof {
+            write_section(&mut buf, &predicate_proof.to_bytes());
+        }
+        Some(buf)
+    }
+
+    /// Decodes a rewards proof, returns `None` if the encoding is not
+    /// canonical or was created for other parameters
+    pub fn decode(&self, slice: &[u8]) -> Option<RewardsProof> {
+        if slice.len() < WIRE_HEADER_SIZE
+            || &slice[..4] != WIRE_MAGIC
+            || slice[4] != WIRE_VERSION
+            || slice[5] & !WIRE_FLAG_PREDICATE != 0
+            || slice[6..WIRE_HEADER_SIZE] != self.fingerprint
+        {
+            return None;
+        }
+        let flags = slice[5];
+        let mut rest = &slice[WIRE_HEADER_SIZE..];
+
+        // Range proof and its commitments
+        let range_proof = read_section(&mut rest)?;
+        if !is_canonical_range_proof(range_proof) {
+            return None;
+        }
+        let range_proof = RangeProof::from_bytes(range_proof).ok()?;
+        let range_comm = read_section(&mut rest)?;
+        if range_comm.is_empty()
+            || !(range_comm.len() / 32).is_power_of_two()
+            || !are_canonical_points(range_comm)
+        {
+            return None;
+        }
+        let range_proof_commitments = range_comm
+            .chunks(32)
+            .map(CompressedRistretto::from_slice)
+            .collect();
+
+        // Linear proof and the commitment C
+        let linear_proof = read_section(&mut rest)?;
+        if !is_canonical_proof(linear_proof, &[]) {
+            return None;
+        }
+        let linear_proof = LinearProof::from_bytes(linear_proof).ok()?;
+        if rest.len() < 32 || !are_canonical_points(&rest[..32]) {
+            return None;
+        }
+        let c = CompressedRistretto::from_slice(&rest[..32]);
+        rest = &rest[32..];
+
+        // Counter bounds proof
+        let counter_bounds_proof = read_section(&mut rest)?;
+        if !is_canonical_bounds_proof(counter_bounds_proof, self.g.len()) {
+            return None;
+        }
+        let counter_bounds_proof = CounterBoundsProof::from_bytes(counter_bounds_proof)?;
+
+        // Optional predicate proof
+        let predicate_proof = if flags & WIRE_FLAG_PREDICATE != 0 {
+            let predicate_proof = read_section(&mut rest)?;
+            if predicate_proof.len() < 4 || !is_canonical_range_proof(&predicate_proof[4..]) {
+                return None;
+            }
+            Some(PredicateProof::from_bytes(predicate_proof)?)
+        } else {
+            None
+        };
+
+        if !rest.is_empty() {
+            return None;
+        }
+
+        Some(RewardsProof {
+            range_proof,
+            range_proof_commitments,
+            linear_proof,
+            linear_proof_commitments: (self.g.clone(), self.f, self.b, c),
+            counter_bounds_proof,
+            predicate_proof,
+        })
+    }
+}
+
+/// Setup parameters of an incentive catalog
+///
+/// The generators are not part of the parameters, they are derived from the
+/// size of the catalog with `rewards_proof_setup` and checked against the
+/// fingerprint, see `SetupParameters::setup`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SetupParameters {
+    incentive_catalog_size: u64,
+    fingerprint: [u8; 32],
+}
+
+impl SetupParameters {
+    /// Returns the parameters of the output of `rewards_proof_setup`
+    pub fn new(
+        pedersen_gens: &[PedersenGens],
+        bulletproof_gens: &[BulletproofGens],
+        incentive_catalog_size: u64,
+    ) -> SetupParameters {
+        WireFormat::new(pedersen_gens, bulletproof_gens, incentive_catalog_size).parameters()
+    }
+
+    /// Creates the parameters from the size of the catalog and the
+    /// fingerprint, which is only checked by `setup`
+    pub fn from_parts(incentive_catalog_size: u64, fingerprint: [u8; 32]) -> SetupParameters {
+        SetupParameters {
+            incentive_catalog_size,
+            fingerprint,
+        }
+    }
+
+    /// Returns the size of the incentive catalog
+    pub fn incentive_catalog_size(&self) -> u64 {
+        self.incentive_catalog_size
+    }
+
+    /// Returns the fingerprint of the generators
+    pub fn fingerprint(&self) -> &[u8; 32] {
+        &self.fingerprint
+    }
+
+    /// Runs `rewards_proof_setup` for the parameters, returns `None` if the
+    /// catalog is empty or the fingerprint does not match the derived
+    /// generators
+    pub fn setup(&self) -> Option<(Vec<PedersenGens>, Vec<BulletproofGens>)> {
+        if self.incentive_catalog_size == 0 {
+            return None;
+        }
+        let n = self.incentive_catalog_size;
+        let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(n);
+        if SetupParameters::new(&pedersen_gens, &bulletproof_gens, n) != *self {
+            return None;
+        }
+        Some((pedersen_gens, bulletproof_gens))
+    }
+}
+
+/// Computes the fingerprint of the parameters, which binds the generators of
+/// all proofs and the size of the incentive catalog
+fn parameter_fingerprint(
+    pedersen_gens: &[PedersenGens],
+    capacities: &[(usize, usize)],
+    g: &[RistrettoPoint],
+) -> [u8; 32] {
+    let mut transcript = Transcript::new(b"rewards proof parameters");
+    transcript.append_u64(b"n", g.len() as u64);
+    for ps_gen in pedersen_gens.iter() {
+        transcript.append_message(b"B", ps_gen.B.compress().as_bytes());
+        transcript.append_message(b"B_blinding", ps_gen.B_blinding.compress().as_bytes());
+    }
+    for (gens_capacity, party_capacity) in capacities.iter() {
+        transcript.append_u64(b"gens_capacity", *gens_capacity as u64);
+        transcript.append_u64(b"party_capacity", *party_capacity as u64);
+    }
+    for point in g.iter() {
+        transcript.append_message(b"G_i", point.compress().as_bytes());
+    }
+
+    let mut fingerprint = [0u8; 32];
+    transcript.challenge_bytes(b"fingerprint", &mut fingerprint);
+    fingerprint
+}
+
+/// Appends a length-prefixed section
+fn write_section(buf: &mut Vec<u8>, section: &[u8]) {
+    buf.extend_from_slice(&(section.len() as u32).to_le_bytes());
+    buf.extend_from_slice(section);
+}
+
+/// Reads a length-prefixed section and advances `rest`
+fn read_section<'a>(rest: &mut &'a [u8]) -> Option<&'a [u8]> {
+    if rest.len() < 4 {
+        return None;
+    }
+    let length = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+    if rest.len() - 4 < length {
+        return None;
+    }
+    let section = &rest[4..4 + length];
+    *rest = &rest[4 + length..];
+    Some(section)
+}
+
+/// Checks that all 32-byte chunks are canonical Ristretto point encodings
+fn are_canonical_points(slice: &[u8]) -> bool {
+    slice.len() % 32 == 0
+        && slice.chunks(32).all(|chunk| {
+            CompressedRistretto::from_slice(chunk)
+                .decompress()
+                .is_some()
+        })
+}
+
+/// Checks that all 32-byte chunks are canonical scalars
+fn are_canonical_scalars(slice: &[u8]) -> bool {
+    slice.len() % 32 == 0
+        && slice
+            .chunks(32)
+            .all(|chunk| Scalar::from_canonical_bytes(chunk.try_into().unwrap()).is_some())
+}
+
+/// Checks a proof that consists of points, followed by two scalars, where
+/// the chunks at `scalar_positions` are scalars as well
+///
+/// This is the layout of the inner product and linear proofs
+/// `L_0 || R_0 || .. || S || a || r`.
+fn is_canonical_proof(slice: &[u8], scalar_positions: &[usize]) -> bool {
+    if slice.len() % 32 != 0 || slice.len() < 64 {
+        return false;
+    }
+    let chunks = slice.len() / 32;
+    slice.chunks(32).enumerate().all(|(i, chunk)| {
+        if i >= chunks - 2 || scalar_positions.contains(&i) {
+            are_canonical_scalars(chunk)
+        } else {
+            are_canonical_points(chunk)
+        }
+    })
+}
+
+/// Checks a range proof `A || S || T_1 || T_2 || t_x || t_x_blinding ||
+/// e_blinding || inner product proof`
+fn is_canonical_range_proof(slice: &[u8]) -> bool {
+    is_canonical_proof(slice, &[4, 5, 6])
+}
+
+/// Checks a counter bounds proof for a catalog of size `n`, see
+/// `CounterBoundsProof::to_bytes`
+fn is_canonical_bounds_proof(slice: &[u8], n: usize) -> bool {
+    if slice.len() < 4 || u32::from_le_bytes(slice[..4].try_into().unwrap()) as usize != n {
+        return false;
+    }
+    let points_end = 4 + (2 * n + 1) * 32;
+    let scalars_end = points_end + (2 * n + 1) * 32;
+    slice.len() >= scalars_end
+        && are_canonical_points(&slice[4..points_end])
+        && are_canonical_scalars(&slice[points_end..scalars_end])
+        && is_canonical_range_proof(&slice[scalars_end..])
+}
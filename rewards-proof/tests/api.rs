@@ -0,0 +1,145 @@
+//! Tests of the aggregated range proof over the values of a rewards proof and
+//! of the verification API
+
+mod common;
+
+use common::{prove, setup, verify, INCENTIVE_CATALOG_SIZE, STATE, WEIGHTS};
+use rewards_proof::api::{
+    reward, rewards_proof_generation_aggregated, rewards_proof_verification, to_scalars,
+    RewardsProof, MAX_RANGE_PROOF_VALUES, REWARD_BITSIZE,
+};
+use rewards_proof::verifier::PreparedVerifier;
+
+#[test]
+fn prove_and_verify() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+    assert!(verify(&pedersen_gens, &bulletproof_gens, &proof, &WEIGHTS));
+    assert!(!verify(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &proof,
+        &[2, 5, 1, 1]
+    ));
+}
+
+#[test]
+fn aggregate_values() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let values = [reward(&STATE, &WEIGHTS).unwrap(), 7, 0];
+    let proof = rewards_proof_generation_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &values,
+        to_scalars(&STATE),
+        to_scalars(&WEIGHTS),
+        INCENTIVE_CATALOG_SIZE,
+        None,
+        None,
+    )
+    .unwrap();
+    // padded to a power of two
+    assert_eq!(proof.range_proof_commitments.len(), 4);
+    assert!(verify(&pedersen_gens, &bulletproof_gens, &proof, &WEIGHTS));
+
+    let mut swapped = proof.clone();
+    swapped.range_proof_commitments.swap(0, 1);
+    assert!(!verify(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &swapped,
+        &WEIGHTS
+    ));
+}
+
+#[test]
+fn reject_values() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let generate = |values: &[u64]| {
+        rewards_proof_generation_aggregated(
+            &pedersen_gens,
+            &bulletproof_gens,
+            values,
+            to_scalars(&STATE),
+            to_scalars(&WEIGHTS),
+            INCENTIVE_CATALOG_SIZE,
+            None,
+            None,
+        )
+    };
+    assert!(generate(&[(1 << REWARD_BITSIZE) - 1]).is_some());
+    assert!(generate(&[1 << REWARD_BITSIZE]).is_none());
+    assert!(generate(&vec![1; MAX_RANGE_PROOF_VALUES]).is_some());
+    assert!(generate(&vec![1; MAX_RANGE_PROOF_VALUES + 1]).is_none());
+}
+
+/// Verifies a proof with the byte API, the parsed API and the prepared
+/// verifier, and checks that all agree
+fn verify_all(proof: &RewardsProof, weights: &[u64]) -> bool {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let public_value = to_scalars(weights);
+    let (range_proof, linear_proof, range_comm, linear_comm, bounds_proof) = proof.to_bytes();
+    let linear_comm = (
+        &linear_comm.0[..],
+        &linear_comm.1[..],
+        &linear_comm.2[..],
+        &linear_comm.3[..],
+    );
+
+    let bytes = rewards_proof_verification(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &range_proof,
+        &range_comm,
+        &linear_proof,
+        &public_value,
+        linear_comm,
+        &bounds_proof,
+    );
+    let parsed = verify(&pedersen_gens, &bulletproof_gens, proof, weights);
+    let prepared_bytes = verifier.verify_bytes(
+        &range_proof,
+        &range_comm,
+        &linear_proof,
+        &public_value,
+        linear_comm,
+        &bounds_proof,
+    );
+    let prepared = verifier.verify(proof, &public_value, None, None);
+    assert_eq!(bytes, parsed);
+    assert_eq!(prepared_bytes, parsed);
+    assert_eq!(prepared, parsed);
+    parsed
+}
+
+#[test]
+fn parsed_matches_bytes() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+    assert!(verify_all(&proof, &WEIGHTS));
+    assert!(!verify_all(&proof, &[2, 5, 1, 1]));
+
+    // the linear proof of another proof
+    let mut spliced = proof.clone();
+    spliced.linear_proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS).linear_proof;
+    assert!(!verify_all(&spliced, &WEIGHTS));
+
+    // bytes that cannot be parsed
+    let (range_proof, linear_proof, range_comm, linear_comm, bounds_proof) = proof.to_bytes();
+    assert!(!rewards_proof_verification(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &range_proof,
+        &range_comm,
+        &linear_proof[1..],
+        &to_scalars(&WEIGHTS),
+        (
+            &linear_comm.0,
+            &linear_comm.1,
+            &linear_comm.2,
+            &linear_comm.3
+        ),
+        &bounds_proof,
+    ));
+}
@@ -0,0 +1,135 @@
+//! Tests of the counter bounds proof over the state entries
+
+mod common;
+
+use common::{prove, setup, verify, INCENTIVE_CATALOG_SIZE, STATE, WEIGHTS};
+use curve25519_dalek::scalar::Scalar;
+use rewards_proof::api::{
+    reward, rewards_proof_generation_aggregated, rewards_proof_verification_aggregated, to_scalars,
+    RewardsProof,
+};
+use rewards_proof::bounds::{CounterBoundsProof, CounterCaps, STATE_ENTRY_BITSIZE};
+
+/// Generates a rewards proof with per-incentive caps
+fn prove_with_caps(state: &[u64], caps: &CounterCaps) -> Option<RewardsProof> {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    rewards_proof_generation_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &[reward(state, &WEIGHTS)?],
+        to_scalars(state),
+        to_scalars(&WEIGHTS),
+        INCENTIVE_CATALOG_SIZE,
+        Some(caps),
+        None,
+    )
+}
+
+/// Verifies a rewards proof against per-incentive caps
+fn verify_with_caps(proof: &RewardsProof, caps: Option<&CounterCaps>) -> bool {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    rewards_proof_verification_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        proof,
+        &to_scalars(&WEIGHTS),
+        caps,
+        None,
+    )
+}
+
+#[test]
+fn verify_caps() {
+    let caps = CounterCaps::new(vec![1, 0, 3, 5], 8).unwrap();
+    let proof = prove_with_caps(&STATE, &caps).unwrap();
+    assert!(verify_with_caps(&proof, Some(&caps)));
+
+    // the proof does not hold for lower caps, or without caps
+    let lower_caps = CounterCaps::new(vec![1, 0, 2, 5], 8).unwrap();
+    assert!(!verify_with_caps(&proof, Some(&lower_caps)));
+    assert!(!verify_with_caps(&proof, None));
+}
+
+#[test]
+fn reject_exceeded_cap() {
+    let caps = CounterCaps::uniform(2, INCENTIVE_CATALOG_SIZE, 8).unwrap();
+    // the third entry exceeds its cap
+    assert!(prove_with_caps(&STATE, &caps).is_none());
+    assert!(prove_with_caps(&[1, 0, 2, 2], &caps).is_some());
+}
+
+#[test]
+fn reject_invalid_caps() {
+    assert!(CounterCaps::new(vec![1, 2], 12).is_none());
+    assert!(CounterCaps::new(vec![1, 256], 8).is_none());
+    assert!(CounterCaps::new(vec![1, 255], 8).is_some());
+    assert!(CounterCaps::new(vec![u64::MAX], 64).is_some());
+}
+
+#[test]
+fn reject_negative_counter() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    // the "negative" counter p - 1 cancels one interaction with the third
+    // incentive, <state, weights> = -2 + 3 = 1
+    let state = vec![
+        -Scalar::one(),
+        Scalar::zero(),
+        Scalar::from(3u64),
+        Scalar::zero(),
+    ];
+    let generate = |state: Vec<Scalar>, value: u64| {
+        rewards_proof_generation_aggregated(
+            &pedersen_gens,
+            &bulletproof_gens,
+            &[value],
+            state,
+            to_scalars(&WEIGHTS),
+            INCENTIVE_CATALOG_SIZE,
+            None,
+            None,
+        )
+    };
+    assert!(generate(state, 1).is_none());
+
+    // the last incentive has weight zero, so only the bounds are violated
+    let large_state = to_scalars(&[0, 0, 0, 1 << STATE_ENTRY_BITSIZE]);
+    assert!(generate(large_state, 0).is_none());
+}
+
+#[test]
+fn reject_bounds_proof_of_other_state() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+    let other = prove(&pedersen_gens, &bulletproof_gens, &[1, 0, 3, 3], &WEIGHTS);
+
+    let mut spliced = proof.clone();
+    spliced.counter_bounds_proof = other.counter_bounds_proof;
+    assert!(verify(&pedersen_gens, &bulletproof_gens, &proof, &WEIGHTS));
+    assert!(!verify(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &spliced,
+        &WEIGHTS
+    ));
+}
+
+#[test]
+fn reject_malformed_bounds_proof() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let bytes = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS)
+        .counter_bounds_proof
+        .to_bytes();
+    assert!(CounterBoundsProof::from_bytes(&bytes).is_some());
+
+    // a size of the catalog that overflows the length on 32-bit targets
+    for n in [u32::MAX, (u32::MAX - 2) / 128 + 1].iter() {
+        let mut invalid = bytes.clone();
+        invalid[..4].copy_from_slice(&n.to_le_bytes());
+        assert!(CounterBoundsProof::from_bytes(&invalid).is_none());
+    }
+
+    // truncated within the points and within the scalars
+    for length in [4 + 32 * 3 + 7, 4 + 32 * (2 * 4 + 1) + 16].iter() {
+        assert!(CounterBoundsProof::from_bytes(&bytes[..*length]).is_none());
+    }
+}
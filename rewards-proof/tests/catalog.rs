@@ -0,0 +1,92 @@
+//! Tests of the incentive catalog
+
+use curve25519_dalek::scalar::Scalar;
+use rewards_proof::api::{
+    rewards_proof_generation_aggregated, rewards_proof_setup, rewards_proof_verification_aggregated,
+};
+use rewards_proof::catalog::IncentiveCatalog;
+use std::collections::HashMap;
+
+/// Catalog of four incentives, the second one retired
+fn catalog() -> IncentiveCatalog {
+    let mut catalog = IncentiveCatalog::new();
+    assert_eq!(catalog.add("ad-1"), Some(0));
+    assert_eq!(catalog.add("ad-2"), Some(1));
+    assert_eq!(catalog.add("ad-3"), Some(2));
+    assert_eq!(catalog.add("ad-4"), Some(3));
+    assert!(catalog.retire("ad-2"));
+    catalog
+}
+
+fn map(entries: &[(&str, u64)]) -> HashMap<String, u64> {
+    entries
+        .iter()
+        .map(|(id, value)| (id.to_string(), *value))
+        .collect()
+}
+
+#[test]
+fn retire_incentive() {
+    let mut catalog = catalog();
+    assert_eq!(catalog.version(), 5);
+    assert_eq!(catalog.incentive_catalog_size(), 4);
+    assert!(!catalog.is_active("ad-2"));
+    // retired incentives keep their position and ID
+    assert_eq!(catalog.position("ad-2"), Some(1));
+    assert_eq!(catalog.id(1), Some("ad-2"));
+    assert!(!catalog.retire("ad-2"));
+    assert_eq!(catalog.add("ad-2"), None);
+    assert_eq!(catalog.add("ad-5"), Some(4));
+    assert!(!catalog.retire("unknown"));
+}
+
+#[test]
+fn retired_incentive_is_not_rewarded() {
+    let catalog = catalog();
+    let counts = map(&[("ad-1", 2), ("ad-2", 5), ("ad-3", 1)]);
+    let state = catalog.state_vector(&counts).unwrap();
+    assert_eq!(
+        state,
+        vec![
+            Scalar::from(2u64),
+            Scalar::zero(),
+            Scalar::from(1u64),
+            Scalar::zero()
+        ]
+    );
+    let sparse_state = catalog.sparse_state(&counts).unwrap();
+    assert_eq!(sparse_state.non_zero_entries(), 2);
+    assert_eq!(sparse_state.to_dense(), state);
+    assert!(catalog.state_vector(&map(&[("unknown", 1)])).is_none());
+
+    // retired incentives cannot be weighted
+    assert!(catalog.policy_vector(&map(&[("ad-2", 1)])).is_none());
+    assert!(catalog
+        .policy(1, 1, &map(&[("ad-1", 1), ("ad-2", 1)]), 0, 1)
+        .is_none());
+
+    let policy_vector = catalog
+        .policy_vector(&map(&[("ad-1", 3), ("ad-3", 4)]))
+        .unwrap();
+    let n = catalog.incentive_catalog_size();
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(n);
+    let proof = rewards_proof_generation_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &[2 * 3 + 4],
+        state,
+        policy_vector.clone(),
+        n,
+        None,
+        None,
+    )
+    .unwrap();
+    assert!(rewards_proof_verification_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &proof,
+        &policy_vector,
+        None,
+        None,
+    ));
+}
@@ -0,0 +1,140 @@
+//! Tests of the R1CS rewards circuits
+
+#![cfg(feature = "r1cs")]
+
+mod common;
+
+use bulletproofs::PedersenGens;
+use common::{STATE, WEIGHTS};
+use curve25519_dalek::scalar::Scalar;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rewards_proof::bounds::CounterCaps;
+use rewards_proof::circuits::{
+    circuit_proof_generation, circuit_proof_generation_with_rng, circuit_proof_verification,
+    BonusRule, CircuitProof, RewardsCircuit,
+};
+use rewards_proof::predicate::TierTable;
+
+/// Circuit with a bonus for the first and third incentive, the score of the
+/// tests is `5 + 4 * 1 * 3 = 17`
+fn circuit(tier_table: Option<TierTable>) -> RewardsCircuit {
+    let bonus_rule = BonusRule {
+        left: 0,
+        right: 2,
+        weight: 4,
+    };
+    RewardsCircuit::new(WEIGHTS.to_vec(), None, vec![bonus_rule], tier_table).unwrap()
+}
+
+#[test]
+fn prove_and_open_reward() {
+    let pedersen_gens = PedersenGens::default();
+    let circuit = circuit(None);
+    assert_eq!(circuit.reward(&STATE), Some(17));
+
+    let bulletproof_gens = circuit.generators();
+    let (proof, blinding) =
+        circuit_proof_generation(&pedersen_gens, &bulletproof_gens, &circuit, &STATE).unwrap();
+    assert!(circuit_proof_verification(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &circuit,
+        &proof
+    ));
+
+    let opening = |reward: u64| {
+        pedersen_gens
+            .commit(Scalar::from(reward), blinding)
+            .compress()
+    };
+    assert_eq!(opening(17), *proof.reward_commitment());
+    assert_ne!(opening(18), *proof.reward_commitment());
+}
+
+#[test]
+fn reject_wrong_tier() {
+    let pedersen_gens = PedersenGens::default();
+    let tier_table = TierTable::new(vec![10, 20], vec![0, 50, 100]).unwrap();
+    let circuit = circuit(Some(tier_table));
+    assert_eq!(circuit.reward(&STATE), Some(50));
+
+    let bulletproof_gens = circuit.generators();
+    let mut rng = StdRng::seed_from_u64(30);
+    let (proof, blinding) = circuit_proof_generation_with_rng(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &circuit,
+        &STATE,
+        &mut rng,
+    )
+    .unwrap();
+    assert!(circuit_proof_verification(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &circuit,
+        &proof
+    ));
+    // the reward opens to the payout of the second tier only
+    let opening = |reward: u64| {
+        pedersen_gens
+            .commit(Scalar::from(reward), blinding)
+            .compress()
+    };
+    assert_eq!(opening(50), *proof.reward_commitment());
+    assert_ne!(opening(100), *proof.reward_commitment());
+
+    // the score is in the last tier of another table
+    let other_table = TierTable::new(vec![10, 15], vec![0, 50, 100]).unwrap();
+    let other_circuit = circuit(Some(other_table));
+    assert!(!circuit_proof_verification(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &other_circuit,
+        &proof
+    ));
+}
+
+#[test]
+fn reject_invalid_state() {
+    let pedersen_gens = PedersenGens::default();
+    let caps = CounterCaps::uniform(2, STATE.len() as u64, 8).unwrap();
+    let circuit = RewardsCircuit::new(WEIGHTS.to_vec(), Some(caps), vec![], None).unwrap();
+    let bulletproof_gens = circuit.generators();
+
+    // the third entry exceeds its cap
+    assert_eq!(circuit.reward(&STATE), None);
+    assert!(circuit_proof_generation(&pedersen_gens, &bulletproof_gens, &circuit, &STATE).is_err());
+    assert!(
+        circuit_proof_generation(&pedersen_gens, &bulletproof_gens, &circuit, &[1, 0, 2, 2])
+            .is_ok()
+    );
+
+    // bonus rules must refer to entries of the state
+    let bonus_rule = BonusRule {
+        left: 0,
+        right: STATE.len(),
+        weight: 1,
+    };
+    assert!(RewardsCircuit::new(WEIGHTS.to_vec(), None, vec![bonus_rule], None).is_none());
+}
+
+#[test]
+fn reject_malformed_circuit_proof() {
+    let pedersen_gens = PedersenGens::default();
+    let circuit = circuit(None);
+    let bulletproof_gens = circuit.generators();
+    let (proof, _) =
+        circuit_proof_generation(&pedersen_gens, &bulletproof_gens, &circuit, &STATE).unwrap();
+    let bytes = proof.to_bytes();
+    let decoded = CircuitProof::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.reward_commitment(), proof.reward_commitment());
+
+    // a number of commitments that overflows the length on 32-bit targets
+    for n in [u32::MAX, u32::MAX / 32].iter() {
+        let mut invalid = bytes.clone();
+        invalid[..4].copy_from_slice(&n.to_le_bytes());
+        assert!(CircuitProof::from_bytes(&invalid).is_none());
+    }
+    assert!(CircuitProof::from_bytes(&bytes[..4 + 32 * 2]).is_none());
+}
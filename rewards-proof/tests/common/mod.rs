@@ -0,0 +1,58 @@
+//! Setup and proofs shared by the integration tests
+
+#![allow(dead_code)]
+
+use bulletproofs::{BulletproofGens, PedersenGens};
+use rewards_proof::api::{
+    reward, rewards_proof_generation_aggregated, rewards_proof_setup,
+    rewards_proof_verification_aggregated, to_scalars, RewardsProof,
+};
+
+/// Size of the incentive catalog of the tests
+pub const INCENTIVE_CATALOG_SIZE: u64 = 4;
+/// Number of interactions per incentive
+pub const STATE: [u64; 4] = [1, 0, 3, 2];
+/// Weights of the policy vector
+pub const WEIGHTS: [u64; 4] = [2, 5, 1, 0];
+
+/// Runs the setup for the catalog of the tests
+pub fn setup() -> (Vec<PedersenGens>, Vec<BulletproofGens>) {
+    rewards_proof_setup(INCENTIVE_CATALOG_SIZE)
+}
+
+/// Generates a rewards proof for the reward `<state, weights>`
+pub fn prove(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    state: &[u64],
+    weights: &[u64],
+) -> RewardsProof {
+    rewards_proof_generation_aggregated(
+        pedersen_gens,
+        bulletproof_gens,
+        &[reward(state, weights).unwrap()],
+        to_scalars(state),
+        to_scalars(weights),
+        state.len() as u64,
+        None,
+        None,
+    )
+    .unwrap()
+}
+
+/// Verifies a rewards proof against a policy vector
+pub fn verify(
+    pedersen_gens: &[PedersenGens],
+    bulletproof_gens: &[BulletproofGens],
+    proof: &RewardsProof,
+    weights: &[u64],
+) -> bool {
+    rewards_proof_verification_aggregated(
+        pedersen_gens,
+        bulletproof_gens,
+        proof,
+        &to_scalars(weights),
+        None,
+        None,
+    )
+}
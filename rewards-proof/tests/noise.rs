@@ -0,0 +1,124 @@
+//! Tests of the noisy rewards proof
+
+#![cfg(feature = "r1cs")]
+
+mod common;
+
+use bulletproofs::PedersenGens;
+use common::{STATE, WEIGHTS};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rewards_proof::circuits::RewardsCircuit;
+use rewards_proof::noise::{
+    noise_commitment, noise_commitment_with_rng, noisy_reward_proof_generation,
+    noisy_reward_proof_generation_with_rng, noisy_reward_proof_verification, NoiseConfig,
+    NoisyRewardProof, MAX_NOISE_COINS,
+};
+
+#[test]
+fn noise_within_range() {
+    let pedersen_gens = PedersenGens::default();
+    let circuit = RewardsCircuit::new(WEIGHTS.to_vec(), None, vec![], None).unwrap();
+    let reward = circuit.reward(&STATE).unwrap();
+    let config = NoiseConfig::new(8).unwrap();
+    let bulletproof_gens = config.generators(&circuit);
+    let issuer_randomness = [31u8; 32];
+
+    for _ in 0..4 {
+        let (commitment, opening) = noise_commitment(&pedersen_gens, &config);
+        let proof = noisy_reward_proof_generation(
+            &pedersen_gens,
+            &bulletproof_gens,
+            &circuit,
+            &config,
+            &STATE,
+            &commitment,
+            &opening,
+            &issuer_randomness,
+        )
+        .unwrap();
+        let (min, max) = config.range();
+        assert!(proof.noisy_reward() >= reward + min && proof.noisy_reward() <= reward + max);
+        let verify = |proof: &NoisyRewardProof, issuer_randomness: &[u8; 32]| {
+            noisy_reward_proof_verification(
+                &pedersen_gens,
+                &bulletproof_gens,
+                &circuit,
+                &config,
+                &commitment,
+                issuer_randomness,
+                proof,
+            )
+        };
+        assert!(verify(&proof, &issuer_randomness));
+        assert!(!verify(&proof, &[32u8; 32]));
+
+        // a noisy reward outside of the range, or with other noise
+        for noisy_reward in [reward + max + 1, proof.noisy_reward() + 1].iter() {
+            let mut bytes = proof.to_bytes();
+            bytes[..8].copy_from_slice(&noisy_reward.to_le_bytes());
+            let tampered = NoisyRewardProof::from_bytes(&bytes).unwrap();
+            assert!(!verify(&tampered, &issuer_randomness));
+        }
+    }
+}
+
+#[test]
+fn noise_config() {
+    assert!(NoiseConfig::new(0).is_none());
+    assert!(NoiseConfig::new(MAX_NOISE_COINS + 1).is_none());
+
+    let config = NoiseConfig::new(MAX_NOISE_COINS).unwrap();
+    assert_eq!(config.range(), (0, MAX_NOISE_COINS as u64));
+    assert_eq!(config.mean(), MAX_NOISE_COINS as f64 / 2.0);
+}
+
+#[test]
+fn deterministic_noise() {
+    let pedersen_gens = PedersenGens::default();
+    let circuit = RewardsCircuit::new(WEIGHTS.to_vec(), None, vec![], None).unwrap();
+    let config = NoiseConfig::new(8).unwrap();
+    let bulletproof_gens = config.generators(&circuit);
+    let issuer_randomness = [31u8; 32];
+
+    // the same client randomness yields the same coins and noisy reward
+    let run = |seed: u64| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (commitment, opening) = noise_commitment_with_rng(&pedersen_gens, &config, &mut rng);
+        let proof = noisy_reward_proof_generation_with_rng(
+            &pedersen_gens,
+            &bulletproof_gens,
+            &circuit,
+            &config,
+            &STATE,
+            &commitment,
+            &opening,
+            &issuer_randomness,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(noisy_reward_proof_verification(
+            &pedersen_gens,
+            &bulletproof_gens,
+            &circuit,
+            &config,
+            &commitment,
+            &issuer_randomness,
+            &proof,
+        ));
+        (commitment.to_bytes(), proof)
+    };
+    let (commitment, proof) = run(31);
+    let (other_commitment, other_proof) = run(31);
+    assert_eq!(commitment, other_commitment);
+    assert_eq!(proof.noisy_reward(), other_proof.noisy_reward());
+    assert_ne!(run(32).0, commitment);
+
+    // a number of commitments that overflows the length on 32-bit targets
+    let bytes = proof.to_bytes();
+    for n in [u32::MAX, u32::MAX / 32 + 1].iter() {
+        let mut invalid = bytes.clone();
+        invalid[8..12].copy_from_slice(&n.to_le_bytes());
+        assert!(NoisyRewardProof::from_bytes(&invalid).is_none());
+    }
+}
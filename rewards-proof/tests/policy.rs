@@ -0,0 +1,105 @@
+//! Tests of signed policies and the policy registry
+
+mod common;
+
+use common::{setup, STATE, WEIGHTS};
+use rewards_proof::api::{rewards_proof_generation_signed, to_scalars};
+use rewards_proof::policy::{IssuerSigningKey, Policy, PolicyRegistry, SignedPolicy};
+
+/// Validity window of the policies of the tests
+const VALID_FROM: u64 = 1_700_000_000;
+const VALID_UNTIL: u64 = 1_800_000_000;
+
+fn policy(version: u32, weights: &[u64]) -> Policy {
+    Policy::new(1, version, weights.to_vec(), VALID_FROM, VALID_UNTIL).unwrap()
+}
+
+#[test]
+fn reject_expired_policy() {
+    let signing_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let issuer_key = signing_key.public_key();
+    let signed_policy = signing_key.sign(&policy(1, &WEIGHTS));
+
+    assert!(signed_policy.verify(&issuer_key, VALID_FROM));
+    assert!(!signed_policy.verify(&issuer_key, VALID_FROM - 1));
+    // the end of the window is exclusive
+    assert!(!signed_policy.verify(&issuer_key, VALID_UNTIL));
+
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let generate = |timestamp: u64| {
+        rewards_proof_generation_signed(
+            &pedersen_gens,
+            &bulletproof_gens,
+            &[5],
+            to_scalars(&STATE),
+            &signed_policy,
+            &issuer_key,
+            timestamp,
+        )
+    };
+    assert!(generate(VALID_FROM).is_some());
+    assert!(generate(VALID_UNTIL).is_none());
+    assert!(Policy::new(1, 1, WEIGHTS.to_vec(), VALID_UNTIL, VALID_FROM).is_none());
+}
+
+#[test]
+fn reject_forged_signature() {
+    let signing_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let issuer_key = signing_key.public_key();
+    let other_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let signed_policy = signing_key.sign(&policy(1, &WEIGHTS));
+
+    let bytes = signed_policy.to_bytes();
+    assert_eq!(
+        SignedPolicy::from_bytes(&bytes),
+        Some(signed_policy.clone())
+    );
+
+    // signed by another issuer
+    let forged = other_key.sign(&policy(1, &WEIGHTS));
+    assert!(!forged.verify(&issuer_key, VALID_FROM));
+
+    // the weights of a signed policy are changed, the first weight follows
+    // the signature and the header of the manifest
+    let mut tampered = bytes.clone();
+    tampered[64 + 41] ^= 1;
+    let tampered = SignedPolicy::from_bytes(&tampered).unwrap();
+    assert_ne!(tampered.policy().weights(), &WEIGHTS[..]);
+    assert!(!tampered.verify_signature(&issuer_key));
+
+    let (pedersen_gens, bulletproof_gens) = setup();
+    assert!(rewards_proof_generation_signed(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &[5],
+        to_scalars(&STATE),
+        &forged,
+        &issuer_key,
+        VALID_FROM,
+    )
+    .is_none());
+}
+
+#[test]
+fn registry_versions() {
+    let signing_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let other_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let mut registry = PolicyRegistry::new(signing_key.public_key());
+
+    assert!(registry.insert(signing_key.sign(&policy(1, &WEIGHTS))));
+    assert!(registry.insert(signing_key.sign(&policy(2, &[1, 1, 1, 1]))));
+    // the same policy again, a different policy with the same version, and a
+    // policy of another issuer
+    assert!(registry.insert(signing_key.sign(&policy(1, &WEIGHTS))));
+    assert!(!registry.insert(signing_key.sign(&policy(1, &[1, 1, 1, 1]))));
+    assert!(!registry.insert(other_key.sign(&policy(3, &WEIGHTS))));
+
+    assert_eq!(registry.get(1, 1).unwrap().policy().weights(), &WEIGHTS[..]);
+    assert!(registry.get(1, 3).is_none());
+    assert_eq!(
+        registry.latest(1, VALID_FROM).unwrap().policy().version(),
+        2
+    );
+    assert!(registry.latest(1, VALID_UNTIL).is_none());
+    assert!(registry.latest(2, VALID_FROM).is_none());
+}
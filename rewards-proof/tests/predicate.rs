@@ -0,0 +1,84 @@
+//! Tests of the tier predicate proof over the score
+
+mod common;
+
+use common::{setup, INCENTIVE_CATALOG_SIZE, STATE, WEIGHTS};
+use rewards_proof::api::{
+    reward, rewards_proof_generation_aggregated, rewards_proof_verification_aggregated, to_scalars,
+    RewardsProof,
+};
+use rewards_proof::predicate::{PredicateProof, TierTable};
+
+/// Generates a rewards proof for the tier of the score of the tests, 5
+fn prove_tier(tier_table: &TierTable) -> Option<RewardsProof> {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    rewards_proof_generation_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &[reward(&STATE, &WEIGHTS).unwrap()],
+        to_scalars(&STATE),
+        to_scalars(&WEIGHTS),
+        INCENTIVE_CATALOG_SIZE,
+        None,
+        Some(tier_table),
+    )
+}
+
+/// Verifies a rewards proof against a tier table
+fn verify_tier(proof: &RewardsProof, tier_table: &TierTable) -> bool {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    rewards_proof_verification_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        proof,
+        &to_scalars(&WEIGHTS),
+        None,
+        Some(tier_table),
+    )
+}
+
+#[test]
+fn verify_tier_payout() {
+    let tier_table = TierTable::new(vec![3, 10], vec![0, 50, 100]).unwrap();
+    let proof = prove_tier(&tier_table).unwrap();
+    assert!(verify_tier(&proof, &tier_table));
+
+    let predicate_proof = proof.predicate_proof.as_ref().unwrap();
+    assert_eq!(predicate_proof.tier(), 1);
+    assert_eq!(predicate_proof.payout(&tier_table), Some(50));
+}
+
+#[test]
+fn reject_wrong_tier() {
+    let tier_table = TierTable::new(vec![3, 10], vec![0, 50, 100]).unwrap();
+    let proof = prove_tier(&tier_table).unwrap();
+
+    // claim the next tier
+    let mut bytes = proof.predicate_proof.as_ref().unwrap().to_bytes();
+    bytes[..4].copy_from_slice(&2u32.to_le_bytes());
+    let mut wrong_tier = proof.clone();
+    wrong_tier.predicate_proof = Some(PredicateProof::from_bytes(&bytes).unwrap());
+    assert!(!verify_tier(&wrong_tier, &tier_table));
+
+    // the score is in the first tier of another table
+    let other_table = TierTable::new(vec![6, 10], vec![0, 50, 100]).unwrap();
+    assert!(!verify_tier(&proof, &other_table));
+
+    let mut without_predicate = proof.clone();
+    without_predicate.predicate_proof = None;
+    assert!(!verify_tier(&without_predicate, &tier_table));
+}
+
+#[test]
+fn reject_invalid_tier_table() {
+    assert!(TierTable::new(vec![10, 3], vec![0, 50, 100]).is_none());
+    assert!(TierTable::new(vec![3, 3], vec![0, 50, 100]).is_none());
+    assert!(TierTable::new(vec![0, 3], vec![0, 50, 100]).is_none());
+    assert!(TierTable::new(vec![3, 10], vec![0, 50]).is_none());
+    assert!(TierTable::new(vec![1 << 32], vec![0, 50]).is_none());
+
+    let tier_table = TierTable::threshold(5, 20).unwrap();
+    assert_eq!(tier_table.tier(4), 0);
+    assert_eq!(tier_table.tier(5), 1);
+    assert_eq!(tier_table.payout(tier_table.tier(5)), Some(20));
+}
@@ -0,0 +1,145 @@
+//! Tests of the protobuf messages and conversions
+
+#![cfg(feature = "protobuf")]
+
+mod common;
+
+use common::{prove, setup, verify, INCENTIVE_CATALOG_SIZE, STATE, WEIGHTS};
+use rewards_proof::api::{
+    reward, rewards_proof_generation_aggregated, rewards_proof_verification_aggregated, to_scalars,
+    RewardsProof,
+};
+use rewards_proof::bounds::CounterCaps;
+use rewards_proof::policy::{IssuerSigningKey, Policy, SignedPolicy};
+use rewards_proof::predicate::{PredicateProof, TierTable};
+use rewards_proof::protobuf::{
+    proto, setup_from_parameters, setup_parameters, verification_result, Message,
+};
+
+#[test]
+fn rewards_proof_round_trip() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+
+    let message = proof.to_protobuf();
+    let encoded = message.encode_to_vec();
+    let decoded_message = proto::RewardsProof::decode(&encoded[..]).unwrap();
+    assert_eq!(decoded_message, message);
+
+    let decoded = RewardsProof::from_protobuf(&decoded_message).unwrap();
+    assert_eq!(decoded.to_protobuf(), message);
+    assert!(verify(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &decoded,
+        &WEIGHTS
+    ));
+
+    // a commitment that is not a compressed point
+    let mut invalid = message;
+    invalid.range_proof_commitments[0].pop();
+    assert!(RewardsProof::from_protobuf(&invalid).is_none());
+}
+
+#[test]
+fn policy_round_trip() {
+    let signing_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let policy = Policy::new(1, 2, WEIGHTS.to_vec(), 1_700_000_000, 1_800_000_000).unwrap();
+    let signed_policy = signing_key.sign(&policy);
+
+    let encoded = signed_policy.to_protobuf().encode_to_vec();
+    let message = proto::SignedPolicy::decode(&encoded[..]).unwrap();
+    let decoded = SignedPolicy::from_protobuf(&message).unwrap();
+    assert_eq!(decoded, signed_policy);
+    assert!(decoded.verify_signature(&signing_key.public_key()));
+    assert_eq!(
+        Policy::from_protobuf(&policy.to_protobuf()).unwrap(),
+        policy
+    );
+
+    // a signature of the wrong length and a missing policy
+    let mut invalid = message.clone();
+    invalid.signature.pop();
+    assert!(SignedPolicy::from_protobuf(&invalid).is_none());
+    let mut invalid = message;
+    invalid.policy = None;
+    assert!(SignedPolicy::from_protobuf(&invalid).is_none());
+}
+
+#[test]
+fn parameters_round_trip() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let parameters = setup_parameters(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let encoded = parameters.encode_to_vec();
+    let message = proto::SetupParameters::decode(&encoded[..]).unwrap();
+    assert_eq!(message, parameters);
+    let (decoded_pedersen_gens, decoded_bulletproof_gens) =
+        setup_from_parameters(&message).unwrap();
+    assert_eq!(
+        setup_parameters(
+            &decoded_pedersen_gens,
+            &decoded_bulletproof_gens,
+            INCENTIVE_CATALOG_SIZE
+        ),
+        parameters
+    );
+
+    // a different fingerprint
+    let mut invalid = message;
+    invalid.fingerprint[0] ^= 1;
+    assert!(setup_from_parameters(&invalid).is_none());
+
+    let caps = CounterCaps::new(vec![1, 0, 3, 5], 8).unwrap();
+    let decoded = CounterCaps::from_protobuf(&caps.to_protobuf()).unwrap();
+    assert_eq!(decoded.caps(), caps.caps());
+    assert_eq!(decoded.bitsize(), caps.bitsize());
+
+    let table = TierTable::new(vec![3, 10], vec![0, 50, 100]).unwrap();
+    let decoded = TierTable::from_protobuf(&table.to_protobuf()).unwrap();
+    assert_eq!(decoded.thresholds(), table.thresholds());
+    assert_eq!(decoded.payouts(), table.payouts());
+}
+
+#[test]
+fn forged_tier_is_not_reported() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let tier_table = TierTable::new(vec![3, 10], vec![0, 50, 100]).unwrap();
+    let proof = rewards_proof_generation_aggregated(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &[reward(&STATE, &WEIGHTS).unwrap()],
+        to_scalars(&STATE),
+        to_scalars(&WEIGHTS),
+        INCENTIVE_CATALOG_SIZE,
+        None,
+        Some(&tier_table),
+    )
+    .unwrap();
+    let verify_tier = |proof: &RewardsProof| {
+        rewards_proof_verification_aggregated(
+            &pedersen_gens,
+            &bulletproof_gens,
+            proof,
+            &to_scalars(&WEIGHTS),
+            None,
+            Some(&tier_table),
+        )
+    };
+    assert!(verify_tier(&proof));
+    assert_eq!(verification_result(0, &proof, true, true).tier, Some(1));
+
+    // claim the highest tier, the rest of the proof is still valid
+    let mut bytes = proof.predicate_proof.as_ref().unwrap().to_bytes();
+    bytes[..4].copy_from_slice(&2u32.to_le_bytes());
+    let mut forged = proof.clone();
+    forged.predicate_proof = Some(PredicateProof::from_bytes(&bytes).unwrap());
+    assert!(verify(&pedersen_gens, &bulletproof_gens, &forged, &WEIGHTS));
+    assert!(!verify_tier(&forged));
+
+    // the tier is only reported if the predicate proof was verified
+    let result = verification_result(3, &forged, true, false);
+    assert_eq!((result.index, result.valid, result.tier), (3, true, None));
+    let valid = verify_tier(&forged);
+    assert_eq!(verification_result(3, &forged, valid, true).tier, None);
+    assert_eq!(verification_result(3, &proof, false, true).tier, None);
+}
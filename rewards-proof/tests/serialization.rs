@@ -0,0 +1,99 @@
+//! Tests of the serde encoding of proofs, policies and parameters
+
+#![cfg(feature = "serde")]
+
+mod common;
+
+use common::{prove, setup, verify, INCENTIVE_CATALOG_SIZE, STATE, WEIGHTS};
+use rewards_proof::api::RewardsProof;
+use rewards_proof::policy::{IssuerSigningKey, Policy, SignedPolicy};
+use rewards_proof::predicate::TierTable;
+use rewards_proof::sparse::SparseState;
+use rewards_proof::wire::{SetupParameters, WireFormat};
+use serde_json::json;
+
+#[test]
+fn rewards_proof_round_trip() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let wire = WireFormat::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+
+    let encoded = serde_json::to_string(&proof).unwrap();
+    let decoded: RewardsProof = serde_json::from_str(&encoded).unwrap();
+    assert_eq!(wire.encode(&decoded), wire.encode(&proof));
+    assert!(decoded.predicate_proof.is_none());
+    assert!(verify(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &decoded,
+        &WEIGHTS
+    ));
+}
+
+#[test]
+fn policy_round_trip() {
+    let signing_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let policy = Policy::new(1, 2, WEIGHTS.to_vec(), 1_700_000_000, 1_800_000_000).unwrap();
+    let signed_policy = signing_key.sign(&policy);
+
+    let encoded = serde_json::to_string(&signed_policy).unwrap();
+    let decoded: SignedPolicy = serde_json::from_str(&encoded).unwrap();
+    assert_eq!(decoded, signed_policy);
+    assert!(decoded.verify_signature(&signing_key.public_key()));
+}
+
+#[test]
+fn parameters_round_trip() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let parameters =
+        SetupParameters::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let encoded = serde_json::to_string(&parameters).unwrap();
+    let decoded: SetupParameters = serde_json::from_str(&encoded).unwrap();
+    assert_eq!(decoded, parameters);
+    assert!(decoded.setup().is_some());
+
+    let table = TierTable::new(vec![3, 10], vec![0, 50, 100]).unwrap();
+    let encoded = serde_json::to_string(&table).unwrap();
+    let decoded: TierTable = serde_json::from_str(&encoded).unwrap();
+    assert_eq!(decoded.thresholds(), table.thresholds());
+    assert_eq!(decoded.payouts(), table.payouts());
+
+    let state = SparseState::from_entries(INCENTIVE_CATALOG_SIZE, vec![(0, 1), (2, 3)]).unwrap();
+    let encoded = serde_json::to_string(&state).unwrap();
+    let decoded: SparseState = serde_json::from_str(&encoded).unwrap();
+    assert_eq!(decoded, state);
+}
+
+#[test]
+fn reject_invalid_encoding() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+    let encoded = serde_json::to_value(&proof).unwrap();
+
+    // invalid hex and a truncated range proof
+    for range_proof in ["zz", "00"].iter() {
+        let mut invalid = encoded.clone();
+        invalid["range_proof"] = json!(range_proof);
+        assert!(serde_json::from_value::<RewardsProof>(invalid).is_err());
+    }
+
+    // a signature of the wrong length
+    let signing_key = IssuerSigningKey::generate(&mut rand::thread_rng());
+    let policy = Policy::new(1, 2, WEIGHTS.to_vec(), 1_700_000_000, 1_800_000_000).unwrap();
+    let mut invalid = serde_json::to_value(&signing_key.sign(&policy)).unwrap();
+    invalid["signature"] = json!(hex::encode(&[0u8; 63][..]));
+    assert!(serde_json::from_value::<SignedPolicy>(invalid).is_err());
+
+    // a fingerprint of the wrong length
+    let invalid =
+        json!({ "incentive_catalog_size": 4, "fingerprint": hex::encode(&[0u8; 31][..]) });
+    assert!(serde_json::from_value::<SetupParameters>(invalid).is_err());
+
+    // a tier table without a payout for the highest tier
+    let invalid = json!({ "thresholds": [3, 10], "payouts": [0, 50] });
+    assert!(serde_json::from_value::<TierTable>(invalid).is_err());
+
+    // a counter outside of the catalog
+    let invalid = json!({ "incentive_catalog_size": 4, "entries": [[4, 1]] });
+    assert!(serde_json::from_value::<SparseState>(invalid).is_err());
+}
@@ -0,0 +1,105 @@
+//! Tests of rewards proofs for sparse states
+
+mod common;
+
+use common::{INCENTIVE_CATALOG_SIZE, STATE, WEIGHTS};
+use curve25519_dalek::scalar::Scalar;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rewards_proof::api::{
+    reward, rewards_proof_generation_aggregated_with_rng, rewards_proof_generation_sparse,
+    rewards_proof_generation_sparse_with_rng, to_scalars,
+};
+use rewards_proof::sparse::SparseState;
+use rewards_proof::wire::WireFormat;
+
+/// Sparse state of `STATE`
+fn sparse_state() -> SparseState {
+    SparseState::from_entries(INCENTIVE_CATALOG_SIZE, vec![(0, 1), (2, 3), (3, 2)]).unwrap()
+}
+
+#[test]
+fn sparse_equals_dense() {
+    let (pedersen_gens, bulletproof_gens) = common::setup();
+    let wire = WireFormat::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let state = sparse_state();
+    assert_eq!(state.to_dense(), to_scalars(&STATE));
+    let values = [reward(&STATE, &WEIGHTS).unwrap()];
+
+    // with the same randomness, both provers output the same proof
+    let sparse = rewards_proof_generation_sparse_with_rng(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &values,
+        &state,
+        to_scalars(&WEIGHTS),
+        None,
+        None,
+        &mut StdRng::seed_from_u64(34),
+    )
+    .unwrap();
+    let dense = rewards_proof_generation_aggregated_with_rng(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &values,
+        to_scalars(&STATE),
+        to_scalars(&WEIGHTS),
+        INCENTIVE_CATALOG_SIZE,
+        None,
+        None,
+        &mut StdRng::seed_from_u64(34),
+    )
+    .unwrap();
+    assert_eq!(wire.encode(&sparse), wire.encode(&dense));
+    assert!(common::verify(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &sparse,
+        &WEIGHTS
+    ));
+}
+
+#[test]
+fn sparse_inner_product() {
+    let state = sparse_state();
+    let public_value = to_scalars(&WEIGHTS);
+    let dense: Scalar = state
+        .to_dense()
+        .iter()
+        .zip(public_value.iter())
+        .map(|(a, b)| a * b)
+        .sum();
+    assert_eq!(state.inner_product(&public_value), Some(dense));
+    assert_eq!(
+        state.inner_product(&public_value),
+        Some(Scalar::from(reward(&STATE, &WEIGHTS).unwrap()))
+    );
+    assert_eq!(state.inner_product(&public_value[1..]), None);
+}
+
+#[test]
+fn reject_invalid_sparse_state() {
+    let (pedersen_gens, bulletproof_gens) = common::setup();
+    let state = sparse_state();
+
+    // a position outside of the catalog
+    assert!(SparseState::from_entries(INCENTIVE_CATALOG_SIZE, vec![(0, 1), (4, 1)]).is_none());
+    let mut other = SparseState::new(INCENTIVE_CATALOG_SIZE);
+    assert!(!other.set(INCENTIVE_CATALOG_SIZE as usize, 1));
+
+    // zero counters are not stored
+    assert!(other.set(1, 0));
+    assert_eq!(other.non_zero_entries(), 0);
+
+    // a policy vector of a different size
+    assert!(rewards_proof_generation_sparse(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &[reward(&STATE, &WEIGHTS).unwrap()],
+        &state,
+        to_scalars(&WEIGHTS[1..]),
+        None,
+        None,
+    )
+    .is_none());
+}
@@ -0,0 +1,113 @@
+//! Tests of the verification of proof streams
+
+mod common;
+
+use common::{prove, setup, INCENTIVE_CATALOG_SIZE, STATE, WEIGHTS};
+use rewards_proof::api::to_scalars;
+use rewards_proof::stream::{
+    read_rewards_proof, write_rewards_proof, StreamSummary, StreamingVerifier,
+};
+use rewards_proof::verifier::PreparedVerifier;
+use std::io;
+
+/// Writes a stream of a valid proof, a proof for another policy vector and
+/// another valid proof, returns the stream and the offsets of the records
+fn stream() -> (Vec<u8>, Vec<usize>) {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let proofs = [
+        prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS),
+        prove(&pedersen_gens, &bulletproof_gens, &STATE, &[2, 5, 1, 1]),
+        prove(&pedersen_gens, &bulletproof_gens, &[0, 1, 0, 0], &WEIGHTS),
+    ];
+    let mut bytes = Vec::new();
+    let mut offsets = Vec::new();
+    for proof in proofs.iter() {
+        offsets.push(bytes.len());
+        write_rewards_proof(&mut bytes, proof).unwrap();
+    }
+    (bytes, offsets)
+}
+
+fn streaming_verifier(chunk_size: usize) -> Option<StreamingVerifier> {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    StreamingVerifier::new(verifier, chunk_size)
+}
+
+#[test]
+fn verify_stream() {
+    let (bytes, _) = stream();
+    let verifier = streaming_verifier(2).unwrap();
+    let mut out = Vec::new();
+    let summary = verifier
+        .verify(&bytes[..], &mut out, &to_scalars(&WEIGHTS))
+        .unwrap();
+    assert_eq!(
+        summary,
+        StreamSummary {
+            valid: 2,
+            invalid: 1
+        }
+    );
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "0 valid\n1 invalid\n2 valid\n"
+    );
+    assert!(streaming_verifier(0).is_none());
+}
+
+#[test]
+fn corrupted_record_is_invalid() {
+    let (mut bytes, offsets) = stream();
+    // the length of the first section exceeds the record
+    bytes[offsets[0] + 4..offsets[0] + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let mut reader = &bytes[..];
+    assert!(read_rewards_proof(&mut reader).unwrap().unwrap().is_none());
+    assert!(read_rewards_proof(&mut reader).unwrap().unwrap().is_some());
+
+    let mut out = Vec::new();
+    let summary = streaming_verifier(2)
+        .unwrap()
+        .verify(&bytes[..], &mut out, &to_scalars(&WEIGHTS))
+        .unwrap();
+    assert_eq!(
+        summary,
+        StreamSummary {
+            valid: 1,
+            invalid: 2
+        }
+    );
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "0 invalid\n1 invalid\n2 valid\n"
+    );
+}
+
+#[test]
+fn reject_truncated_record() {
+    let (bytes, offsets) = stream();
+    let verifier = streaming_verifier(2).unwrap();
+
+    // the stream ends within the last record and within a record length
+    for truncated in [&bytes[..bytes.len() - 1], &bytes[..offsets[2] + 2]].iter() {
+        let mut reader = *truncated;
+        assert!(read_rewards_proof(&mut reader).unwrap().is_some());
+        assert!(read_rewards_proof(&mut reader).unwrap().is_some());
+        assert_eq!(
+            read_rewards_proof(&mut reader).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        let error = verifier
+            .verify(*truncated, io::sink(), &to_scalars(&WEIGHTS))
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    // a policy vector of a different size
+    let error = verifier
+        .verify(&bytes[..], io::sink(), &to_scalars(&WEIGHTS[1..]))
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
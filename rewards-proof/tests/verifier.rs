@@ -0,0 +1,232 @@
+//! Tests of the prepared verifier against the sequential verification
+
+mod common;
+
+use common::{prove, setup, verify, INCENTIVE_CATALOG_SIZE, STATE, WEIGHTS};
+use rewards_proof::api::{
+    rewards_proof_setup, rewards_proof_verification_batch, to_scalars, RewardsProof,
+};
+use rewards_proof::verifier::PreparedVerifier;
+
+/// Policy vectors the proof of `STATE` and `WEIGHTS` is checked against, only
+/// the first one is valid
+const POLICIES: [[u64; 4]; 3] = [WEIGHTS, [2, 5, 1, 1], [0, 0, 0, 0]];
+
+#[test]
+fn prepared_matches_sequential() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+
+    for (i, weights) in POLICIES.iter().enumerate() {
+        let public_value = to_scalars(weights);
+        let expected = verify(&pedersen_gens, &bulletproof_gens, &proof, weights);
+        assert_eq!(expected, i == 0);
+        assert_eq!(verifier.verify(&proof, &public_value, None, None), expected);
+
+        let policy = verifier.prepare_policy(&public_value).unwrap();
+        assert_eq!(
+            verifier.verify_with_policy(&proof, &policy, None, None),
+            expected
+        );
+    }
+}
+
+#[test]
+fn prepared_rejects_other_catalog() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+
+    // a policy vector of a different size
+    assert!(verifier
+        .prepare_policy(&to_scalars(&WEIGHTS[1..]))
+        .is_none());
+    assert!(!verifier.verify(&proof, &to_scalars(&WEIGHTS[1..]), None, None));
+    assert_eq!(
+        verifier.verify_batch(&[proof.clone(), proof], &to_scalars(&WEIGHTS[1..])),
+        vec![false, false]
+    );
+}
+
+/// Returns copies of the proof with `G` and with `F` replaced
+fn substitute_generators(proof: &RewardsProof) -> Vec<RewardsProof> {
+    let mut reversed_g = proof.clone();
+    reversed_g.linear_proof_commitments.0.reverse();
+    let mut f_as_b = proof.clone();
+    f_as_b.linear_proof_commitments.1 = f_as_b.linear_proof_commitments.2;
+    vec![reversed_g, f_as_b]
+}
+
+#[test]
+fn reject_substituted_generators() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+    let public_value = to_scalars(&WEIGHTS);
+
+    for forged in substitute_generators(&proof) {
+        assert!(!verify(
+            &pedersen_gens,
+            &bulletproof_gens,
+            &forged,
+            &WEIGHTS
+        ));
+        assert!(!verifier.verify(&forged, &public_value, None, None));
+        let policy = verifier.prepare_policy(&public_value).unwrap();
+        assert!(!verifier.verify_with_policy(&forged, &policy, None, None));
+        assert_eq!(
+            verifier.verify_batch(&[proof.clone(), forged], &public_value),
+            vec![true, false]
+        );
+    }
+}
+
+#[test]
+fn batch_matches_sequential() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+    let other = prove(&pedersen_gens, &bulletproof_gens, &[0, 1, 0, 0], &WEIGHTS);
+    let invalid = prove(&pedersen_gens, &bulletproof_gens, &STATE, &[2, 5, 1, 1]);
+    let mut proofs = vec![proof, invalid, other];
+    proofs.extend(substitute_generators(&proofs[0]));
+
+    let sequential: Vec<bool> = proofs
+        .iter()
+        .map(|proof| verify(&pedersen_gens, &bulletproof_gens, proof, &WEIGHTS))
+        .collect();
+    assert_eq!(sequential, vec![true, false, true, false, false]);
+    assert_eq!(
+        rewards_proof_verification_batch(&verifier, &proofs, &to_scalars(&WEIGHTS)),
+        sequential
+    );
+}
+
+#[test]
+fn prepared_rejects_other_linear_proof() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+    let other = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+    let public_value = to_scalars(&WEIGHTS);
+
+    // the linear proof of another commitment
+    let mut spliced = proof.clone();
+    spliced.linear_proof = other.linear_proof.clone();
+    assert!(!verify(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &spliced,
+        &WEIGHTS
+    ));
+    assert!(!verifier.verify(&spliced, &public_value, None, None));
+    assert!(verifier.verify(&other, &public_value, None, None));
+}
+
+#[test]
+fn prepared_policy_matches_unprepared() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let proofs = vec![
+        prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS),
+        prove(
+            &pedersen_gens,
+            &bulletproof_gens,
+            &[0, 1, 0, 0],
+            &[2, 5, 1, 1],
+        ),
+    ];
+
+    for weights in POLICIES.iter() {
+        let public_value = to_scalars(weights);
+        let policy = verifier.prepare_policy(&public_value).unwrap();
+        assert_eq!(policy.public_value(), &public_value[..]);
+        let unprepared: Vec<bool> = proofs
+            .iter()
+            .map(|proof| verifier.verify(proof, &public_value, None, None))
+            .collect();
+        let prepared: Vec<bool> = proofs
+            .iter()
+            .map(|proof| verifier.verify_with_policy(proof, &policy, None, None))
+            .collect();
+        assert_eq!(prepared, unprepared);
+        let borrowed: Vec<&RewardsProof> = proofs.iter().collect();
+        assert_eq!(
+            verifier.verify_batch_with_policy(&borrowed, &policy),
+            unprepared
+        );
+        let decoded: Vec<Option<&RewardsProof>> = vec![Some(&proofs[0]), None, Some(&proofs[1])];
+        assert_eq!(
+            verifier.verify_decoded_batch_with_policy(&decoded, &policy),
+            vec![unprepared[0], false, unprepared[1]]
+        );
+    }
+}
+
+#[test]
+fn reject_policy_of_other_catalog() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+
+    // a policy prepared for a catalog of 8 incentives
+    let (other_pedersen_gens, other_bulletproof_gens) = rewards_proof_setup(8);
+    let other = PreparedVerifier::new(&other_pedersen_gens, &other_bulletproof_gens, 8);
+    let weights = [2, 5, 1, 0, 0, 0, 0, 0];
+    let policy = other.prepare_policy(&to_scalars(&weights)).unwrap();
+    assert!(!verifier.verify_with_policy(&proof, &policy, None, None));
+    assert_eq!(
+        verifier.verify_batch_with_policy(&[&proof], &policy),
+        vec![false]
+    );
+}
+
+/// Run with and without the `parallel` feature, the batch verification
+/// returns the same result for every proof as the verification one after
+/// another
+#[test]
+fn parallel_batch_matches_sequential() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let verifier = PreparedVerifier::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let policy = verifier.prepare_policy(&to_scalars(&WEIGHTS)).unwrap();
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+    let mut spliced = proof.clone();
+    spliced.linear_proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS).linear_proof;
+    let mut proofs = vec![
+        proof,
+        prove(&pedersen_gens, &bulletproof_gens, &STATE, &[2, 5, 1, 1]),
+        prove(&pedersen_gens, &bulletproof_gens, &[0, 1, 0, 0], &WEIGHTS),
+        spliced,
+        prove(&pedersen_gens, &bulletproof_gens, &[3, 3, 3, 3], &WEIGHTS),
+    ];
+    proofs.extend(substitute_generators(&proofs[0]));
+
+    let sequential: Vec<bool> = proofs
+        .iter()
+        .map(|proof| verifier.verify_with_policy(proof, &policy, None, None))
+        .collect();
+    assert_eq!(
+        sequential,
+        vec![true, false, true, false, true, false, false]
+    );
+    let borrowed: Vec<&RewardsProof> = proofs.iter().collect();
+    assert_eq!(
+        verifier.verify_batch_with_policy(&borrowed, &policy),
+        sequential
+    );
+
+    // proofs that could not be decoded in between
+    let decoded: Vec<Option<&RewardsProof>> = proofs
+        .iter()
+        .flat_map(|proof| vec![Some(proof), None])
+        .collect();
+    let expected: Vec<bool> = sequential
+        .iter()
+        .flat_map(|valid| vec![*valid, false])
+        .collect();
+    assert_eq!(
+        verifier.verify_decoded_batch_with_policy(&decoded, &policy),
+        expected
+    );
+}
@@ -0,0 +1,93 @@
+//! Tests of the canonical wire format
+
+mod common;
+
+use common::{prove, setup, verify, INCENTIVE_CATALOG_SIZE, STATE, WEIGHTS};
+use curve25519_dalek::constants::BASEPOINT_ORDER;
+use rewards_proof::api::rewards_proof_setup;
+use rewards_proof::wire::WireFormat;
+
+/// Offset of the range proof, after the header and the section length
+const RANGE_PROOF_OFFSET: usize = 38 + 4;
+/// Offset of `t_x` in the range proof, after `A`, `S`, `T_1` and `T_2`
+const T_X_OFFSET: usize = RANGE_PROOF_OFFSET + 4 * 32;
+
+/// Encodes a proof of `STATE` and `WEIGHTS`
+fn encoded() -> (WireFormat, Vec<u8>) {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let wire = WireFormat::new(&pedersen_gens, &bulletproof_gens, INCENTIVE_CATALOG_SIZE);
+    let proof = prove(&pedersen_gens, &bulletproof_gens, &STATE, &WEIGHTS);
+    let bytes = wire.encode(&proof).unwrap();
+    (wire, bytes)
+}
+
+#[test]
+fn decode_round_trip() {
+    let (pedersen_gens, bulletproof_gens) = setup();
+    let (wire, bytes) = encoded();
+    let decoded = wire.decode(&bytes).unwrap();
+    assert_eq!(wire.encode(&decoded).unwrap(), bytes);
+    assert!(verify(
+        &pedersen_gens,
+        &bulletproof_gens,
+        &decoded,
+        &WEIGHTS
+    ));
+}
+
+#[test]
+fn reject_other_parameters() {
+    let (wire, bytes) = encoded();
+
+    // trailing bytes
+    let mut invalid = bytes.clone();
+    invalid.push(0);
+    assert!(wire.decode(&invalid).is_none());
+
+    // another version and unknown flags
+    for (offset, value) in [(4, 2), (5, 0x02)].iter() {
+        let mut invalid = bytes.clone();
+        invalid[*offset] = *value;
+        assert!(wire.decode(&invalid).is_none());
+    }
+
+    // a different fingerprint
+    let (pedersen_gens, bulletproof_gens) = rewards_proof_setup(8);
+    let other = WireFormat::new(&pedersen_gens, &bulletproof_gens, 8);
+    assert_ne!(other.fingerprint(), wire.fingerprint());
+    assert!(other.decode(&bytes).is_none());
+    assert!(other.encode(&wire.decode(&bytes).unwrap()).is_none());
+}
+
+#[test]
+fn reject_non_canonical_point() {
+    let (wire, bytes) = encoded();
+
+    // `A` of the range proof
+    let mut invalid = bytes.clone();
+    invalid[RANGE_PROOF_OFFSET..RANGE_PROOF_OFFSET + 32].copy_from_slice(&[0xff; 32]);
+    assert!(wire.decode(&invalid).is_none());
+}
+
+#[test]
+fn reject_non_canonical_scalar() {
+    let (wire, bytes) = encoded();
+
+    // `t_x + l` encodes the same scalar as `t_x`, but not canonically
+    let mut invalid = bytes.clone();
+    let mut carry = 0u16;
+    for (byte, l) in invalid[T_X_OFFSET..T_X_OFFSET + 32]
+        .iter_mut()
+        .zip(BASEPOINT_ORDER.to_bytes().iter())
+    {
+        let sum = *byte as u16 + *l as u16 + carry;
+        *byte = sum as u8;
+        carry = sum >> 8;
+    }
+    assert_eq!(carry, 0);
+    assert!(wire.decode(&invalid).is_none());
+
+    let mut invalid = bytes;
+    invalid[T_X_OFFSET..T_X_OFFSET + 32].copy_from_slice(&[0xff; 32]);
+    assert!(wire.decode(&invalid).is_none());
+}